@@ -0,0 +1,480 @@
+// /src/dom/component.rs
+
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::rc::Rc;
+
+use slotmap::SecondaryMap;
+
+use crate::dom::dom::{Dom, NodeContent, NodeId};
+use crate::dom::domapi::DomError;
+use crate::dom::element::Element;
+use crate::dom::signal::{ReactiveState, Signal};
+use crate::dom::styleengine::Style;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ComponentId(usize);
+
+/// A piece of UI with state of its own. The component value is its props: when a parent renders
+/// it again with equal props, it is left alone.
+pub trait Component: PartialEq + 'static {
+    type State: 'static;
+
+    /// Creates the local state once, when the component is mounted. Signals created through
+    /// `scope` and read in `render` make it render again when they are set.
+    fn init(&self, scope: &Scope) -> Self::State;
+
+    fn render(&self, state: &Self::State) -> View;
+}
+
+/// Lets components create signals tied to the runtime that renders them.
+#[derive(Clone)]
+pub struct Scope {
+    state: Rc<ReactiveState>,
+}
+
+impl Scope {
+    pub fn signal<T>(&self, value: T) -> Signal<T> {
+        Signal::new(self.state.clone(), value)
+    }
+}
+
+trait AnyComponent {
+    fn component_type(&self) -> TypeId;
+    fn init(&self, scope: &Scope) -> Box<dyn Any>;
+    fn render(&self, state: &dyn Any) -> View;
+    fn same_props(&self, other: &dyn AnyComponent) -> bool;
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<C: Component> AnyComponent for C {
+    fn component_type(&self) -> TypeId {
+        TypeId::of::<C>()
+    }
+
+    fn init(&self, scope: &Scope) -> Box<dyn Any> {
+        Box::new(Component::init(self, scope))
+    }
+
+    fn render(&self, state: &dyn Any) -> View {
+        let state = state.downcast_ref::<C::State>().expect("component state has the type its component created");
+        Component::render(self, state)
+    }
+
+    fn same_props(&self, other: &dyn AnyComponent) -> bool {
+        other.as_any().downcast_ref::<C>().is_some_and(|other| other == self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// What a component renders: elements with their inline style and children, text, and other
+/// components. Keys identify children across renders, so reordered children keep their nodes
+/// and component state instead of being rebuilt.
+#[derive(Clone)]
+pub struct View {
+    key: Option<String>,
+    kind: ViewKind,
+}
+
+#[derive(Clone)]
+enum ViewKind {
+    Element { element: Element, style: Box<Style>, children: Vec<View> },
+    Text(String),
+    Component(Rc<dyn AnyComponent>),
+}
+
+impl View {
+    pub fn element(element: Element) -> Self {
+        Self {
+            key: None,
+            kind: ViewKind::Element {
+                element,
+                style: Box::default(),
+                children: Vec::new(),
+            },
+        }
+    }
+
+    pub fn text(content: impl Into<String>) -> Self {
+        Self { key: None, kind: ViewKind::Text(content.into()) }
+    }
+
+    pub fn component(component: impl Component) -> Self {
+        Self {
+            key: None,
+            kind: ViewKind::Component(Rc::new(component)),
+        }
+    }
+
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Sets the inline style of an element view; other views have none.
+    pub fn style(mut self, style: Style) -> Self {
+        if let ViewKind::Element { style: current, .. } = &mut self.kind {
+            **current = style;
+        }
+        self
+    }
+
+    /// Adds a child to an element view; other views can't have children.
+    pub fn child(mut self, child: View) -> Self {
+        if let ViewKind::Element { children, .. } = &mut self.kind {
+            children.push(child);
+        }
+        self
+    }
+
+    pub fn children(mut self, new_children: impl IntoIterator<Item = View>) -> Self {
+        if let ViewKind::Element { children, .. } = &mut self.kind {
+            children.extend(new_children);
+        }
+        self
+    }
+}
+
+impl fmt::Debug for View {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ViewKind::Element { element, children, .. } => f.debug_struct("Element").field("key", &self.key).field("name", &element.name).field("children", children).finish(),
+            ViewKind::Text(content) => f.debug_struct("Text").field("key", &self.key).field("content", content).finish(),
+            ViewKind::Component(_) => f.debug_struct("Component").field("key", &self.key).finish(),
+        }
+    }
+}
+
+struct Instance {
+    component: Rc<dyn AnyComponent>,
+    state: Box<dyn Any>,
+    root: NodeId,
+    /// The component whose render returned this one directly, so both share a root node.
+    outer: Option<ComponentId>,
+    /// The component this one's render returned directly.
+    inner: Option<ComponentId>,
+}
+
+/// A node in the tree and the outermost component rendered at it, if any.
+#[derive(Clone, Copy)]
+struct Slot {
+    node: NodeId,
+    component: Option<ComponentId>,
+}
+
+/// Mounts components into a `Dom` and keeps them up to date. Call `update` once per frame,
+/// before `compute_styles`, to re-render the components whose signals changed.
+pub struct ComponentRuntime {
+    reactive: Rc<ReactiveState>,
+    instances: HashMap<ComponentId, Instance>,
+    next_id: usize,
+    /// The outermost component rendered at each node it created
+    owners: SecondaryMap<slotmap::DefaultKey, ComponentId>,
+    keys: SecondaryMap<slotmap::DefaultKey, String>,
+}
+
+impl Default for ComponentRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ComponentRuntime {
+    pub fn new() -> Self {
+        Self {
+            reactive: Rc::new(ReactiveState::default()),
+            instances: HashMap::new(),
+            next_id: 0,
+            owners: SecondaryMap::new(),
+            keys: SecondaryMap::new(),
+        }
+    }
+
+    /// A signal that isn't local to any component, for state shared between them.
+    pub fn signal<T>(&self, value: T) -> Signal<T> {
+        Signal::new(self.reactive.clone(), value)
+    }
+
+    /// Renders a component at the end of `parent_id`'s children. Nothing is rendered when `parent_id` can't have children.
+    pub fn mount(&mut self, dom: &mut Dom, parent_id: NodeId, component: impl Component) -> Result<ComponentId, DomError> {
+        dom.check_parent(parent_id)?;
+        let slot = self.reconcile(dom, View::component(component), None);
+        dom.append_child(parent_id, slot.node).expect("just checked");
+        let component_id = slot.component.expect("a component view mounts a component");
+        self.owners.insert(slot.node.into(), component_id);
+        Ok(component_id)
+    }
+
+    /// Removes a mounted component's nodes from the tree, along with every component rendered into them.
+    pub fn unmount(&mut self, dom: &mut Dom, component_id: ComponentId) {
+        if let Some(root) = self.instances.get(&component_id).map(|instance| instance.root) {
+            self.unmount_subtree(dom, root);
+            dom.remove_node(root);
+        }
+    }
+
+    /// The node a component currently renders to.
+    pub fn root_node(&self, component_id: ComponentId) -> Option<NodeId> {
+        self.instances.get(&component_id).map(|instance| instance.root)
+    }
+
+    /// Re-renders the components that read a signal which has since been set, outer components
+    /// first so inner ones they re-render with new props aren't rendered twice. Returns whether
+    /// anything was rendered.
+    pub fn update(&mut self, dom: &mut Dom) -> bool {
+        let mut rendered = false;
+        loop {
+            let stale: Vec<ComponentId> = self.reactive.stale.borrow().iter().copied().collect();
+            let next = stale.into_iter().filter(|id| self.instances.contains_key(id)).min_by_key(|&id| self.nesting(dom, id));
+            let Some(component_id) = next else {
+                self.reactive.stale.borrow_mut().clear();
+                return rendered;
+            };
+            self.rerender(dom, component_id);
+            rendered = true;
+        }
+    }
+
+    /// How deep a component sits: the depth of its node, then how many components render it directly.
+    fn nesting(&self, dom: &Dom, component_id: ComponentId) -> (usize, usize) {
+        let instance = &self.instances[&component_id];
+        let outers = std::iter::successors(instance.outer, |id| self.instances.get(id).and_then(|instance| instance.outer)).count();
+        (dom.ancestors(instance.root).count(), outers)
+    }
+
+    fn rerender(&mut self, dom: &mut Dom, component_id: ComponentId) {
+        let old_root = self.instances[&component_id].root;
+        let new_root = self.render_into(dom, component_id, Some(old_root));
+        if new_root == old_root {
+            return;
+        }
+
+        // The render replaced the root node, so every component sharing it moves to the new one
+        let mut current = self.instances[&component_id].outer;
+        while let Some(outer_id) = current {
+            let outer = self.instances.get_mut(&outer_id).expect("outer components outlive their inner ones");
+            outer.root = new_root;
+            current = outer.outer;
+        }
+        if let Some(owner) = self.owners.remove(old_root.into()) {
+            self.owners.insert(new_root.into(), owner);
+        }
+        if let Some(key) = self.keys.remove(old_root.into()) {
+            self.keys.insert(new_root.into(), key);
+        }
+        if let Some(parent_id) = dom.parent(old_root) {
+            // The new root is a fresh node and the old one is still the parent's child
+            let replaced = dom.replace_child(parent_id, new_root, old_root);
+            debug_assert!(replaced.is_ok(), "can't replace {:?} with {:?}: {:?}", old_root, new_root, replaced);
+        }
+        self.unmount_subtree(dom, old_root);
+        dom.remove_node(old_root);
+    }
+
+    /// Renders a component and reconciles the result with the node it rendered to before.
+    /// Returns the component's root node, which is a new one when the old one couldn't be reused.
+    fn render_into(&mut self, dom: &mut Dom, component_id: ComponentId, existing: Option<NodeId>) -> NodeId {
+        let instance = &self.instances[&component_id];
+        self.reactive.stale.borrow_mut().remove(&component_id);
+        let view = self.reactive.track(component_id, || instance.component.render(instance.state.as_ref()));
+
+        let inner = instance.inner;
+        let slot = self.reconcile(dom, view, existing.map(|node| Slot { node, component: inner }));
+        if let Some(inner_id) = slot.component {
+            self.instances.get_mut(&inner_id).expect("just rendered").outer = Some(component_id);
+        }
+        let instance = self.instances.get_mut(&component_id).expect("rendering doesn't unmount the component itself");
+        instance.inner = slot.component;
+        instance.root = slot.node;
+        slot.node
+    }
+
+    /// Makes the tree at `existing` match `view`, reusing its node when it is of the same kind.
+    /// Returns the node that now stands for the view; when that isn't `existing.node`, the caller
+    /// puts it in place of the old one.
+    fn reconcile(&mut self, dom: &mut Dom, view: View, existing: Option<Slot>) -> Slot {
+        let existing_component = existing.and_then(|slot| slot.component);
+        match view.kind {
+            ViewKind::Component(component) => {
+                if let Some(component_id) = existing_component.filter(|id| self.instances.get(id).is_some_and(|instance| instance.component.component_type() == component.component_type())) {
+                    let instance = self.instances.get_mut(&component_id).expect("just checked");
+                    let props_changed = !instance.component.same_props(component.as_ref());
+                    if props_changed {
+                        instance.component = component;
+                    }
+                    let node = match existing {
+                        Some(slot) if !props_changed && !self.reactive.stale.borrow().contains(&component_id) => slot.node,
+                        _ => self.render_into(dom, component_id, existing.map(|slot| slot.node)),
+                    };
+                    return Slot { node, component: Some(component_id) };
+                }
+
+                if let Some(old_id) = existing_component {
+                    self.unmount_chain(old_id);
+                }
+                let component_id = ComponentId(self.next_id);
+                self.next_id += 1;
+                let scope = Scope { state: self.reactive.clone() };
+                let state = component.init(&scope);
+                // The root is a placeholder until the first render creates or reuses one
+                let root = existing.map_or(dom.root, |slot| slot.node);
+                self.instances.insert(
+                    component_id,
+                    Instance {
+                        component,
+                        state,
+                        root,
+                        outer: None,
+                        inner: None,
+                    },
+                );
+                let node = self.render_into(dom, component_id, existing.map(|slot| slot.node));
+                Slot { node, component: Some(component_id) }
+            }
+            ViewKind::Element { element, style, children } => {
+                if let Some(old_id) = existing_component {
+                    self.unmount_chain(old_id);
+                }
+                let reusable = existing.map(|slot| slot.node).filter(|&node| dom.get_element(node).is_some_and(|current| current.name == element.name));
+                let node = match reusable {
+                    Some(node) => {
+                        sync_element(dom, node, &element);
+                        if dom.styles.get(node.into()) != Some(&*style) {
+                            dom.replace_style(node, *style);
+                        }
+                        node
+                    }
+                    None => {
+                        let node = dom.create_element(element);
+                        if *style != Style::default() {
+                            dom.replace_style(node, *style);
+                        }
+                        node
+                    }
+                };
+                self.reconcile_children(dom, node, children);
+                Slot { node, component: None }
+            }
+            ViewKind::Text(content) => {
+                if let Some(old_id) = existing_component {
+                    self.unmount_chain(old_id);
+                }
+                let reusable = existing.and_then(|slot| match dom.content.get(slot.node.into()) {
+                    Some(NodeContent::Text(text)) => Some((slot.node, text.content == content)),
+                    _ => None,
+                });
+                let node = match reusable {
+                    Some((node, true)) => node,
+                    Some((node, false)) => {
+                        dom.set_text_content(node, content);
+                        node
+                    }
+                    None => dom.create_text_node(content),
+                };
+                Slot { node, component: None }
+            }
+        }
+    }
+
+    /// Matches child views to the existing children by key, or in order for unkeyed ones, then
+    /// removes the children nothing matched and moves the rest into the new order.
+    fn reconcile_children(&mut self, dom: &mut Dom, parent_id: NodeId, views: Vec<View>) {
+        let old_children: Vec<NodeId> = dom.children(parent_id).cloned().unwrap_or_default();
+        let mut keyed: HashMap<String, NodeId> = old_children.iter().filter_map(|&node| self.keys.get(node.into()).map(|key| (key.clone(), node))).collect();
+        let mut unkeyed: VecDeque<NodeId> = old_children.iter().copied().filter(|&node| !self.keys.contains_key(node.into())).collect();
+
+        let mut new_children = Vec::with_capacity(views.len());
+        for view in views {
+            let matched = match &view.key {
+                Some(key) => keyed.remove(key),
+                None => unkeyed.pop_front(),
+            };
+            let key = view.key.clone();
+            let existing = matched.map(|node| Slot {
+                node,
+                component: self.owners.remove(node.into()),
+            });
+            let slot = self.reconcile(dom, view, existing);
+
+            let node_key: slotmap::DefaultKey = slot.node.into();
+            if let Some(matched) = matched.filter(|&node| node != slot.node) {
+                self.keys.remove(matched.into());
+            }
+            match key {
+                Some(key) => self.keys.insert(node_key, key),
+                None => self.keys.remove(node_key),
+            };
+            if let Some(component_id) = slot.component {
+                self.owners.insert(node_key, component_id);
+            }
+            new_children.push(slot.node);
+        }
+
+        let kept: HashSet<NodeId> = new_children.iter().copied().collect();
+        for node in old_children {
+            if !kept.contains(&node) {
+                self.unmount_subtree(dom, node);
+                dom.remove_node(node);
+            }
+        }
+        for (index, &node) in new_children.iter().enumerate() {
+            if dom.child_at(parent_id, index) != Some(node) {
+                // Every node before `index` is already in place, so it is in range
+                let moved = dom.move_node(node, parent_id, index);
+                debug_assert!(moved.is_ok(), "can't move {:?} into {:?}: {:?}", node, parent_id, moved);
+            }
+        }
+    }
+
+    /// Forgets the components rendered anywhere in a subtree that is about to be removed.
+    fn unmount_subtree(&mut self, dom: &Dom, node_id: NodeId) {
+        for node in std::iter::once(node_id).chain(dom.descendants(node_id)) {
+            if let Some(component_id) = self.owners.remove(node.into()) {
+                self.unmount_chain(component_id);
+            }
+            self.keys.remove(node.into());
+        }
+    }
+
+    /// Drops a component and the components it rendered directly, keeping their nodes.
+    fn unmount_chain(&mut self, component_id: ComponentId) {
+        let mut current = Some(component_id);
+        while let Some(id) = current {
+            self.reactive.stale.borrow_mut().remove(&id);
+            self.reactive.unsubscribe(id);
+            current = self.instances.remove(&id).and_then(|instance| instance.inner);
+        }
+    }
+}
+
+/// Brings an element's id, classes and attributes in line with `element`, touching only what differs.
+fn sync_element(dom: &mut Dom, node_id: NodeId, element: &Element) {
+    let Some(current) = dom.get_element(node_id).cloned() else {
+        return;
+    };
+    if current.id != element.id {
+        match &element.id {
+            Some(id) => dom.set_id(node_id, id),
+            None => {
+                dom.remove_attribute(node_id, "id");
+            }
+        }
+    }
+    if current.class_list != element.class_list {
+        dom.set_attribute(node_id, "class", &element.class_list.to_string());
+    }
+    for (name, value) in &element.attributes {
+        if current.attributes.get(name) != Some(value) {
+            dom.set_attribute(node_id, name, value);
+        }
+    }
+    for name in current.attributes.keys() {
+        if !element.attributes.contains_key(name) {
+            dom.remove_attribute(node_id, name);
+        }
+    }
+}
@@ -0,0 +1,1459 @@
+// /src/dom/cssparser.rs
+
+use std::fmt;
+
+use crate::dom::grid::{AutoRepeat, GridAutoFlow, GridLine, GridPlacement, GridTemplateAreas, TrackBreadth, TrackList, TrackSize};
+use crate::dom::length::{CalcLength, Length};
+use crate::dom::selector::{parse_selector_list, Selector};
+use crate::dom::styleengine::{
+    AlignContent, AlignItems, AlignSelf, BorderSide, BorderStyle, BoxModelValues, BoxSizing, Color, CssWideKeyword, Display, FlexDirection, FlexWrap, Float, Font, FontSlant, FontWeight, JustifyContent, LineHeight, Overflow, Position, PositionOffsets, PropertyId, Size, Style, TextAlign, VerticalAlign, WhiteSpace,
+};
+
+/// A parse failure with the 1-based line and column it occurred at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CssParseError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for CssParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for CssParseError {}
+
+#[derive(Debug, Clone)]
+pub struct CssRule {
+    /// The selector as written, kept for diagnostics.
+    pub selector: String,
+    pub selectors: Vec<Selector>,
+    pub style: Style,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Stylesheet {
+    pub rules: Vec<CssRule>,
+}
+
+impl Stylesheet {
+    /// Parses a full stylesheet made of `selector { declarations }` rules.
+    pub fn parse(input: &str) -> Result<Self, CssParseError> {
+        let mut parser = Parser::new(input)?;
+        parser.parse_rules()
+    }
+}
+
+impl Style {
+    /// Parses a declaration block such as `padding: 20px; border-top: 5px solid blue`.
+    pub fn from_css(input: &str) -> Result<Self, CssParseError> {
+        let mut parser = Parser::new(input)?;
+        parser.parse_declarations(false)
+    }
+}
+
+// --- Tokenizer ---
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    Function(String),
+    AtKeyword(String),
+    Hash(String),
+    String(String),
+    Number(f32),
+    Percentage(f32),
+    Dimension(f32, String),
+    Whitespace,
+    Colon,
+    Semicolon,
+    Comma,
+    LeftBrace,
+    RightBrace,
+    LeftParen,
+    RightParen,
+    Delim(char),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    line: usize,
+    column: usize,
+    start: usize,
+    end: usize,
+}
+
+struct Tokenizer<'a> {
+    input: &'a str,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0, line: 1, column: 1 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn peek_at(&self, n: usize) -> Option<char> {
+        self.input[self.pos..].chars().nth(n)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn tokenize(mut self) -> Result<Vec<Token>, CssParseError> {
+        let mut tokens = Vec::new();
+
+        while let Some(c) = self.peek() {
+            let (line, column, start) = (self.line, self.column, self.pos);
+
+            if c == '/' && self.peek_at(1) == Some('*') {
+                self.skip_comment()?;
+                continue;
+            }
+
+            let kind = if c.is_whitespace() {
+                while self.peek().is_some_and(char::is_whitespace) {
+                    self.bump();
+                }
+                TokenKind::Whitespace
+            } else if self.starts_number() {
+                self.consume_numeric()
+            } else if self.starts_ident(0) {
+                let name = self.consume_name();
+                if self.peek() == Some('(') {
+                    self.bump();
+                    TokenKind::Function(name.to_ascii_lowercase())
+                } else {
+                    TokenKind::Ident(name)
+                }
+            } else {
+                self.bump();
+                match c {
+                    ':' => TokenKind::Colon,
+                    ';' => TokenKind::Semicolon,
+                    ',' => TokenKind::Comma,
+                    '{' => TokenKind::LeftBrace,
+                    '}' => TokenKind::RightBrace,
+                    '(' => TokenKind::LeftParen,
+                    ')' => TokenKind::RightParen,
+                    '#' => TokenKind::Hash(self.consume_name()),
+                    '@' => TokenKind::AtKeyword(self.consume_name()),
+                    '"' | '\'' => TokenKind::String(self.consume_string(c, line, column)?),
+                    other => TokenKind::Delim(other),
+                }
+            };
+
+            tokens.push(Token { kind, line, column, start, end: self.pos });
+        }
+
+        Ok(tokens)
+    }
+
+    fn skip_comment(&mut self) -> Result<(), CssParseError> {
+        let (line, column) = (self.line, self.column);
+        self.bump();
+        self.bump();
+        loop {
+            match self.bump() {
+                Some('*') if self.peek() == Some('/') => {
+                    self.bump();
+                    return Ok(());
+                }
+                Some(_) => {}
+                None => return Err(CssParseError { message: "unterminated comment".to_string(), line, column }),
+            }
+        }
+    }
+
+    fn is_name_char(c: char) -> bool {
+        c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+    }
+
+    fn is_name_start(c: char) -> bool {
+        c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+    }
+
+    fn starts_ident(&self, offset: usize) -> bool {
+        match self.peek_at(offset) {
+            Some('-') => self.peek_at(offset + 1).is_some_and(|c| Self::is_name_start(c) || c == '-'),
+            Some(c) => Self::is_name_start(c),
+            None => false,
+        }
+    }
+
+    fn starts_number(&self) -> bool {
+        let digit_at = |n: usize| self.peek_at(n).is_some_and(|c| c.is_ascii_digit());
+        match self.peek() {
+            Some('+') | Some('-') => digit_at(1) || (self.peek_at(1) == Some('.') && digit_at(2)),
+            Some('.') => digit_at(1),
+            Some(c) => c.is_ascii_digit(),
+            None => false,
+        }
+    }
+
+    fn consume_name(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(Self::is_name_char) {
+            self.bump();
+        }
+        self.input[start..self.pos].to_string()
+    }
+
+    fn consume_numeric(&mut self) -> TokenKind {
+        let start = self.pos;
+        if matches!(self.peek(), Some('+') | Some('-')) {
+            self.bump();
+        }
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+        }
+        if self.peek() == Some('.') && self.peek_at(1).is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.bump();
+            }
+        }
+        // Only treat `e` as an exponent when digits follow, so `1em` stays a dimension.
+        if matches!(self.peek(), Some('e') | Some('E')) {
+            let digit_after_sign = matches!(self.peek_at(1), Some('+') | Some('-')) && self.peek_at(2).is_some_and(|c| c.is_ascii_digit());
+            if self.peek_at(1).is_some_and(|c| c.is_ascii_digit()) || digit_after_sign {
+                self.bump();
+                if digit_after_sign {
+                    self.bump();
+                }
+                while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    self.bump();
+                }
+            }
+        }
+
+        let value: f32 = self.input[start..self.pos].parse().unwrap_or(0.0);
+
+        if self.peek() == Some('%') {
+            self.bump();
+            TokenKind::Percentage(value)
+        } else if self.starts_ident(0) {
+            TokenKind::Dimension(value, self.consume_name().to_ascii_lowercase())
+        } else {
+            TokenKind::Number(value)
+        }
+    }
+
+    fn consume_string(&mut self, quote: char, line: usize, column: usize) -> Result<String, CssParseError> {
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                Some(c) if c == quote => return Ok(value),
+                Some('\\') => {
+                    if let Some(escaped) = self.bump() {
+                        value.push(escaped);
+                    }
+                }
+                Some('\n') | None => return Err(CssParseError { message: "unterminated string".to_string(), line, column }),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+}
+
+// --- Parser ---
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Result<Self, CssParseError> {
+        let tokens = Tokenizer::new(source).tokenize()?;
+        Ok(Self { source, tokens, pos: 0 })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|t| t.kind == TokenKind::Whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn end_error(&self, message: impl Into<String>) -> CssParseError {
+        // Counted by hand since `lines()` would ignore a trailing newline
+        let line = self.source.matches('\n').count() + 1;
+        let column = self.source.rsplit('\n').next().map_or(0, |last| last.chars().count()) + 1;
+        CssParseError { message: message.into(), line, column }
+    }
+
+    fn parse_rules(&mut self) -> Result<Stylesheet, CssParseError> {
+        let mut sheet = Stylesheet::default();
+
+        loop {
+            self.skip_whitespace();
+            let Some(first) = self.peek().cloned() else { break };
+
+            if let TokenKind::AtKeyword(name) = &first.kind {
+                return Err(error_at(&first, format!("at-rule `@{}` is not supported", name)));
+            }
+
+            // The selector is everything up to the opening brace; it is kept as source text.
+            let mut last = first.clone();
+            loop {
+                match self.peek().cloned() {
+                    Some(Token { kind: TokenKind::LeftBrace, .. }) => {
+                        self.pos += 1;
+                        break;
+                    }
+                    Some(token @ Token { kind: TokenKind::RightBrace | TokenKind::Semicolon, .. }) => {
+                        return Err(error_at(&token, "expected `{` after selector"));
+                    }
+                    Some(token) => {
+                        last = token;
+                        self.pos += 1;
+                    }
+                    None => return Err(self.end_error("expected `{` after selector")),
+                }
+            }
+
+            let selector = self.source[first.start..last.end].trim().to_string();
+            if selector.is_empty() || first.kind == TokenKind::LeftBrace {
+                return Err(error_at(&first, "expected a selector"));
+            }
+
+            // Selector errors are reported relative to the selector text; shift them to the rule.
+            let selectors = parse_selector_list(&selector).map_err(|err| CssParseError {
+                column: if err.line == 1 { first.column + err.column - 1 } else { err.column },
+                line: first.line + err.line - 1,
+                message: err.message,
+            })?;
+
+            let style = self.parse_declarations(true)?;
+            sheet.rules.push(CssRule { selector, selectors, style, line: first.line, column: first.column });
+        }
+
+        Ok(sheet)
+    }
+
+    /// Parses `name: value;` pairs. Inside a rule block the closing brace ends the list.
+    fn parse_declarations(&mut self, in_block: bool) -> Result<Style, CssParseError> {
+        let mut style = Style::default();
+
+        loop {
+            while self.peek().is_some_and(|t| matches!(t.kind, TokenKind::Whitespace | TokenKind::Semicolon)) {
+                self.pos += 1;
+            }
+
+            let Some(token) = self.peek().cloned() else {
+                if in_block {
+                    return Err(self.end_error("unclosed block, expected `}`"));
+                }
+                break;
+            };
+
+            let name = match &token.kind {
+                TokenKind::RightBrace if in_block => {
+                    self.pos += 1;
+                    break;
+                }
+                TokenKind::Ident(name) => name.to_ascii_lowercase(),
+                _ => return Err(error_at(&token, "expected a property name")),
+            };
+            self.pos += 1;
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(Token { kind: TokenKind::Colon, .. }) => self.pos += 1,
+                Some(other) => return Err(error_at(other, format!("expected `:` after `{}`", name))),
+                None => return Err(self.end_error(format!("expected `:` after `{}`", name))),
+            }
+
+            let mut values = Vec::new();
+            let mut depth = 0;
+            while let Some(value) = self.peek() {
+                match value.kind {
+                    TokenKind::Semicolon | TokenKind::RightBrace if depth == 0 => break,
+                    TokenKind::Function(_) | TokenKind::LeftParen => depth += 1,
+                    TokenKind::RightParen => depth -= 1,
+                    _ => {}
+                }
+                if value.kind != TokenKind::Whitespace {
+                    values.push(value.clone());
+                }
+                self.pos += 1;
+            }
+
+            if values.is_empty() {
+                return Err(error_at(&token, format!("expected a value for `{}`", name)));
+            }
+            if let Some(bang) = values.iter().find(|t| t.kind == TokenKind::Delim('!')) {
+                return Err(error_at(bang, "`!important` is not supported"));
+            }
+
+            apply_declaration(&mut style, &name, &token, &values)?;
+        }
+
+        Ok(style)
+    }
+}
+
+fn error_at(token: &Token, message: impl Into<String>) -> CssParseError {
+    CssParseError { message: message.into(), line: token.line, column: token.column }
+}
+
+/// Cursor over the component values of a single declaration.
+struct Values<'a> {
+    property: &'a Token,
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl<'a> Values<'a> {
+    fn new(property: &'a Token, tokens: &'a [Token]) -> Self {
+        Self { property, tokens, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<&'a Token, CssParseError> {
+        let token = self.tokens.get(self.pos).ok_or_else(|| {
+            let last = self.tokens.last().unwrap_or(self.property);
+            error_at(last, "unexpected end of value")
+        })?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn expect_end(&self) -> Result<(), CssParseError> {
+        match self.peek() {
+            Some(token) => Err(error_at(token, "unexpected extra value")),
+            None => Ok(()),
+        }
+    }
+
+    fn keyword<T: Copy>(&mut self, table: &[(&str, T)]) -> Result<T, CssParseError> {
+        let token = self.next()?;
+        if let TokenKind::Ident(ident) = &token.kind {
+            if let Some((_, value)) = table.iter().find(|(name, _)| name.eq_ignore_ascii_case(ident)) {
+                return Ok(*value);
+            }
+        }
+        let expected: Vec<&str> = table.iter().map(|(name, _)| *name).collect();
+        Err(error_at(token, format!("expected one of: {}", expected.join(", "))))
+    }
+
+    fn number(&mut self) -> Result<f32, CssParseError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Number(value) => Ok(value),
+            _ => Err(error_at(token, "expected a number")),
+        }
+    }
+
+    fn length(&mut self) -> Result<Length, CssParseError> {
+        let token = self.peek().unwrap_or(self.property);
+        let length = self.length_percentage()?;
+        if length.has_percent() {
+            return Err(error_at(token, "percentages are not allowed here"));
+        }
+        Ok(length)
+    }
+
+    fn length_percentage(&mut self) -> Result<Length, CssParseError> {
+        let token = self.next()?;
+        match &token.kind {
+            TokenKind::Percentage(value) => Ok(Length::Percent(*value)),
+            TokenKind::Function(name) if name == "calc" => match self.calc_function(token)? {
+                CalcValue::Length(calc) => Ok(calc.simplify()),
+                CalcValue::Number(_) => Err(error_at(token, "calc() must produce a length here")),
+            },
+            _ => token_to_length(token),
+        }
+    }
+
+    fn size(&mut self) -> Result<Size, CssParseError> {
+        match self.peek() {
+            Some(Token { kind: TokenKind::Ident(ident), .. }) if ident.eq_ignore_ascii_case("auto") => {
+                self.pos += 1;
+                Ok(Size::Auto)
+            }
+            _ => self.length_percentage().map(Size::Length),
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, message: &str) -> Result<(), CssParseError> {
+        let token = self.next()?;
+        if token.kind == kind {
+            Ok(())
+        } else {
+            Err(error_at(token, message))
+        }
+    }
+
+    /// Consumes the next token if it is the identifier `name`.
+    fn skip_ident(&mut self, name: &str) -> bool {
+        match self.peek() {
+            Some(Token { kind: TokenKind::Ident(ident), .. }) if ident.eq_ignore_ascii_case(name) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Parses `none` or a list of track sizes. `repeat()` with a count is expanded in place.
+    fn track_list(&mut self) -> Result<TrackList, CssParseError> {
+        let mut list = TrackList::default();
+        if self.skip_ident("none") {
+            return Ok(list);
+        }
+
+        while let Some(token) = self.peek() {
+            match &token.kind {
+                TokenKind::Function(name) if name == "repeat" => {
+                    self.pos += 1;
+                    let count_token = self.next()?;
+                    let count = match &count_token.kind {
+                        TokenKind::Number(count) if count.fract() == 0.0 && *count >= 1.0 => Some(*count as usize),
+                        TokenKind::Ident(ident) if ident.eq_ignore_ascii_case("auto-fill") || ident.eq_ignore_ascii_case("auto-fit") => None,
+                        _ => return Err(error_at(count_token, "expected a positive integer, `auto-fill` or `auto-fit`")),
+                    };
+                    self.expect(TokenKind::Comma, "expected `,` after the repeat() count")?;
+
+                    let mut tracks = Vec::new();
+                    loop {
+                        match self.peek() {
+                            Some(Token { kind: TokenKind::RightParen, .. }) => {
+                                self.pos += 1;
+                                break;
+                            }
+                            Some(_) => tracks.push(self.track_size()?),
+                            None => return Err(error_at(token, "unterminated repeat()")),
+                        }
+                    }
+                    if tracks.is_empty() {
+                        return Err(error_at(token, "repeat() needs at least one track size"));
+                    }
+
+                    match count {
+                        Some(count) => {
+                            for _ in 0..count {
+                                list.tracks.extend_from_slice(&tracks);
+                            }
+                        }
+                        None if list.auto_repeat.is_some() => return Err(error_at(token, "only one automatic repeat() is allowed")),
+                        None => list.auto_repeat = Some(AutoRepeat { index: list.tracks.len(), tracks }),
+                    }
+                }
+                TokenKind::Delim('[') => return Err(error_at(token, "named grid lines are not supported")),
+                _ => list.tracks.push(self.track_size()?),
+            }
+        }
+        Ok(list)
+    }
+
+    fn track_size(&mut self) -> Result<TrackSize, CssParseError> {
+        match self.peek() {
+            Some(token @ Token { kind: TokenKind::Function(name), .. }) if name == "minmax" => {
+                self.pos += 1;
+                let min = self.track_breadth()?;
+                self.expect(TokenKind::Comma, "expected `,` in minmax()")?;
+                let max = self.track_breadth()?;
+                self.expect(TokenKind::RightParen, "expected `)` after minmax()")?;
+                if let TrackBreadth::Fr(_) = min {
+                    return Err(error_at(token, "the minimum of minmax() cannot be an `fr` value"));
+                }
+                Ok(TrackSize { min, max })
+            }
+            _ => self.track_breadth().map(TrackSize::new),
+        }
+    }
+
+    fn track_breadth(&mut self) -> Result<TrackBreadth, CssParseError> {
+        match self.peek() {
+            Some(token @ Token { kind: TokenKind::Dimension(value, unit), .. }) if unit == "fr" => {
+                self.pos += 1;
+                if *value < 0.0 {
+                    return Err(error_at(token, "`fr` values cannot be negative"));
+                }
+                Ok(TrackBreadth::Fr(*value))
+            }
+            Some(Token { kind: TokenKind::Ident(_), .. }) => self.keyword(&[("auto", TrackBreadth::Auto), ("min-content", TrackBreadth::MinContent), ("max-content", TrackBreadth::MaxContent)]),
+            _ => self.length_percentage().map(TrackBreadth::Length),
+        }
+    }
+
+    /// Parses one side of `grid-row`/`grid-column`: `auto`, a line number, `span <integer>` or an area name.
+    fn grid_line(&mut self) -> Result<GridLine, CssParseError> {
+        let token = self.next()?;
+        match &token.kind {
+            TokenKind::Ident(ident) if ident.eq_ignore_ascii_case("auto") => Ok(GridLine::Auto),
+            TokenKind::Ident(ident) if ident.eq_ignore_ascii_case("span") => {
+                let count = self.next()?;
+                match count.kind {
+                    TokenKind::Number(value) if value.fract() == 0.0 && value >= 1.0 => Ok(GridLine::Span(value as u32)),
+                    _ => Err(error_at(count, "expected a positive integer after `span`")),
+                }
+            }
+            TokenKind::Ident(name) => Ok(GridLine::Named(name.clone())),
+            TokenKind::Number(value) if value.fract() == 0.0 && *value != 0.0 => Ok(GridLine::Line(*value as i32)),
+            _ => Err(error_at(token, "expected `auto`, a non-zero line number, `span` or an area name")),
+        }
+    }
+
+    /// `start [/ end]`. A lone area name applies to both sides.
+    fn grid_placement(&mut self) -> Result<GridPlacement, CssParseError> {
+        let start = self.grid_line()?;
+        let end = if self.is_empty() {
+            match &start {
+                GridLine::Named(name) => GridLine::Named(name.clone()),
+                _ => GridLine::Auto,
+            }
+        } else {
+            self.expect(TokenKind::Delim('/'), "expected `/` between grid lines")?;
+            self.grid_line()?
+        };
+        Ok(GridPlacement { start: Some(start), end: Some(end) })
+    }
+
+    /// Parses the body of `calc(` up to and including its closing parenthesis.
+    fn calc_function(&mut self, function: &Token) -> Result<CalcValue, CssParseError> {
+        let value = self.calc_sum()?;
+        match self.next() {
+            Ok(Token { kind: TokenKind::RightParen, .. }) => Ok(value),
+            Ok(token) => Err(error_at(token, "expected an operator or `)` in calc()")),
+            Err(_) => Err(error_at(function, "unterminated calc()")),
+        }
+    }
+
+    fn calc_sum(&mut self) -> Result<CalcValue, CssParseError> {
+        let mut value = self.calc_product()?;
+        while let Some(token) = self.peek() {
+            let sign = match token.kind {
+                TokenKind::Delim('+') => 1.0,
+                TokenKind::Delim('-') => -1.0,
+                _ => break,
+            };
+            self.pos += 1;
+            value = match (value, self.calc_product()?) {
+                (CalcValue::Number(lhs), CalcValue::Number(rhs)) => CalcValue::Number(lhs + sign * rhs),
+                (CalcValue::Length(lhs), CalcValue::Length(rhs)) => CalcValue::Length(lhs.add(rhs.scale(sign))),
+                _ => return Err(error_at(token, "cannot add a number to a length")),
+            };
+        }
+        Ok(value)
+    }
+
+    fn calc_product(&mut self) -> Result<CalcValue, CssParseError> {
+        let mut value = self.calc_operand()?;
+        while let Some(token) = self.peek() {
+            let operator = match token.kind {
+                TokenKind::Delim(operator @ ('*' | '/')) => operator,
+                _ => break,
+            };
+            self.pos += 1;
+            value = match (operator, value, self.calc_operand()?) {
+                ('*', CalcValue::Number(lhs), CalcValue::Number(rhs)) => CalcValue::Number(lhs * rhs),
+                ('*', CalcValue::Length(length), CalcValue::Number(factor)) | ('*', CalcValue::Number(factor), CalcValue::Length(length)) => CalcValue::Length(length.scale(factor)),
+                ('/', _, CalcValue::Number(divisor)) if divisor == 0.0 => return Err(error_at(token, "division by zero in calc()")),
+                ('/', CalcValue::Number(lhs), CalcValue::Number(rhs)) => CalcValue::Number(lhs / rhs),
+                ('/', CalcValue::Length(length), CalcValue::Number(divisor)) => CalcValue::Length(length.scale(1.0 / divisor)),
+                ('*', ..) => return Err(error_at(token, "cannot multiply two lengths")),
+                _ => return Err(error_at(token, "can only divide by a number")),
+            };
+        }
+        Ok(value)
+    }
+
+    fn calc_operand(&mut self) -> Result<CalcValue, CssParseError> {
+        let token = self.next()?;
+        match &token.kind {
+            TokenKind::Number(value) => Ok(CalcValue::Number(*value)),
+            TokenKind::Percentage(value) => Ok(CalcValue::Length(Length::Percent(*value).to_calc())),
+            TokenKind::Dimension(..) => token_to_length(token).map(|length| CalcValue::Length(length.to_calc())),
+            TokenKind::LeftParen => self.calc_function(token),
+            TokenKind::Function(name) if name == "calc" => self.calc_function(token),
+            _ => Err(error_at(token, "expected a number, length or percentage in calc()")),
+        }
+    }
+
+    fn color(&mut self) -> Result<Color, CssParseError> {
+        let token = self.next()?;
+        match &token.kind {
+            TokenKind::Ident(name) => named_color(name).ok_or_else(|| error_at(token, format!("unknown color `{}`", name))),
+            TokenKind::Hash(hex) => hex_color(hex).ok_or_else(|| error_at(token, format!("invalid hex color `#{}`", hex))),
+            TokenKind::Function(name) if name == "rgb" || name == "rgba" => self.rgb_function(token),
+            _ => Err(error_at(token, "expected a color")),
+        }
+    }
+
+    fn rgb_function(&mut self, function: &Token) -> Result<Color, CssParseError> {
+        let mut channels = Vec::new();
+        loop {
+            let token = self.next()?;
+            match token.kind {
+                TokenKind::RightParen => break,
+                TokenKind::Comma | TokenKind::Delim('/') => {}
+                TokenKind::Number(value) => channels.push((value, false)),
+                TokenKind::Percentage(value) => channels.push((value, true)),
+                _ => return Err(error_at(token, "expected a number or percentage")),
+            }
+        }
+
+        let channel = |(value, percent): (f32, bool)| if percent { value / 100.0 } else { value / 255.0 };
+        let alpha = |(value, percent): (f32, bool)| if percent { value / 100.0 } else { value };
+        match channels.as_slice() {
+            [r, g, b] => Ok(Color::new(channel(*r), channel(*g), channel(*b), 1.0)),
+            [r, g, b, a] => Ok(Color::new(channel(*r), channel(*g), channel(*b), alpha(*a).clamp(0.0, 1.0))),
+            _ => Err(error_at(function, "rgb() expects 3 or 4 components")),
+        }
+    }
+
+    fn box_values(&mut self) -> Result<BoxModelValues, CssParseError> {
+        let mut values = Vec::new();
+        while !self.is_empty() {
+            values.push(self.length_percentage()?);
+        }
+        let [top, right, bottom, left] = expand_sides(&values).ok_or_else(|| error_at(self.property, "expected 1 to 4 lengths"))?;
+        Ok(BoxModelValues::default().top(top).right(right).bottom(bottom).left(left))
+    }
+
+    /// Parses `<width> || <style> || <color>` in any order. `none`/`hidden` give a side with no
+    /// width rather than no side, so they override a border set by another rule.
+    fn border_side(&mut self) -> Result<BorderSide, CssParseError> {
+        let mut width = None;
+        let mut color = None;
+        let mut visible = true;
+
+        while let Some(token) = self.peek() {
+            match &token.kind {
+                TokenKind::Ident(ident) if BORDER_STYLES.iter().any(|s| s.eq_ignore_ascii_case(ident)) => {
+                    visible = !matches!(ident.to_ascii_lowercase().as_str(), "none" | "hidden");
+                    self.pos += 1;
+                }
+                TokenKind::Ident(ident) if BORDER_WIDTHS.iter().any(|(s, _)| s.eq_ignore_ascii_case(ident)) => {
+                    width = BORDER_WIDTHS.iter().find(|(s, _)| s.eq_ignore_ascii_case(ident)).map(|(_, w)| Length::Px(*w));
+                    self.pos += 1;
+                }
+                TokenKind::Dimension(..) | TokenKind::Number(_) if width.is_none() => width = Some(self.length()?),
+                TokenKind::Function(name) if name == "calc" && width.is_none() => width = Some(self.length()?),
+                _ if color.is_none() => color = Some(self.color()?),
+                _ => return Err(error_at(token, "unexpected value in border")),
+            }
+        }
+
+        Ok(BorderSide {
+            width: if visible { width.unwrap_or(DEFAULT_BORDER_WIDTH) } else { Length::ZERO },
+            color: color.unwrap_or(DEFAULT_BORDER_COLOR),
+        })
+    }
+}
+
+const BORDER_STYLES: [&str; 10] = ["none", "hidden", "solid", "dashed", "dotted", "double", "groove", "ridge", "inset", "outset"];
+const BORDER_WIDTHS: [(&str, f32); 3] = [("thin", 1.0), ("medium", 3.0), ("thick", 5.0)];
+const DEFAULT_BORDER_WIDTH: Length = Length::Px(3.0);
+// `currentColor` has no counterpart yet, so use the initial text color.
+const DEFAULT_BORDER_COLOR: Color = Color::WHITE;
+const OVERFLOW_KEYWORDS: [(&str, Overflow); 5] = [("visible", Overflow::Visible), ("hidden", Overflow::Hidden), ("clip", Overflow::Hidden), ("scroll", Overflow::Scroll), ("auto", Overflow::Auto)];
+
+/// A `calc()` term: plain numbers only appear as factors and divisors.
+#[derive(Clone, Copy)]
+enum CalcValue {
+    Number(f32),
+    Length(CalcLength),
+}
+
+fn token_to_length(token: &Token) -> Result<Length, CssParseError> {
+    match &token.kind {
+        TokenKind::Dimension(value, unit) => match unit.as_str() {
+            "px" => Ok(Length::Px(*value)),
+            "em" => Ok(Length::Em(*value)),
+            "rem" => Ok(Length::Rem(*value)),
+            "vw" => Ok(Length::Vw(*value)),
+            "vh" => Ok(Length::Vh(*value)),
+            "vmin" => Ok(Length::Vmin(*value)),
+            "vmax" => Ok(Length::Vmax(*value)),
+            _ => Err(error_at(token, format!("unsupported unit `{}`", unit))),
+        },
+        TokenKind::Number(value) if *value == 0.0 => Ok(Length::ZERO),
+        _ => Err(error_at(token, "expected a length")),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Side {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+const SIDES: [(&str, Side); 4] = [("top", Side::Top), ("right", Side::Right), ("bottom", Side::Bottom), ("left", Side::Left)];
+
+fn border_side_mut(border: &mut BorderStyle, side: Side) -> &mut Option<BorderSide> {
+    match side {
+        Side::Top => &mut border.top,
+        Side::Right => &mut border.right,
+        Side::Bottom => &mut border.bottom,
+        Side::Left => &mut border.left,
+    }
+}
+
+fn box_side_mut(values: &mut BoxModelValues, side: Side) -> &mut Option<Length> {
+    match side {
+        Side::Top => &mut values.top,
+        Side::Right => &mut values.right,
+        Side::Bottom => &mut values.bottom,
+        Side::Left => &mut values.left,
+    }
+}
+
+/// Expands 1 to 4 values into top/right/bottom/left like the `margin` shorthand.
+fn expand_sides<T: Copy>(values: &[T]) -> Option<[T; 4]> {
+    match *values {
+        [all] => Some([all, all, all, all]),
+        [vertical, horizontal] => Some([vertical, horizontal, vertical, horizontal]),
+        [top, horizontal, bottom] => Some([top, horizontal, bottom, horizontal]),
+        [top, right, bottom, left] => Some([top, right, bottom, left]),
+        _ => None,
+    }
+}
+
+/// The `Style` properties a declaration sets: one for a longhand, all of its longhands for a shorthand.
+fn property_ids(name: &str) -> Option<&'static [PropertyId]> {
+    Some(match name {
+        "display" => &[PropertyId::Display],
+        "width" => &[PropertyId::Width],
+        "height" => &[PropertyId::Height],
+        "background-color" | "background" => &[PropertyId::BackgroundColor],
+        "color" => &[PropertyId::Color],
+        "box-sizing" => &[PropertyId::BoxSizing],
+        "position" => &[PropertyId::Position],
+        "float" => &[PropertyId::Float],
+        "white-space" => &[PropertyId::WhiteSpace],
+        "flex-direction" => &[PropertyId::FlexDirection],
+        "justify-content" => &[PropertyId::JustifyContent],
+        "align-items" => &[PropertyId::AlignItems],
+        "align-content" => &[PropertyId::AlignContent],
+        "align-self" => &[PropertyId::AlignSelf],
+        "flex-wrap" => &[PropertyId::FlexWrap],
+        "flex-grow" => &[PropertyId::FlexGrow],
+        "flex-shrink" => &[PropertyId::FlexShrink],
+        "flex" => &[PropertyId::FlexGrow, PropertyId::FlexShrink, PropertyId::FlexBasis],
+        "flex-basis" => &[PropertyId::FlexBasis],
+        "order" => &[PropertyId::Order],
+        "row-gap" => &[PropertyId::RowGap],
+        "column-gap" => &[PropertyId::ColumnGap],
+        "gap" => &[PropertyId::RowGap, PropertyId::ColumnGap],
+        "grid-template-columns" => &[PropertyId::GridTemplateColumns],
+        "grid-template-rows" => &[PropertyId::GridTemplateRows],
+        "grid-template-areas" => &[PropertyId::GridTemplateAreas],
+        "grid-auto-columns" => &[PropertyId::GridAutoColumns],
+        "grid-auto-rows" => &[PropertyId::GridAutoRows],
+        "grid-auto-flow" => &[PropertyId::GridAutoFlow],
+        "grid-column" => &[PropertyId::GridColumnStart, PropertyId::GridColumnEnd],
+        "grid-column-start" => &[PropertyId::GridColumnStart],
+        "grid-column-end" => &[PropertyId::GridColumnEnd],
+        "grid-row" => &[PropertyId::GridRowStart, PropertyId::GridRowEnd],
+        "grid-row-start" => &[PropertyId::GridRowStart],
+        "grid-row-end" => &[PropertyId::GridRowEnd],
+        "grid-area" => &[PropertyId::GridRowStart, PropertyId::GridColumnStart, PropertyId::GridRowEnd, PropertyId::GridColumnEnd],
+        "font-size" => &[PropertyId::FontSize],
+        "font-family" => &[PropertyId::FontFamily],
+        "font-weight" => &[PropertyId::FontWeight],
+        "font-style" => &[PropertyId::FontStyle],
+        "font-stretch" => &[PropertyId::FontStretch],
+        "top" => &[PropertyId::Top],
+        "right" => &[PropertyId::Right],
+        "bottom" => &[PropertyId::Bottom],
+        "left" => &[PropertyId::Left],
+        "overflow" => &[PropertyId::OverflowX, PropertyId::OverflowY],
+        "overflow-x" => &[PropertyId::OverflowX],
+        "overflow-y" => &[PropertyId::OverflowY],
+        "vertical-align" => &[PropertyId::VerticalAlign],
+        "line-height" => &[PropertyId::LineHeight],
+        "text-align" => &[PropertyId::TextAlign],
+        "text-indent" => &[PropertyId::TextIndent],
+        "letter-spacing" => &[PropertyId::LetterSpacing],
+        "word-spacing" => &[PropertyId::WordSpacing],
+        "margin" => &[PropertyId::MarginTop, PropertyId::MarginRight, PropertyId::MarginBottom, PropertyId::MarginLeft],
+        "margin-top" => &[PropertyId::MarginTop],
+        "margin-right" => &[PropertyId::MarginRight],
+        "margin-bottom" => &[PropertyId::MarginBottom],
+        "margin-left" => &[PropertyId::MarginLeft],
+        "padding" => &[PropertyId::PaddingTop, PropertyId::PaddingRight, PropertyId::PaddingBottom, PropertyId::PaddingLeft],
+        "padding-top" => &[PropertyId::PaddingTop],
+        "padding-right" => &[PropertyId::PaddingRight],
+        "padding-bottom" => &[PropertyId::PaddingBottom],
+        "padding-left" => &[PropertyId::PaddingLeft],
+        // A side's width and color are stored together, so a keyword on either applies to both
+        "border" | "border-width" | "border-color" | "border-style" => &[PropertyId::BorderTop, PropertyId::BorderRight, PropertyId::BorderBottom, PropertyId::BorderLeft],
+        "border-top" | "border-top-width" | "border-top-color" | "border-top-style" => &[PropertyId::BorderTop],
+        "border-right" | "border-right-width" | "border-right-color" | "border-right-style" => &[PropertyId::BorderRight],
+        "border-bottom" | "border-bottom-width" | "border-bottom-color" | "border-bottom-style" => &[PropertyId::BorderBottom],
+        "border-left" | "border-left-width" | "border-left-color" | "border-left-style" => &[PropertyId::BorderLeft],
+        _ => return None,
+    })
+}
+
+fn css_wide_keyword(tokens: &[Token]) -> Option<CssWideKeyword> {
+    let [Token { kind: TokenKind::Ident(ident), .. }] = tokens else {
+        return None;
+    };
+    match ident.to_ascii_lowercase().as_str() {
+        "inherit" => Some(CssWideKeyword::Inherit),
+        "initial" => Some(CssWideKeyword::Initial),
+        "unset" => Some(CssWideKeyword::Unset),
+        _ => None,
+    }
+}
+
+fn apply_declaration(style: &mut Style, name: &str, property: &Token, tokens: &[Token]) -> Result<(), CssParseError> {
+    let ids = property_ids(name).ok_or_else(|| error_at(property, format!("unknown property `{}`", name)))?;
+    if let Some(keyword) = css_wide_keyword(tokens) {
+        for &id in ids {
+            style.set_keyword(id, keyword);
+        }
+        return Ok(());
+    }
+
+    apply_value(style, name, property, tokens)?;
+    // A later value in the same block overrides an earlier keyword.
+    for id in ids {
+        style.keywords.remove(id);
+    }
+    Ok(())
+}
+
+fn apply_value(style: &mut Style, name: &str, property: &Token, tokens: &[Token]) -> Result<(), CssParseError> {
+    let mut values = Values::new(property, tokens);
+
+    match name {
+        "display" => {
+            style.display = Some(values.keyword(&[("block", Display::Block), ("inline", Display::Inline), ("inline-block", Display::InlineBlock), ("flex", Display::Flex), ("grid", Display::Grid), ("none", Display::None)])?);
+        }
+        "width" => style.width = Some(values.size()?),
+        "height" => style.height = Some(values.size()?),
+        "background-color" | "background" => style.bg_color = Some(values.color()?),
+        "color" => style.color = Some(values.color()?),
+        "margin" => style.margin = Some(values.box_values()?),
+        "padding" => style.padding = Some(values.box_values()?),
+        "box-sizing" => style.box_sizing = Some(values.keyword(&[("content-box", BoxSizing::ContentBox), ("border-box", BoxSizing::BorderBox)])?),
+        "position" => style.position = Some(values.keyword(&[("static", Position::Static), ("relative", Position::Relative), ("absolute", Position::Absolute), ("fixed", Position::Fixed), ("sticky", Position::Sticky)])?),
+        "float" => style.float = Some(values.keyword(&[("left", Float::Left), ("right", Float::Right), ("none", Float::None)])?),
+        "white-space" => {
+            style.white_space = Some(values.keyword(&[("normal", WhiteSpace::Normal), ("nowrap", WhiteSpace::NoWrap), ("pre", WhiteSpace::Pre), ("pre-wrap", WhiteSpace::PreWrap), ("pre-line", WhiteSpace::PreLine)])?);
+        }
+        "border" => {
+            let side = Some(values.border_side()?);
+            style.border = Some(BorderStyle { top: side, right: side, bottom: side, left: side });
+        }
+        "border-width" | "border-color" | "border-style" => {
+            let mut parsed = Vec::new();
+            while !values.is_empty() {
+                parsed.push(values.border_part(name)?);
+            }
+            let sides = expand_sides(&parsed).ok_or_else(|| error_at(property, "expected 1 to 4 values"))?;
+            let border = style.border.get_or_insert_with(BorderStyle::default);
+            for ((_, side), value) in SIDES.iter().zip(sides) {
+                value.apply_to(border_side_mut(border, *side));
+            }
+        }
+        "flex-direction" => {
+            style.flex_direction = Some(values.keyword(&[("row", FlexDirection::Row), ("row-reverse", FlexDirection::RowReverse), ("column", FlexDirection::Column), ("column-reverse", FlexDirection::ColumnReverse)])?);
+        }
+        "justify-content" => {
+            style.justify_content = Some(values.keyword(&[
+                ("flex-start", JustifyContent::FlexStart),
+                ("start", JustifyContent::FlexStart),
+                ("flex-end", JustifyContent::FlexEnd),
+                ("end", JustifyContent::FlexEnd),
+                ("center", JustifyContent::Center),
+                ("space-between", JustifyContent::SpaceBetween),
+                ("space-around", JustifyContent::SpaceAround),
+                ("space-evenly", JustifyContent::SpaceEvenly),
+            ])?);
+        }
+        "align-items" => {
+            style.align_items = Some(values.keyword(&[("flex-start", AlignItems::FlexStart), ("start", AlignItems::FlexStart), ("flex-end", AlignItems::FlexEnd), ("end", AlignItems::FlexEnd), ("center", AlignItems::Center), ("stretch", AlignItems::Stretch), ("baseline", AlignItems::Baseline)])?);
+        }
+        "align-content" => {
+            style.align_content = Some(values.keyword(&[
+                ("flex-start", AlignContent::FlexStart),
+                ("start", AlignContent::FlexStart),
+                ("flex-end", AlignContent::FlexEnd),
+                ("end", AlignContent::FlexEnd),
+                ("center", AlignContent::Center),
+                ("stretch", AlignContent::Stretch),
+                ("space-between", AlignContent::SpaceBetween),
+                ("space-around", AlignContent::SpaceAround),
+                ("space-evenly", AlignContent::SpaceEvenly),
+            ])?);
+        }
+        "align-self" => {
+            style.align_self = Some(values.keyword(&[("auto", AlignSelf::Auto), ("flex-start", AlignSelf::FlexStart), ("start", AlignSelf::FlexStart), ("flex-end", AlignSelf::FlexEnd), ("end", AlignSelf::FlexEnd), ("center", AlignSelf::Center), ("stretch", AlignSelf::Stretch), ("baseline", AlignSelf::Baseline)])?);
+        }
+        "flex-wrap" => style.flex_wrap = Some(values.keyword(&[("nowrap", FlexWrap::NoWrap), ("wrap", FlexWrap::Wrap), ("wrap-reverse", FlexWrap::WrapReverse)])?),
+        "flex-grow" => style.flex_grow = Some(values.number()?),
+        "flex-shrink" => style.flex_shrink = Some(values.number()?),
+        "flex-basis" => style.flex_basis = Some(values.size()?),
+        "order" => {
+            let token = values.next()?;
+            match token.kind {
+                TokenKind::Number(value) if value.fract() == 0.0 => style.order = Some(value as i32),
+                _ => return Err(error_at(token, "expected an integer")),
+            }
+        }
+        "row-gap" => style.row_gap = Some(values.length_percentage()?),
+        "column-gap" => style.column_gap = Some(values.length_percentage()?),
+        "overflow" => {
+            let x = values.keyword(&OVERFLOW_KEYWORDS)?;
+            let y = if values.is_empty() { x } else { values.keyword(&OVERFLOW_KEYWORDS)? };
+            style.overflow_x = Some(x);
+            style.overflow_y = Some(y);
+        }
+        "overflow-x" => style.overflow_x = Some(values.keyword(&OVERFLOW_KEYWORDS)?),
+        "overflow-y" => style.overflow_y = Some(values.keyword(&OVERFLOW_KEYWORDS)?),
+        "vertical-align" => {
+            let vertical_align = match values.peek() {
+                Some(Token { kind: TokenKind::Ident(_), .. }) => values.keyword(&[
+                    ("baseline", VerticalAlign::Baseline),
+                    ("sub", VerticalAlign::Sub),
+                    ("super", VerticalAlign::Super),
+                    ("text-top", VerticalAlign::TextTop),
+                    ("text-bottom", VerticalAlign::TextBottom),
+                    ("middle", VerticalAlign::Middle),
+                    ("top", VerticalAlign::Top),
+                    ("bottom", VerticalAlign::Bottom),
+                ])?,
+                _ => VerticalAlign::Length(values.length_percentage()?),
+            };
+            style.vertical_align = Some(vertical_align);
+        }
+        "line-height" => {
+            let line_height = match values.peek() {
+                Some(Token { kind: TokenKind::Ident(_), .. }) => values.keyword(&[("normal", LineHeight::Normal)])?,
+                Some(Token { kind: TokenKind::Number(_), .. }) => LineHeight::Number(values.number()?),
+                _ => LineHeight::Length(values.length_percentage()?),
+            };
+            style.line_height = Some(line_height);
+        }
+        "text-align" => {
+            style.text_align = Some(values.keyword(&[
+                ("left", TextAlign::Left),
+                ("right", TextAlign::Right),
+                ("center", TextAlign::Center),
+                ("justify", TextAlign::Justify),
+                ("start", TextAlign::Start),
+                ("end", TextAlign::End),
+            ])?)
+        }
+        "text-indent" => style.text_indent = Some(values.length_percentage()?),
+        "letter-spacing" => style.letter_spacing = Some(if values.skip_ident("normal") { Length::Px(0.0) } else { values.length()? }),
+        "word-spacing" => style.word_spacing = Some(if values.skip_ident("normal") { Length::Px(0.0) } else { values.length()? }),
+        "gap" => {
+            let row = values.length_percentage()?;
+            let column = if values.is_empty() { row } else { values.length_percentage()? };
+            style.row_gap = Some(row);
+            style.column_gap = Some(column);
+        }
+        "flex" => {
+            // `none` is `0 0 auto`, `auto` is `1 1 auto`, and a grow factor alone implies a basis of 0
+            let (grow, shrink, basis) = match values.peek().map(|token| &token.kind) {
+                Some(TokenKind::Ident(ident)) if ident.eq_ignore_ascii_case("none") => {
+                    values.pos += 1;
+                    (0.0, 0.0, Size::Auto)
+                }
+                Some(TokenKind::Ident(ident)) if ident.eq_ignore_ascii_case("auto") => {
+                    values.pos += 1;
+                    (1.0, 1.0, Size::Auto)
+                }
+                Some(TokenKind::Number(grow)) => {
+                    let grow = *grow;
+                    values.pos += 1;
+                    let shrink = match values.peek().map(|token| &token.kind) {
+                        Some(TokenKind::Number(shrink)) => {
+                            let shrink = *shrink;
+                            values.pos += 1;
+                            shrink
+                        }
+                        _ => 1.0,
+                    };
+                    let basis = if values.is_empty() { Size::Length(Length::ZERO) } else { values.size()? };
+                    (grow, shrink, basis)
+                }
+                _ => (1.0, 1.0, values.size()?),
+            };
+            style.flex_grow = Some(grow);
+            style.flex_shrink = Some(shrink);
+            style.flex_basis = Some(basis);
+        }
+        "grid-template-columns" => style.grid_template_columns = Some(values.track_list()?),
+        "grid-template-rows" => style.grid_template_rows = Some(values.track_list()?),
+        "grid-template-areas" => {
+            let mut rows = Vec::new();
+            if !values.skip_ident("none") {
+                while !values.is_empty() {
+                    let token = values.next()?;
+                    match &token.kind {
+                        TokenKind::String(row) => rows.push(row.clone()),
+                        _ => return Err(error_at(token, "expected a string of area names")),
+                    }
+                }
+            }
+            let areas = if rows.is_empty() { GridTemplateAreas::default() } else { GridTemplateAreas::parse(&rows).map_err(|message| error_at(property, message))? };
+            style.grid_template_areas = Some(areas);
+        }
+        "grid-auto-columns" => style.grid_auto_columns = Some(values.track_size()?),
+        "grid-auto-rows" => style.grid_auto_rows = Some(values.track_size()?),
+        "grid-auto-flow" => style.grid_auto_flow = Some(values.keyword(&[("row", GridAutoFlow::Row), ("column", GridAutoFlow::Column)])?),
+        "grid-column" => style.grid_column = Some(values.grid_placement()?),
+        "grid-row" => style.grid_row = Some(values.grid_placement()?),
+        "grid-column-start" => style.grid_column.get_or_insert_with(GridPlacement::default).start = Some(values.grid_line()?),
+        "grid-column-end" => style.grid_column.get_or_insert_with(GridPlacement::default).end = Some(values.grid_line()?),
+        "grid-row-start" => style.grid_row.get_or_insert_with(GridPlacement::default).start = Some(values.grid_line()?),
+        "grid-row-end" => style.grid_row.get_or_insert_with(GridPlacement::default).end = Some(values.grid_line()?),
+        "grid-area" => {
+            // `row-start / column-start / row-end / column-end`; omitted names repeat the opposite side's name
+            let mut lines = vec![values.grid_line()?];
+            while !values.is_empty() && lines.len() < 4 {
+                values.expect(TokenKind::Delim('/'), "expected `/` between grid lines")?;
+                lines.push(values.grid_line()?);
+            }
+            let same_name = |line: &GridLine| if let GridLine::Named(name) = line { GridLine::Named(name.clone()) } else { GridLine::Auto };
+            let row_start = lines[0].clone();
+            let column_start = lines.get(1).cloned().unwrap_or_else(|| same_name(&row_start));
+            let row_end = lines.get(2).cloned().unwrap_or_else(|| same_name(&row_start));
+            let column_end = lines.get(3).cloned().unwrap_or_else(|| same_name(&column_start));
+            style.grid_row = Some(GridPlacement { start: Some(row_start), end: Some(row_end) });
+            style.grid_column = Some(GridPlacement { start: Some(column_start), end: Some(column_end) });
+        }
+        "font-size" => style.font.get_or_insert_with(Font::default).font_size = Some(values.length_percentage()?),
+        "font-family" => {
+            // Comma-separated names, each quoted or a run of identifiers
+            let mut families = Vec::new();
+            loop {
+                let token = values.next()?;
+                let family = match &token.kind {
+                    TokenKind::String(family) => family.clone(),
+                    TokenKind::Ident(first) => {
+                        let mut family = first.clone();
+                        while let Some(Token { kind: TokenKind::Ident(word), .. }) = values.peek() {
+                            family.push(' ');
+                            family.push_str(word);
+                            values.pos += 1;
+                        }
+                        family
+                    }
+                    _ => return Err(error_at(token, "expected a font family")),
+                };
+                families.push(family);
+                if values.is_empty() {
+                    break;
+                }
+                values.expect(TokenKind::Comma, "expected `,` between font families")?;
+            }
+            style.font.get_or_insert_with(Font::default).font_family = Some(families);
+        }
+        "font-weight" => {
+            let token = values.next()?;
+            let weight = match &token.kind {
+                TokenKind::Number(weight) if (1.0..=1000.0).contains(weight) => FontWeight::Absolute(*weight as u16),
+                TokenKind::Ident(ident) => match ident.to_ascii_lowercase().as_str() {
+                    "normal" => FontWeight::Absolute(FontWeight::NORMAL),
+                    "bold" => FontWeight::Absolute(FontWeight::BOLD),
+                    "bolder" => FontWeight::Bolder,
+                    "lighter" => FontWeight::Lighter,
+                    _ => return Err(error_at(token, "expected one of: normal, bold, bolder, lighter")),
+                },
+                _ => return Err(error_at(token, "expected a font weight from 1 to 1000")),
+            };
+            style.font.get_or_insert_with(Font::default).font_weight = Some(weight);
+        }
+        "font-style" => {
+            let slant = values.keyword(&[("normal", FontSlant::Normal), ("italic", FontSlant::Italic), ("oblique", FontSlant::Oblique)])?;
+            // The angle of `oblique <angle>` is left to the font
+            if slant == FontSlant::Oblique && matches!(values.peek(), Some(Token { kind: TokenKind::Dimension(_, unit), .. }) if unit == "deg") {
+                values.pos += 1;
+            }
+            style.font.get_or_insert_with(Font::default).font_style = Some(slant);
+        }
+        "font-stretch" => {
+            let stretch = match values.peek() {
+                Some(Token { kind: TokenKind::Percentage(percent), .. }) if *percent >= 0.0 => {
+                    values.pos += 1;
+                    *percent
+                }
+                _ => values.keyword(&[
+                    ("ultra-condensed", 50.0),
+                    ("extra-condensed", 62.5),
+                    ("condensed", 75.0),
+                    ("semi-condensed", 87.5),
+                    ("normal", 100.0),
+                    ("semi-expanded", 112.5),
+                    ("expanded", 125.0),
+                    ("extra-expanded", 150.0),
+                    ("ultra-expanded", 200.0),
+                ])?,
+            };
+            style.font.get_or_insert_with(Font::default).font_stretch = Some(stretch);
+        }
+        "top" | "right" | "bottom" | "left" => {
+            let value = Some(values.size()?);
+            let offsets = style.position_offsets.get_or_insert_with(PositionOffsets::default);
+            match name {
+                "top" => offsets.top = value,
+                "right" => offsets.right = value,
+                "bottom" => offsets.bottom = value,
+                _ => offsets.left = value,
+            }
+        }
+        _ => {
+            if let Some(rest) = name.strip_prefix("margin-").or_else(|| name.strip_prefix("padding-")) {
+                let side = side_named(rest).ok_or_else(|| error_at(property, format!("unknown property `{}`", name)))?;
+                let value = values.length_percentage()?;
+                let target = if name.starts_with("margin") { &mut style.margin } else { &mut style.padding };
+                *box_side_mut(target.get_or_insert_with(BoxModelValues::default), side) = Some(value);
+            } else if let Some(rest) = name.strip_prefix("border-") {
+                let (side_name, part) = rest.split_once('-').unwrap_or((rest, ""));
+                let side = side_named(side_name).ok_or_else(|| error_at(property, format!("unknown property `{}`", name)))?;
+                let border = style.border.get_or_insert_with(BorderStyle::default);
+                let target = border_side_mut(border, side);
+                match part {
+                    "" => *target = Some(values.border_side()?),
+                    "width" | "color" | "style" => values.border_part(part)?.apply_to(target),
+                    _ => return Err(error_at(property, format!("unknown property `{}`", name))),
+                }
+            } else {
+                return Err(error_at(property, format!("unknown property `{}`", name)));
+            }
+        }
+    }
+
+    values.expect_end()
+}
+
+fn side_named(name: &str) -> Option<Side> {
+    SIDES.iter().find(|(n, _)| *n == name).map(|(_, side)| *side)
+}
+
+/// One component of the `border-width` / `border-color` / `border-style` longhands.
+#[derive(Clone, Copy)]
+enum BorderPart {
+    Width(Length),
+    Color(Color),
+    Visible(bool),
+}
+
+impl BorderPart {
+    fn apply_to(self, side: &mut Option<BorderSide>) {
+        let current = side.unwrap_or(BorderSide { width: DEFAULT_BORDER_WIDTH, color: DEFAULT_BORDER_COLOR });
+        *side = match self {
+            BorderPart::Width(width) => Some(BorderSide { width, ..current }),
+            BorderPart::Color(color) => Some(BorderSide { color, ..current }),
+            BorderPart::Visible(true) => Some(current),
+            BorderPart::Visible(false) => Some(BorderSide { width: Length::ZERO, ..current }),
+        };
+    }
+}
+
+impl Values<'_> {
+    fn border_part(&mut self, name: &str) -> Result<BorderPart, CssParseError> {
+        if name.ends_with("width") {
+            if let Some(Token { kind: TokenKind::Ident(ident), .. }) = self.peek() {
+                if let Some((_, width)) = BORDER_WIDTHS.iter().find(|(s, _)| s.eq_ignore_ascii_case(ident)) {
+                    self.pos += 1;
+                    return Ok(BorderPart::Width(Length::Px(*width)));
+                }
+            }
+            self.length().map(BorderPart::Width)
+        } else if name.ends_with("color") {
+            self.color().map(BorderPart::Color)
+        } else {
+            let token = self.next()?;
+            match &token.kind {
+                TokenKind::Ident(ident) if BORDER_STYLES.iter().any(|s| s.eq_ignore_ascii_case(ident)) => Ok(BorderPart::Visible(!matches!(ident.to_ascii_lowercase().as_str(), "none" | "hidden"))),
+                _ => Err(error_at(token, "expected a border style")),
+            }
+        }
+    }
+}
+
+fn hex_color(hex: &str) -> Option<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|d| (d * 17) as f32 / 255.0);
+    let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok().map(|d| d as f32 / 255.0);
+
+    match hex.len() {
+        3 => Some(Color::new(digit(0)?, digit(1)?, digit(2)?, 1.0)),
+        4 => Some(Color::new(digit(0)?, digit(1)?, digit(2)?, digit(3)?)),
+        6 => Some(Color::new(pair(0)?, pair(2)?, pair(4)?, 1.0)),
+        8 => Some(Color::new(pair(0)?, pair(2)?, pair(4)?, pair(6)?)),
+        _ => None,
+    }
+}
+
+fn named_color(name: &str) -> Option<Color> {
+    let rgb = |r: u8, g: u8, b: u8| Color::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0);
+
+    let color = match name.to_ascii_lowercase().as_str() {
+        "transparent" => Color::TRANSPARENT,
+        "black" => rgb(0, 0, 0),
+        "white" => rgb(255, 255, 255),
+        "red" => rgb(255, 0, 0),
+        "green" => rgb(0, 128, 0),
+        "lime" => rgb(0, 255, 0),
+        "blue" => rgb(0, 0, 255),
+        "yellow" => rgb(255, 255, 0),
+        "cyan" | "aqua" => rgb(0, 255, 255),
+        "magenta" | "fuchsia" => rgb(255, 0, 255),
+        "gray" | "grey" => rgb(128, 128, 128),
+        "darkgray" | "darkgrey" => rgb(169, 169, 169),
+        "lightgray" | "lightgrey" => rgb(211, 211, 211),
+        "silver" => rgb(192, 192, 192),
+        "maroon" => rgb(128, 0, 0),
+        "olive" => rgb(128, 128, 0),
+        "teal" => rgb(0, 128, 128),
+        "navy" => rgb(0, 0, 128),
+        "purple" => rgb(128, 0, 128),
+        "orange" => rgb(255, 165, 0),
+        _ => return None,
+    };
+    Some(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::dom::Dom;
+
+    fn kinds(input: &str) -> Vec<TokenKind> {
+        Tokenizer::new(input).tokenize().unwrap().into_iter().map(|token| token.kind).collect()
+    }
+
+    #[test]
+    fn tokenizes_numbers_names_and_strings() {
+        assert_eq!(
+            kinds("1em 1e2 -.5% #fff calc( 'a\\'b' /* skipped */-x"),
+            [
+                TokenKind::Dimension(1.0, "em".to_string()),
+                TokenKind::Whitespace,
+                TokenKind::Number(100.0),
+                TokenKind::Whitespace,
+                TokenKind::Percentage(-0.5),
+                TokenKind::Whitespace,
+                TokenKind::Hash("fff".to_string()),
+                TokenKind::Whitespace,
+                TokenKind::Function("calc".to_string()),
+                TokenKind::Whitespace,
+                TokenKind::String("a'b".to_string()),
+                TokenKind::Whitespace,
+                TokenKind::Ident("-x".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_where_errors_are() {
+        let error = Tokenizer::new("a {\n  content: 'open\n}").tokenize().unwrap_err();
+        assert_eq!((error.line, error.column, error.message.as_str()), (2, 12, "unterminated string"));
+        let error = Stylesheet::parse("p {\n  color: red;\n").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 1));
+        let error = Stylesheet::parse("p { colour: red }").unwrap_err();
+        assert_eq!((error.line, error.column, error.message.as_str()), (1, 5, "unknown property `colour`"));
+    }
+
+    #[test]
+    fn parses_rules_and_declarations() {
+        let sheet = Stylesheet::parse("div > .a, #b { width: calc(100% - 2em); color: #ff000080 }\np { margin: 1px 2px }").unwrap();
+        assert_eq!(sheet.rules.len(), 2);
+        assert_eq!(sheet.rules[0].selectors.len(), 2);
+        assert_eq!((sheet.rules[1].line, sheet.rules[1].column), (2, 1));
+        let style = &sheet.rules[0].style;
+        assert!(matches!(style.width, Some(Size::Length(Length::Calc(_)))));
+        assert_eq!(style.color, Some(Color { r: 1.0, g: 0.0, b: 0.0, a: 128.0 / 255.0 }));
+        let margin = sheet.rules[1].style.margin.unwrap();
+        assert_eq!((margin.top, margin.right, margin.bottom, margin.left), (Some(Length::Px(1.0)), Some(Length::Px(2.0)), Some(Length::Px(1.0)), Some(Length::Px(2.0))));
+    }
+
+    #[test]
+    fn keyword_on_a_side_leaves_the_other_sides() {
+        let style = Style::from_css("margin: 10px; margin-left: inherit").unwrap();
+        assert_eq!(style.keywords.len(), 1);
+        assert_eq!(style.keywords.get(&PropertyId::MarginLeft), Some(&CssWideKeyword::Inherit));
+        assert_eq!(style.margin.and_then(|margin| margin.top), Some(Length::Px(10.0)));
+
+        // A value for another side doesn't clear the keyword
+        let style = Style::from_css("margin-left: inherit; margin-top: 5px").unwrap();
+        assert_eq!(style.keywords.get(&PropertyId::MarginLeft), Some(&CssWideKeyword::Inherit));
+        let style = Style::from_css("left: initial; grid-column-end: unset; grid-column-start: 2").unwrap();
+        assert_eq!(style.keywords.keys().copied().collect::<Vec<_>>(), [PropertyId::Left, PropertyId::GridColumnEnd]);
+    }
+
+    #[test]
+    fn keyword_on_a_side_inherits_only_that_side() {
+        let mut dom = Dom::from_html(r#"<div style="margin: 3px"><p style="margin: 10px; margin-left: inherit"></p></div>"#).unwrap();
+        dom.compute_styles(800.0, 600.0);
+        let paragraph = dom.children(dom.children(dom.root).unwrap()[0]).unwrap()[0];
+        let margin = dom.computed_styles[paragraph.into()].margin;
+        assert_eq!(margin.left, Some(Length::Px(3.0)));
+        assert_eq!(margin.top, Some(Length::Px(10.0)));
+        assert_eq!(margin.right, Some(Length::Px(10.0)));
+    }
+
+    #[test]
+    fn calc_follows_precedence_and_nesting() {
+        let width = |css: &str| Style::from_css(&format!("width: {}", css)).unwrap().width;
+        let length = |calc: CalcLength| Some(Size::Length(calc.simplify()));
+        assert_eq!(width("calc(100% - 2 * 10px)"), length(CalcLength { px: -20.0, percent: 100.0, ..CalcLength::default() }));
+        assert_eq!(width("calc((1em + 2px) / 2)"), length(CalcLength { px: 1.0, em: 0.5, ..CalcLength::default() }));
+        assert_eq!(width("calc(1px + calc(2px * 3) - 10% / 4)"), length(CalcLength { px: 7.0, percent: -2.5, ..CalcLength::default() }));
+        assert_eq!(width("calc((2 + 3) * 4px)"), Some(Size::Length(Length::Px(20.0))));
+        // Operators of the same precedence apply left to right
+        assert_eq!(width("calc(10px - 4px - 2px)"), Some(Size::Length(Length::Px(4.0))));
+        assert_eq!(width("calc(12px / 2 / 3)"), Some(Size::Length(Length::Px(2.0))));
+        assert_eq!(width("calc(1vw + 2vmin - 1vw)"), Some(Size::Length(Length::Vmin(2.0))));
+    }
+
+    #[test]
+    fn calc_rejects_what_makes_no_length() {
+        let message = |css: &str| Style::from_css(css).unwrap_err().message;
+        assert_eq!(message("width: calc(10px / 0)"), "division by zero in calc()");
+        assert_eq!(message("width: calc(10px / (2 - 2))"), "division by zero in calc()");
+        assert_eq!(message("width: calc(10px * 2px)"), "cannot multiply two lengths");
+        assert_eq!(message("width: calc(10px / 2px)"), "can only divide by a number");
+        assert_eq!(message("width: calc(2 * 3px + 1)"), "cannot add a number to a length");
+        assert_eq!(message("width: calc(2 * 3)"), "calc() must produce a length here");
+        assert_eq!(message("width: calc(10px + 5px"), "unterminated calc()");
+        assert_eq!(message("border-width: calc(1px + 10%)"), "percentages are not allowed here");
+    }
+}
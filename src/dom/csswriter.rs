@@ -0,0 +1,440 @@
+// /src/dom/csswriter.rs
+
+use crate::dom::fontmanager::is_generic_family;
+use crate::dom::grid::{GridAutoFlow, GridLine, GridPlacement, GridTemplateAreas, TrackBreadth, TrackList, TrackSize};
+use crate::dom::length::Length;
+use crate::dom::styleengine::{AlignContent, AlignItems, AlignSelf, BorderSide, BoxModelValues, BoxSizing, Color, CssWideKeyword, Display, FlexDirection, FlexWrap, Float, FontSlant, FontWeight, JustifyContent, LineHeight, Overflow, Position, PropertyId, Size, Style, TextAlign, VerticalAlign, WhiteSpace};
+
+impl Style {
+    /// Writes the properties that are set as a declaration block, which `Style::from_css` parses
+    /// back into the same style. Returns an empty string for a style that sets nothing.
+    pub fn to_css(&self) -> String {
+        let mut declarations: Vec<(&str, String)> = Vec::new();
+        let mut push = |name: &'static str, value: Option<String>| {
+            if let Some(value) = value {
+                declarations.push((name, value));
+            }
+        };
+
+        push("display", self.display.map(|value| display_css(value).to_string()));
+        push("width", self.width.map(size_css));
+        push("height", self.height.map(size_css));
+        push("background-color", self.bg_color.map(color_css));
+        push("color", self.color.map(color_css));
+        push("box-sizing", self.box_sizing.map(|value| box_sizing_css(value).to_string()));
+        push("position", self.position.map(|value| position_css(value).to_string()));
+        if let Some(offsets) = &self.position_offsets {
+            push("top", offsets.top.map(size_css));
+            push("right", offsets.right.map(size_css));
+            push("bottom", offsets.bottom.map(size_css));
+            push("left", offsets.left.map(size_css));
+        }
+        push("float", self.float.map(|value| float_css(value).to_string()));
+        if let Some(font) = &self.font {
+            push("font-size", font.font_size.map(|size| size.to_string()));
+            push("font-family", font.font_family.as_ref().map(|families| families.iter().map(|family| font_family_css(family)).collect::<Vec<_>>().join(", ")));
+            push("font-weight", font.font_weight.map(font_weight_css));
+            push("font-style", font.font_style.map(|value| font_slant_css(value).to_string()));
+            push("font-stretch", font.font_stretch.map(|value| format!("{}%", value)));
+        }
+        push("white-space", self.white_space.map(|value| white_space_css(value).to_string()));
+        push("line-height", self.line_height.map(line_height_css));
+        push("text-align", self.text_align.map(|value| text_align_css(value).to_string()));
+        push("text-indent", self.text_indent.map(|value| value.to_string()));
+        push("letter-spacing", self.letter_spacing.map(|value| value.to_string()));
+        push("word-spacing", self.word_spacing.map(|value| value.to_string()));
+
+        push("flex-direction", self.flex_direction.map(|value| flex_direction_css(value).to_string()));
+        push("justify-content", self.justify_content.map(|value| justify_content_css(value).to_string()));
+        push("align-items", self.align_items.map(|value| align_items_css(value).to_string()));
+        push("align-content", self.align_content.map(|value| align_content_css(value).to_string()));
+        push("flex-wrap", self.flex_wrap.map(|value| flex_wrap_css(value).to_string()));
+        push("align-self", self.align_self.map(|value| align_self_css(value).to_string()));
+        push("flex-grow", self.flex_grow.map(|value| value.to_string()));
+        push("flex-shrink", self.flex_shrink.map(|value| value.to_string()));
+        push("flex-basis", self.flex_basis.map(size_css));
+        push("order", self.order.map(|value| value.to_string()));
+        push("row-gap", self.row_gap.map(|value| value.to_string()));
+        push("column-gap", self.column_gap.map(|value| value.to_string()));
+
+        push("grid-template-columns", self.grid_template_columns.as_ref().map(track_list_css));
+        push("grid-template-rows", self.grid_template_rows.as_ref().map(track_list_css));
+        push("grid-template-areas", self.grid_template_areas.as_ref().map(template_areas_css));
+        push("grid-auto-columns", self.grid_auto_columns.map(track_size_css));
+        push("grid-auto-rows", self.grid_auto_rows.map(track_size_css));
+        push("grid-auto-flow", self.grid_auto_flow.map(|value| grid_auto_flow_css(value).to_string()));
+        push("overflow-x", self.overflow_x.map(|value| overflow_css(value).to_string()));
+        push("overflow-y", self.overflow_y.map(|value| overflow_css(value).to_string()));
+        push("vertical-align", self.vertical_align.map(vertical_align_css));
+
+        let mut css: Vec<String> = declarations.into_iter().map(|(name, value)| format!("{}: {}", name, value)).collect();
+        // Sides of the box properties are written one by one, since a shorthand would set the missing ones
+        for (name, values) in [("margin", &self.margin), ("padding", &self.padding)] {
+            if let Some(values) = values {
+                css.extend(box_sides(values).into_iter().filter_map(|(side, value)| value.map(|value| format!("{}-{}: {}", name, side, value))));
+            }
+        }
+        if let Some(border) = &self.border {
+            // Only the sides that are set are written; `border: none` keeps an empty border
+            let sides: Vec<String> = [("top", border.top), ("right", border.right), ("bottom", border.bottom), ("left", border.left)]
+                .into_iter()
+                .filter_map(|(side, value)| value.map(|value| format!("border-{}: {}", side, border_side_css(value))))
+                .collect();
+            if sides.is_empty() {
+                css.push("border: none".to_string());
+            }
+            css.extend(sides);
+        }
+        // Grid lines too, unless both are set
+        for (name, placement) in [("grid-column", &self.grid_column), ("grid-row", &self.grid_row)] {
+            match placement {
+                Some(GridPlacement { start: Some(start), end: Some(end) }) => css.push(format!("{}: {} / {}", name, grid_line_css(start), grid_line_css(end))),
+                Some(placement) => {
+                    let lines = [("start", &placement.start), ("end", &placement.end)];
+                    css.extend(lines.into_iter().filter_map(|(side, line)| line.as_ref().map(|line| format!("{}-{}: {}", name, side, grid_line_css(line)))));
+                }
+                None => {}
+            }
+        }
+        for (&property, &keyword) in &self.keywords {
+            for name in property_names(property) {
+                css.push(format!("{}: {}", name, keyword_css(keyword)));
+            }
+        }
+        css.join("; ")
+    }
+}
+
+/// `#rrggbb` when the channels are whole bytes, as colors parsed from CSS are, and `rgba()` otherwise.
+fn color_css(color: Color) -> String {
+    let channels = [color.r, color.g, color.b, color.a];
+    let bytes: Vec<u8> = channels.iter().map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8).collect();
+    if channels.iter().zip(&bytes).all(|(&channel, &byte)| byte as f32 / 255.0 == channel) {
+        return match bytes[3] {
+            255 => format!("#{:02x}{:02x}{:02x}", bytes[0], bytes[1], bytes[2]),
+            _ => format!("#{:02x}{:02x}{:02x}{:02x}", bytes[0], bytes[1], bytes[2], bytes[3]),
+        };
+    }
+    format!("rgba({}, {}, {}, {})", color.r * 255.0, color.g * 255.0, color.b * 255.0, color.a)
+}
+
+fn size_css(size: Size) -> String {
+    match size {
+        Size::Auto => "auto".to_string(),
+        Size::Length(length) => length.to_string(),
+    }
+}
+
+fn box_sides(values: &BoxModelValues) -> [(&'static str, Option<Length>); 4] {
+    [("top", values.top), ("right", values.right), ("bottom", values.bottom), ("left", values.left)]
+}
+
+fn border_side_css(side: BorderSide) -> String {
+    // `none` is parsed as a side with no width
+    if side.width == Length::ZERO {
+        return "none".to_string();
+    }
+    format!("{} solid {}", side.width, color_css(side.color))
+}
+
+fn track_breadth_css(breadth: TrackBreadth) -> String {
+    match breadth {
+        TrackBreadth::Length(length) => length.to_string(),
+        TrackBreadth::Fr(factor) => format!("{}fr", factor),
+        TrackBreadth::Auto => "auto".to_string(),
+        TrackBreadth::MinContent => "min-content".to_string(),
+        TrackBreadth::MaxContent => "max-content".to_string(),
+    }
+}
+
+fn track_size_css(size: TrackSize) -> String {
+    if TrackSize::new(size.max) == size {
+        track_breadth_css(size.max)
+    } else {
+        format!("minmax({}, {})", track_breadth_css(size.min), track_breadth_css(size.max))
+    }
+}
+
+fn track_list_css(list: &TrackList) -> String {
+    let mut parts: Vec<String> = list.tracks.iter().map(|&track| track_size_css(track)).collect();
+    if let Some(repeat) = &list.auto_repeat {
+        let tracks: Vec<String> = repeat.tracks.iter().map(|&track| track_size_css(track)).collect();
+        parts.insert(repeat.index.min(parts.len()), format!("repeat(auto-fill, {})", tracks.join(" ")));
+    }
+    if parts.is_empty() { "none".to_string() } else { parts.join(" ") }
+}
+
+fn template_areas_css(areas: &GridTemplateAreas) -> String {
+    if areas.rows == 0 || areas.columns == 0 {
+        return "none".to_string();
+    }
+    let mut cells = vec![vec!["."; areas.columns]; areas.rows];
+    for area in &areas.areas {
+        for row in &mut cells[area.row_start..area.row_end] {
+            for cell in &mut row[area.column_start..area.column_end] {
+                *cell = &area.name;
+            }
+        }
+    }
+    cells.iter().map(|row| format!("\"{}\"", row.join(" "))).collect::<Vec<_>>().join(" ")
+}
+
+fn grid_line_css(line: &GridLine) -> String {
+    match line {
+        GridLine::Auto => "auto".to_string(),
+        GridLine::Line(line) => line.to_string(),
+        GridLine::Span(count) => format!("span {}", count),
+        GridLine::Named(name) => name.clone(),
+    }
+}
+
+/// The declarations that set a property, for writing CSS-wide keywords.
+fn property_names(property: PropertyId) -> &'static [&'static str] {
+    match property {
+        PropertyId::Display => &["display"],
+        PropertyId::Width => &["width"],
+        PropertyId::Height => &["height"],
+        PropertyId::BackgroundColor => &["background-color"],
+        PropertyId::Color => &["color"],
+        PropertyId::MarginTop => &["margin-top"],
+        PropertyId::MarginRight => &["margin-right"],
+        PropertyId::MarginBottom => &["margin-bottom"],
+        PropertyId::MarginLeft => &["margin-left"],
+        PropertyId::PaddingTop => &["padding-top"],
+        PropertyId::PaddingRight => &["padding-right"],
+        PropertyId::PaddingBottom => &["padding-bottom"],
+        PropertyId::PaddingLeft => &["padding-left"],
+        PropertyId::BorderTop => &["border-top"],
+        PropertyId::BorderRight => &["border-right"],
+        PropertyId::BorderBottom => &["border-bottom"],
+        PropertyId::BorderLeft => &["border-left"],
+        PropertyId::BoxSizing => &["box-sizing"],
+        PropertyId::Position => &["position"],
+        PropertyId::Top => &["top"],
+        PropertyId::Right => &["right"],
+        PropertyId::Bottom => &["bottom"],
+        PropertyId::Left => &["left"],
+        PropertyId::Float => &["float"],
+        PropertyId::FontSize => &["font-size"],
+        PropertyId::FontFamily => &["font-family"],
+        PropertyId::FontWeight => &["font-weight"],
+        PropertyId::FontStyle => &["font-style"],
+        PropertyId::FontStretch => &["font-stretch"],
+        PropertyId::WhiteSpace => &["white-space"],
+        PropertyId::FlexDirection => &["flex-direction"],
+        PropertyId::JustifyContent => &["justify-content"],
+        PropertyId::AlignItems => &["align-items"],
+        PropertyId::AlignContent => &["align-content"],
+        PropertyId::FlexWrap => &["flex-wrap"],
+        PropertyId::AlignSelf => &["align-self"],
+        PropertyId::FlexGrow => &["flex-grow"],
+        PropertyId::FlexShrink => &["flex-shrink"],
+        PropertyId::FlexBasis => &["flex-basis"],
+        PropertyId::Order => &["order"],
+        PropertyId::RowGap => &["row-gap"],
+        PropertyId::ColumnGap => &["column-gap"],
+        PropertyId::GridTemplateColumns => &["grid-template-columns"],
+        PropertyId::GridTemplateRows => &["grid-template-rows"],
+        PropertyId::GridTemplateAreas => &["grid-template-areas"],
+        PropertyId::GridAutoColumns => &["grid-auto-columns"],
+        PropertyId::GridAutoRows => &["grid-auto-rows"],
+        PropertyId::GridAutoFlow => &["grid-auto-flow"],
+        PropertyId::GridColumnStart => &["grid-column-start"],
+        PropertyId::GridColumnEnd => &["grid-column-end"],
+        PropertyId::GridRowStart => &["grid-row-start"],
+        PropertyId::GridRowEnd => &["grid-row-end"],
+        PropertyId::OverflowX => &["overflow-x"],
+        PropertyId::OverflowY => &["overflow-y"],
+        PropertyId::VerticalAlign => &["vertical-align"],
+        PropertyId::LineHeight => &["line-height"],
+        PropertyId::TextAlign => &["text-align"],
+        PropertyId::TextIndent => &["text-indent"],
+        PropertyId::LetterSpacing => &["letter-spacing"],
+        PropertyId::WordSpacing => &["word-spacing"],
+    }
+}
+
+fn keyword_css(keyword: CssWideKeyword) -> &'static str {
+    match keyword {
+        CssWideKeyword::Inherit => "inherit",
+        CssWideKeyword::Initial => "initial",
+        CssWideKeyword::Unset => "unset",
+    }
+}
+
+fn display_css(display: Display) -> &'static str {
+    match display {
+        Display::Block => "block",
+        Display::Inline => "inline",
+        Display::InlineBlock => "inline-block",
+        Display::Flex => "flex",
+        Display::Grid => "grid",
+        Display::None => "none",
+    }
+}
+
+fn box_sizing_css(box_sizing: BoxSizing) -> &'static str {
+    match box_sizing {
+        BoxSizing::ContentBox => "content-box",
+        BoxSizing::BorderBox => "border-box",
+    }
+}
+
+fn position_css(position: Position) -> &'static str {
+    match position {
+        Position::Static => "static",
+        Position::Relative => "relative",
+        Position::Absolute => "absolute",
+        Position::Fixed => "fixed",
+        Position::Sticky => "sticky",
+    }
+}
+
+fn float_css(float: Float) -> &'static str {
+    match float {
+        Float::Left => "left",
+        Float::Right => "right",
+        Float::None => "none",
+    }
+}
+
+/// Generic families are keywords; every other name is quoted.
+fn font_family_css(family: &str) -> String {
+    if is_generic_family(family) { family.to_string() } else { format!("\"{}\"", family.replace('"', "\\\"")) }
+}
+
+fn font_weight_css(weight: FontWeight) -> String {
+    match weight {
+        FontWeight::Absolute(weight) => weight.to_string(),
+        FontWeight::Bolder => "bolder".to_string(),
+        FontWeight::Lighter => "lighter".to_string(),
+    }
+}
+
+fn font_slant_css(slant: FontSlant) -> &'static str {
+    match slant {
+        FontSlant::Normal => "normal",
+        FontSlant::Italic => "italic",
+        FontSlant::Oblique => "oblique",
+    }
+}
+
+fn vertical_align_css(vertical_align: VerticalAlign) -> String {
+    match vertical_align {
+        VerticalAlign::Baseline => "baseline".to_string(),
+        VerticalAlign::Sub => "sub".to_string(),
+        VerticalAlign::Super => "super".to_string(),
+        VerticalAlign::TextTop => "text-top".to_string(),
+        VerticalAlign::TextBottom => "text-bottom".to_string(),
+        VerticalAlign::Middle => "middle".to_string(),
+        VerticalAlign::Top => "top".to_string(),
+        VerticalAlign::Bottom => "bottom".to_string(),
+        VerticalAlign::Length(length) => length.to_string(),
+    }
+}
+
+fn line_height_css(line_height: LineHeight) -> String {
+    match line_height {
+        LineHeight::Normal => "normal".to_string(),
+        LineHeight::Number(value) => value.to_string(),
+        LineHeight::Length(length) => length.to_string(),
+    }
+}
+
+fn text_align_css(text_align: TextAlign) -> &'static str {
+    match text_align {
+        TextAlign::Left => "left",
+        TextAlign::Right => "right",
+        TextAlign::Center => "center",
+        TextAlign::Justify => "justify",
+        TextAlign::Start => "start",
+        TextAlign::End => "end",
+    }
+}
+
+fn white_space_css(white_space: WhiteSpace) -> &'static str {
+    match white_space {
+        WhiteSpace::Normal => "normal",
+        WhiteSpace::NoWrap => "nowrap",
+        WhiteSpace::Pre => "pre",
+        WhiteSpace::PreWrap => "pre-wrap",
+        WhiteSpace::PreLine => "pre-line",
+    }
+}
+
+fn flex_direction_css(direction: FlexDirection) -> &'static str {
+    match direction {
+        FlexDirection::Row => "row",
+        FlexDirection::RowReverse => "row-reverse",
+        FlexDirection::Column => "column",
+        FlexDirection::ColumnReverse => "column-reverse",
+    }
+}
+
+fn justify_content_css(justify: JustifyContent) -> &'static str {
+    match justify {
+        JustifyContent::FlexStart => "flex-start",
+        JustifyContent::FlexEnd => "flex-end",
+        JustifyContent::Center => "center",
+        JustifyContent::SpaceBetween => "space-between",
+        JustifyContent::SpaceAround => "space-around",
+        JustifyContent::SpaceEvenly => "space-evenly",
+    }
+}
+
+fn align_items_css(align: AlignItems) -> &'static str {
+    match align {
+        AlignItems::FlexStart => "flex-start",
+        AlignItems::FlexEnd => "flex-end",
+        AlignItems::Center => "center",
+        AlignItems::Stretch => "stretch",
+        AlignItems::Baseline => "baseline",
+    }
+}
+
+fn align_content_css(align: AlignContent) -> &'static str {
+    match align {
+        AlignContent::FlexStart => "flex-start",
+        AlignContent::FlexEnd => "flex-end",
+        AlignContent::Center => "center",
+        AlignContent::Stretch => "stretch",
+        AlignContent::SpaceBetween => "space-between",
+        AlignContent::SpaceAround => "space-around",
+        AlignContent::SpaceEvenly => "space-evenly",
+    }
+}
+
+fn align_self_css(align: AlignSelf) -> &'static str {
+    match align {
+        AlignSelf::Auto => "auto",
+        AlignSelf::FlexStart => "flex-start",
+        AlignSelf::FlexEnd => "flex-end",
+        AlignSelf::Center => "center",
+        AlignSelf::Stretch => "stretch",
+        AlignSelf::Baseline => "baseline",
+    }
+}
+
+fn flex_wrap_css(wrap: FlexWrap) -> &'static str {
+    match wrap {
+        FlexWrap::NoWrap => "nowrap",
+        FlexWrap::Wrap => "wrap",
+        FlexWrap::WrapReverse => "wrap-reverse",
+    }
+}
+
+fn grid_auto_flow_css(flow: GridAutoFlow) -> &'static str {
+    match flow {
+        GridAutoFlow::Row => "row",
+        GridAutoFlow::Column => "column",
+    }
+}
+
+fn overflow_css(overflow: Overflow) -> &'static str {
+    match overflow {
+        Overflow::Visible => "visible",
+        Overflow::Hidden => "hidden",
+        Overflow::Scroll => "scroll",
+        Overflow::Auto => "auto",
+    }
+}
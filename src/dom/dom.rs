@@ -3,10 +3,15 @@
 use std::cell::RefCell;
 
 use crate::dom::{
-    domapi::StyleManager,
+    cssparser::Stylesheet,
+    domapi::{DomError, StyleManager},
     element::Element,
-    fragment::DocumentFragment,
-    layoutengine::{LayoutData, TextInfo},
+    fragment::{DocumentFragment, FragmentMapping},
+    inline::InlineFragment,
+    invalidation::{CachedLayout, TextWidths},
+    layoutengine::{LayoutData, Rect, TextInfo},
+    observer::MutationObservers,
+    scroll::ScrollState,
     styleengine::{ComputedStyle, Display, Font, Style},
     text::Text,
 };
@@ -47,12 +52,39 @@ pub struct Dom {
     pub content: SecondaryMap<slotmap::DefaultKey, NodeContent>,
     pub layout: SecondaryMap<slotmap::DefaultKey, LayoutData>,
     // pub text_info: SecondaryMap<slotmap::DefaultKey, TextInfo>,
+    // Nodes whose style must be recomputed, and nodes (with all their ancestors) whose layout must be redone
     pub dirty: RefCell<SecondaryMap<slotmap::DefaultKey, bool>>,
+    pub layout_dirty: RefCell<SecondaryMap<slotmap::DefaultKey, bool>>,
 
     // Separate style storage
     pub styles: SecondaryMap<slotmap::DefaultKey, Style>,
     pub text_info: SecondaryMap<slotmap::DefaultKey, TextInfo>,
+    // The part of each inline element's box on each line it spans
+    pub inline_fragments: SecondaryMap<slotmap::DefaultKey, Vec<InlineFragment>>,
     pub computed_styles: SecondaryMap<slotmap::DefaultKey, ComputedStyle>,
+
+    // Author stylesheets, cascaded in order beneath the per-node styles
+    pub stylesheets: Vec<Stylesheet>,
+
+    // The viewport of the last layout, and where out-of-flow boxes would have gone in normal flow
+    pub viewport: Rect,
+    pub static_positions: SecondaryMap<slotmap::DefaultKey, Rect>,
+
+    // Scroll offsets of scroll containers, kept across layouts
+    pub scroll_states: SecondaryMap<slotmap::DefaultKey, ScrollState>,
+
+    // What the next pass can reuse from the last: the viewport styles were computed for, each node's
+    // layout inputs and result, text widths, and how far boxes were moved after normal flow
+    pub styled_viewport: Option<(f32, f32)>,
+    pub layout_cache: SecondaryMap<slotmap::DefaultKey, CachedLayout>,
+    pub text_widths: SecondaryMap<slotmap::DefaultKey, TextWidths>,
+    pub post_layout_offsets: SecondaryMap<slotmap::DefaultKey, (f32, f32)>,
+
+    // Nodes with a positioned box or scroll container in their subtree, so the passes after normal flow can skip the rest
+    pub post_layout_subtrees: SecondaryMap<slotmap::DefaultKey, bool>,
+
+    // Mutation observers and the records they haven't been given yet
+    pub(crate) mutation_observers: MutationObservers,
 }
 
 impl Dom {
@@ -66,10 +98,22 @@ impl Dom {
             layout: SecondaryMap::new(),
             // text_info: SecondaryMap::new(),
             dirty: RefCell::new(SecondaryMap::new()),
+            layout_dirty: RefCell::new(SecondaryMap::new()),
             styles: SecondaryMap::new(),
             computed_styles: SecondaryMap::new(),
             style_manager: StyleManager::new(),
             text_info: SecondaryMap::new(),
+            inline_fragments: SecondaryMap::new(),
+            stylesheets: Vec::new(),
+            viewport: Rect::default(),
+            static_positions: SecondaryMap::new(),
+            scroll_states: SecondaryMap::new(),
+            styled_viewport: None,
+            layout_cache: SecondaryMap::new(),
+            text_widths: SecondaryMap::new(),
+            post_layout_offsets: SecondaryMap::new(),
+            post_layout_subtrees: SecondaryMap::new(),
+            mutation_observers: MutationObservers::default(),
         };
         let root_element = Element::new(); // You might want a specific tag here
         let root_node_id = dom.create_element(root_element);
@@ -92,17 +136,18 @@ impl Dom {
         node_id
     }
 
-    pub fn append_new_element(&mut self, parent_id: NodeId, element: Element) -> NodeId {
+    /// Creates an element at the end of `parent_id`'s children. Nothing is created when the parent can't take it.
+    pub fn append_new_element(&mut self, parent_id: NodeId, element: Element) -> Result<NodeId, DomError> {
         let child_id = self.create_element(element);
-        self.append_child(parent_id, child_id);
-        child_id
+        self.append_child(parent_id, child_id).inspect_err(|_| self.remove_node(child_id))?;
+        Ok(child_id)
     }
 
-    pub fn append_new_styled_element(&mut self, parent_id: NodeId, element: Element, style: &Style) -> NodeId {
+    pub fn append_new_styled_element(&mut self, parent_id: NodeId, element: Element, style: &Style) -> Result<NodeId, DomError> {
         let child_id = self.create_element(element);
         self.set_style(child_id, style.clone());
-        self.append_child(parent_id, child_id);
-        child_id
+        self.append_child(parent_id, child_id).inspect_err(|_| self.remove_node(child_id))?;
+        Ok(child_id)
     }
 
     pub fn create_text(&mut self, text: Text) -> NodeId {
@@ -147,16 +192,17 @@ impl Dom {
     }
 
     pub fn find_node_at_position(&self, x: f32, y: f32) -> Option<NodeId> {
-        // Collect all nodes in depth-first order (from top to bottom in rendering order)
-        let nodes = self.collect_nodes_depth_first(self.root);
+        // Collect all nodes in the order they are painted, so positioned boxes come after the flow they cover
+        let nodes = self.collect_nodes_paint_order(self.root);
 
         // Check nodes in reverse order (from top-most to bottom-most)
         for node_id in nodes.iter().rev() {
             let key: slotmap::DefaultKey = (*node_id).into();
 
             if let Some(layout_data) = self.layout.get(key) {
-                // Check if the point is inside the node's bounds
-                if x >= layout_data.computed_x && x <= layout_data.computed_x + layout_data.actual_width && y >= layout_data.computed_y && y <= layout_data.computed_y + layout_data.actual_height {
+                // Check if the point is inside the node's bounds, and not in a part a scroll container cuts off
+                let bounds = Rect { x: layout_data.computed_x, y: layout_data.computed_y, width: layout_data.actual_width, height: layout_data.actual_height };
+                if bounds.contains(x, y) && self.clip_rect(*node_id).is_none_or(|clip| clip.contains(x, y)) {
                     return Some(*node_id);
                 }
             }
@@ -164,8 +210,8 @@ impl Dom {
         return Some(self.root);
     }
 
-    pub fn append_fragment(&mut self, parent_id: NodeId, fragment: DocumentFragment) {
-        fragment.merge_into(self, parent_id);
+    pub fn append_fragment(&mut self, parent_id: NodeId, fragment: DocumentFragment) -> Result<FragmentMapping, DomError> {
+        fragment.merge_into(self, parent_id)
     }
 
     pub fn create_fragment() -> DocumentFragment {
@@ -1,9 +1,52 @@
+use std::{borrow::Cow, fmt};
+
 use slotmap::SecondaryMap;
 
 use crate::dom::{
-    dom::{NodeContent, NodeId}, element::{Element}, layoutengine::TextInfo, styleengine::{BorderStyle, BoxModelValues, BoxSizing, Color, Display, Float, Font, Size, Style}, text::Text, Dom
+    cssparser::Stylesheet, dom::{NodeContent, NodeId}, element::{Element}, layoutengine::TextInfo, observer::MutationKind, length::Length, styleengine::{BorderStyle, BoxModelValues, BoxSizing, Color, Display, Float, Font, Size, Style}, text::Text, Dom
 };
 
+/// Why a tree mutation was refused. The tree is left unchanged when one is returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DomError {
+    /// The node was removed, or never belonged to this tree.
+    NodeNotFound(NodeId),
+    /// Only elements can have children.
+    NotAnElement(NodeId),
+    /// The node was expected to be a child of `parent`.
+    NotAChild { parent: NodeId, child: NodeId },
+    /// The node would become its own ancestor.
+    HierarchyCycle(NodeId),
+    /// The root can't be moved, detached or replaced.
+    RootNode,
+    IndexOutOfBounds { index: usize, len: usize },
+    /// Only text nodes have text to edit.
+    NotAText(NodeId),
+    /// A text offset past the end of the text, or a range start past the range's end (given as `len`).
+    /// In bytes or chars, depending on the method.
+    TextOffsetOutOfBounds { offset: usize, len: usize },
+    /// A byte offset in the middle of a UTF-8 encoded character.
+    NotACharBoundary(usize),
+}
+
+impl fmt::Display for DomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DomError::NodeNotFound(node_id) => write!(f, "node {:?} does not exist", node_id),
+            DomError::NotAnElement(node_id) => write!(f, "node {:?} is not an element and can't have children", node_id),
+            DomError::NotAChild { parent, child } => write!(f, "node {:?} is not a child of {:?}", child, parent),
+            DomError::HierarchyCycle(node_id) => write!(f, "node {:?} can't be moved inside itself", node_id),
+            DomError::RootNode => write!(f, "the root node can't be moved, detached or replaced"),
+            DomError::IndexOutOfBounds { index, len } => write!(f, "child index {} is out of bounds for {} children", index, len),
+            DomError::NotAText(node_id) => write!(f, "node {:?} is not a text node", node_id),
+            DomError::TextOffsetOutOfBounds { offset, len } => write!(f, "text offset {} is out of bounds for a length of {}", offset, len),
+            DomError::NotACharBoundary(offset) => write!(f, "byte offset {} is not on a character boundary", offset),
+        }
+    }
+}
+
+impl std::error::Error for DomError {}
+
 #[derive(Debug)]
 pub struct StyleManager {
     pub element_styles: SecondaryMap<slotmap::DefaultKey, Style>,
@@ -20,15 +63,15 @@ impl StyleManager {
         }
     }
 
-    fn set_width(&mut self, node_id: NodeId, width: f32) {
+    fn set_width(&mut self, node_id: NodeId, width: impl Into<Length>) {
         if let Some(style) = self.element_styles.get_mut(node_id.into()) {
-            style.width = Some(Size::Points(width));
+            style.width = Some(Size::Length(width.into()));
         }
     }
 
-    fn set_height(&mut self, node_id: NodeId, height: f32) {
+    fn set_height(&mut self, node_id: NodeId, height: impl Into<Length>) {
         if let Some(style) = self.element_styles.get_mut(node_id.into()) {
-            style.height = Some(Size::Points(height));
+            style.height = Some(Size::Length(height.into()));
         }
     }
 
@@ -68,18 +111,18 @@ impl StyleManager {
         }
     }
 
-    fn set_margin_top(&mut self, node_id: NodeId, value: f32) {
+    fn set_margin_top(&mut self, node_id: NodeId, value: impl Into<Length>) {
         if let Some(style) = self.element_styles.get_mut(node_id.into()) {
             let mut margin = style.margin.unwrap_or_default();
-            margin.top = Some(value);
+            margin.top = Some(value.into());
             style.margin = Some(margin);
         }
     }
 
-    fn set_margin_right(&mut self, node_id: NodeId, value: f32) {
+    fn set_margin_right(&mut self, node_id: NodeId, value: impl Into<Length>) {
         if let Some(style) = self.element_styles.get_mut(node_id.into()) {
             let mut margin = style.margin.unwrap_or_default();
-            margin.right = Some(value);
+            margin.right = Some(value.into());
             style.margin = Some(margin);
         }
     }
@@ -96,86 +139,59 @@ impl StyleManager {
 
 impl Dom {
     pub fn set_display(&mut self, node_id: NodeId, display: Display) {
-        self.style_manager.set_display(node_id, display);
-        self.set_dirty(node_id, true);
+        self.update_style(node_id, |style| style.display = Some(display));
     }
 
     fn set_dirty(&self, node_id: NodeId, is_dirty: bool) {
         self.dirty.borrow_mut().insert(node_id.into(), is_dirty);
     }
 
-    pub fn set_width(&mut self, node_id: NodeId, width: f32) {
-        if let Some(style) = self.styles.get_mut(node_id.into()) {
-            style.width = Some(Size::Points(width));
-            self.set_dirty(node_id, true);
-        }
+    pub fn set_width(&mut self, node_id: NodeId, width: impl Into<Length>) {
+        self.update_style(node_id, |style| style.width = Some(Size::Length(width.into())));
     }
 
-    pub fn set_height(&mut self, node_id: NodeId, height: f32) {
-        if let Some(style) = self.styles.get_mut(node_id.into()) {
-            style.height = Some(Size::Points(height));
-            self.set_dirty(node_id, true);
-        }
+    pub fn set_height(&mut self, node_id: NodeId, height: impl Into<Length>) {
+        self.update_style(node_id, |style| style.height = Some(Size::Length(height.into())));
     }
 
     pub fn set_bg_color(&mut self, node_id: NodeId, color: Color) {
-        if let Some(style) = self.styles.get_mut(node_id.into()) {
-            style.bg_color = Some(color);
-            self.set_dirty(node_id, true);
-        }
+        self.update_style(node_id, |style| style.bg_color = Some(color));
     }
 
     pub fn set_margin(&mut self, node_id: NodeId, margin: BoxModelValues) {
-        if let Some(style) = self.styles.get_mut(node_id.into()) {
-            style.margin = Some(margin);
-            self.set_dirty(node_id, true);
-        }
+        self.update_style(node_id, |style| style.margin = Some(margin));
     }
 
     pub fn set_padding(&mut self, node_id: NodeId, padding: BoxModelValues) {
-        if let Some(style) = self.styles.get_mut(node_id.into()) {
-            style.padding = Some(padding);
-            self.set_dirty(node_id, true);
-        }
+        self.update_style(node_id, |style| style.padding = Some(padding));
     }
 
     pub fn set_border(&mut self, node_id: NodeId, border: BorderStyle) {
-        if let Some(style) = self.styles.get_mut(node_id.into()) {
-            style.border = Some(border);
-            self.set_dirty(node_id, true);
-        }
+        self.update_style(node_id, |style| style.border = Some(border));
     }
 
     pub fn set_box_sizing(&mut self, node_id: NodeId, box_sizing: BoxSizing) {
-        if let Some(style) = self.styles.get_mut(node_id.into()) {
-            style.box_sizing = Some(box_sizing);
-            self.set_dirty(node_id, true);
-        }
+        self.update_style(node_id, |style| style.box_sizing = Some(box_sizing));
     }
 
     pub fn set_float(&mut self, node_id: NodeId, float: Float) {
-        if let Some(style) = self.styles.get_mut(node_id.into()) {
-            style.float = Some(float);
-            self.set_dirty(node_id, true);
-        }
+        self.update_style(node_id, |style| style.float = Some(float));
     }
 
-    pub fn set_margin_top(&mut self, node_id: NodeId, value: f32) {
-        if let Some(style) = self.styles.get_mut(node_id.into()) {
+    pub fn set_margin_top(&mut self, node_id: NodeId, value: impl Into<Length>) {
+        self.update_style(node_id, |style| {
             let mut margin = style.margin.unwrap_or_default();
-            margin.top = Some(value);
+            margin.top = Some(value.into());
             style.margin = Some(margin);
-            self.set_dirty(node_id, true);
-        }
+        });
     }
 
-    pub fn set_margin_right(&mut self, node_id: NodeId, value: f32) {
-        if let Some(style) = self.styles.get_mut(node_id.into()) {
+    pub fn set_margin_right(&mut self, node_id: NodeId, value: impl Into<Length>) {
+        self.update_style(node_id, |style| {
             let mut margin = style.margin.unwrap_or_default();
-            margin.right = Some(value);
+            margin.right = Some(value.into());
             style.margin = Some(margin);
-            self.set_dirty(node_id, true);
-        }
+        });
     }
 
     pub fn is_dirty(&self, node_id: NodeId) -> bool {
@@ -189,27 +205,124 @@ impl Dom {
     pub fn set_style(&mut self, node_id: NodeId, style: Style) {
         let key: slotmap::DefaultKey = node_id.into();
 
-        // Get the current style or create a default one
-        let mut current_style = self.styles.get(key).cloned().unwrap_or_default();
+        // Start from a default style for nodes that don't have one yet
+        if !self.styles.contains_key(key) {
+            self.styles.insert(key, Style::default());
+        }
 
         // Apply the new style properties to the current style
-        current_style.apply(&style);
+        self.update_style(node_id, |current_style| current_style.apply(&style));
+    }
 
-        self.styles.insert(key, current_style);
-        self.dirty.borrow_mut().insert(node_id.into(), true);
+    /// Replaces a node's whole inline style, unlike `set_style` which only overrides the properties it sets.
+    pub fn replace_style(&mut self, node_id: NodeId, style: Style) {
+        let key: slotmap::DefaultKey = node_id.into();
+        if !self.styles.contains_key(key) {
+            self.styles.insert(key, Style::default());
+        }
+        self.update_style(node_id, |current_style| *current_style = style);
     }
 
-    pub fn append_child(&mut self, parent_id: NodeId, child_id: NodeId) {
-        let parent_key: slotmap::DefaultKey = parent_id.into();
-        let child_key: slotmap::DefaultKey = child_id.into();
+    /// Changes a node's inline style, marks it for restyling and tells observers.
+    fn update_style(&mut self, node_id: NodeId, update: impl FnOnce(&mut Style)) {
+        let is_observed = self.is_observed();
+        let Some(style) = self.styles.get_mut(node_id.into()) else {
+            return;
+        };
+        let old_style = is_observed.then(|| Box::new(style.clone()));
+        update(style);
+        self.set_dirty(node_id, true);
+        self.queue_mutation(node_id, MutationKind::Style { old_style });
+    }
+
+    /// Appends a node as the last child of `parent_id`, taking it out of its previous parent first.
+    pub fn append_child(&mut self, parent_id: NodeId, child_id: NodeId) -> Result<(), DomError> {
+        // Counted without the node, which may already be one of the children
+        let len = self.children.get(parent_id.into()).map_or(0, |children| children.iter().filter(|&&id| id != child_id).count());
+        self.move_node(child_id, parent_id, len)
+    }
+
+    pub fn add_stylesheet(&mut self, stylesheet: Stylesheet) {
+        self.stylesheets.push(stylesheet);
+        self.set_dirty(self.root, true);
+    }
 
-        // Add child to parent's children list
-        if let Some(children) = self.children.get_mut(parent_key) {
-            children.push(child_id);
+    pub fn get_element(&self, id: NodeId) -> Option<&Element> {
+        match self.content.get(id.into()) {
+            Some(NodeContent::Element(element)) => Some(element),
+            _ => None,
         }
-        
+    }
 
-        self.parents.insert(child_key, Some(parent_id));
+    /// Sets an attribute; `id` and `class` set the element's id and class list.
+    pub fn set_attribute(&mut self, node_id: NodeId, name: &str, value: &str) {
+        self.update_element(node_id, name, |element| {
+            element.set_attribute(name, value);
+            true
+        });
+    }
+
+    pub fn remove_attribute(&mut self, node_id: NodeId, name: &str) -> bool {
+        self.update_element(node_id, name, |element| element.remove_attribute(name))
+    }
+
+    pub fn get_attribute(&self, node_id: NodeId, name: &str) -> Option<Cow<'_, str>> {
+        self.get_element(node_id)?.get_attribute(name)
+    }
+
+    pub fn set_id(&mut self, node_id: NodeId, id: &str) {
+        self.set_attribute(node_id, "id", id);
+    }
+
+    /// Returns false when the element already had the class.
+    pub fn add_class(&mut self, node_id: NodeId, class: &str) -> bool {
+        self.update_element(node_id, "class", |element| element.class_list.add(class))
+    }
+
+    /// Returns false when the element didn't have the class.
+    pub fn remove_class(&mut self, node_id: NodeId, class: &str) -> bool {
+        self.update_element(node_id, "class", |element| element.class_list.remove(class))
+    }
+
+    /// Returns whether the element has the class afterwards.
+    pub fn toggle_class(&mut self, node_id: NodeId, class: &str) -> bool {
+        self.update_element(node_id, "class", |element| {
+            element.class_list.toggle(class);
+            true
+        });
+        self.has_class(node_id, class)
+    }
+
+    pub fn has_class(&self, node_id: NodeId, class: &str) -> bool {
+        self.get_element(node_id).is_some_and(|element| element.class_list.contains(class))
+    }
+
+    /// Changes an element and marks it for restyling when `update` reports a change, since
+    /// selectors may now match it differently. Observers see the change as one to `attribute`.
+    /// Returns what `update` did, or false for non-elements.
+    fn update_element(&mut self, node_id: NodeId, attribute: &str, update: impl FnOnce(&mut Element) -> bool) -> bool {
+        let is_observed = self.is_observed();
+        let Some(NodeContent::Element(element)) = self.content.get_mut(node_id.into()) else {
+            return false;
+        };
+        let old_value = if is_observed { element.get_attribute(attribute).map(Cow::into_owned) } else { None };
+        let changed = update(element);
+        if changed {
+            self.set_dirty(node_id, true);
+            self.queue_mutation(node_id, MutationKind::Attribute { name: attribute.to_string(), old_value });
+        }
+        changed
+    }
+
+    /// Replaces a text node's content. Returns false for elements.
+    pub fn set_text_content(&mut self, node_id: NodeId, content: impl Into<String>) -> bool {
+        let Some(NodeContent::Text(text)) = self.content.get_mut(node_id.into()) else {
+            return false;
+        };
+        let old_value = std::mem::replace(&mut text.content, content.into());
+        self.invalidate_text(node_id);
+        self.queue_mutation(node_id, MutationKind::Text { old_value: Some(old_value) });
+        true
     }
 
     pub fn is_element(&self, id: NodeId) -> bool {
@@ -235,7 +348,7 @@ impl Dom {
                 
                 // Create and append a new text node
                 let text_node_id = self.create_text_node(text);
-                self.append_child(element_id, text_node_id);
+                self.append_child(element_id, text_node_id).expect("just checked");
                 
             }
             Some(NodeContent::Text(_)) => {},
@@ -287,33 +400,210 @@ impl Dom {
         }
         
         // Remove from parent's children list
-        if let Some(Some(parent_id)) = self.parents.get(key).cloned() {
-            if let Some(children) = self.children.get_mut(parent_id.into()) {
-                children.retain(|&id| id != node_id);
-            }
-            self.set_dirty(parent_id, true);
-        }
+        self.unlink(node_id);
         
-        // Remove all children recursively
-        if let Some(children) = self.children.get(key).cloned() {
-            for child_id in children {
-                self.remove_node(child_id);
-            }
+        // Free the whole subtree; observers only hear about its root leaving the tree
+        for id in self.collect_nodes_depth_first(node_id) {
+            self.free_node(id);
         }
-        
-        // Remove from storage
+        self.drop_freed_observers();
+    }
+
+    fn free_node(&mut self, node_id: NodeId) {
+        let key: slotmap::DefaultKey = node_id.into();
         self.nodes.remove(key);
         self.content.remove(key);
         self.styles.remove(key);
         self.computed_styles.remove(key);
         self.text_info.remove(key);
+        self.inline_fragments.remove(key);
         self.layout.remove(key);
+        self.static_positions.remove(key);
+        self.scroll_states.remove(key);
         self.dirty.borrow_mut().remove(key);
+        self.layout_dirty.borrow_mut().remove(key);
+        self.layout_cache.remove(key);
+        self.text_widths.remove(key);
+        self.post_layout_offsets.remove(key);
+        self.post_layout_subtrees.remove(key);
         self.children.remove(key);
         self.parents.remove(key);
         
     }
 
+    /// Inserts a node into `parent_id` just before `reference_id`, taking it out of its previous parent first.
+    pub fn insert_before(&mut self, parent_id: NodeId, child_id: NodeId, reference_id: NodeId) -> Result<(), DomError> {
+        let index = self.child_index(parent_id, reference_id)?;
+        self.insert_at(parent_id, child_id, index, Some(reference_id))
+    }
+
+    /// Inserts a node into `parent_id` just after `reference_id`, taking it out of its previous parent first.
+    pub fn insert_after(&mut self, parent_id: NodeId, child_id: NodeId, reference_id: NodeId) -> Result<(), DomError> {
+        let index = self.child_index(parent_id, reference_id)?;
+        self.insert_at(parent_id, child_id, index + 1, Some(reference_id))
+    }
+
+    pub fn prepend_child(&mut self, parent_id: NodeId, child_id: NodeId) -> Result<(), DomError> {
+        self.move_node(child_id, parent_id, 0)
+    }
+
+    /// Puts `new_child_id` where `old_child_id` was. The old child is detached, not freed, so it
+    /// can be inserted elsewhere or passed to `remove_node`.
+    pub fn replace_child(&mut self, parent_id: NodeId, new_child_id: NodeId, old_child_id: NodeId) -> Result<(), DomError> {
+        if new_child_id == old_child_id {
+            return self.child_index(parent_id, old_child_id).map(|_| ());
+        }
+        self.insert_before(parent_id, new_child_id, old_child_id)?;
+        self.detach(old_child_id)
+    }
+
+    /// Takes a node and its subtree out of the tree without freeing them.
+    pub fn detach(&mut self, node_id: NodeId) -> Result<(), DomError> {
+        self.check_exists(node_id)?;
+        if node_id == self.root {
+            return Err(DomError::RootNode);
+        }
+        self.unlink(node_id);
+        Ok(())
+    }
+
+    /// Moves a node to position `index` among the children of `new_parent_id`, counted as if the
+    /// node had already been taken out of its current place. Works for detached nodes too.
+    pub fn move_node(&mut self, node_id: NodeId, new_parent_id: NodeId, index: usize) -> Result<(), DomError> {
+        self.check_insertion(new_parent_id, node_id)?;
+        let len = self.children.get(new_parent_id.into()).map_or(0, |children| children.iter().filter(|&&id| id != node_id).count());
+        if index > len {
+            return Err(DomError::IndexOutOfBounds { index, len });
+        }
+        self.unlink(node_id);
+        self.link(new_parent_id, node_id, index);
+        Ok(())
+    }
+
+    /// Copies a node and its subtree, with their content and inline styles, into new detached nodes.
+    pub fn clone_subtree(&mut self, node_id: NodeId) -> Result<NodeId, DomError> {
+        self.check_exists(node_id)?;
+        let key: slotmap::DefaultKey = node_id.into();
+        let clone_id = match self.content.get(key).cloned() {
+            Some(NodeContent::Element(element)) => self.create_element(element),
+            Some(NodeContent::Text(text)) => self.create_text(text),
+            None => return Err(DomError::NodeNotFound(node_id)),
+        };
+        if let Some(style) = self.styles.get(key).cloned() {
+            self.styles.insert(clone_id.into(), style);
+        }
+
+        for child_id in self.children.get(key).cloned().unwrap_or_default() {
+            let child_clone_id = self.clone_subtree(child_id)?;
+            self.link(clone_id, child_clone_id, self.children.get(clone_id.into()).map_or(0, Vec::len));
+        }
+        Ok(clone_id)
+    }
+
+    /// Whether `ancestor_id` is `node_id` or one of its ancestors.
+    pub fn is_inclusive_ancestor(&self, ancestor_id: NodeId, node_id: NodeId) -> bool {
+        std::iter::successors(Some(node_id), |&id| self.parent(id)).any(|id| id == ancestor_id)
+    }
+
+    pub fn index_in_parent(&self, node_id: NodeId) -> Option<usize> {
+        let parent_id = self.parent(node_id)?;
+        self.children.get(parent_id.into())?.iter().position(|&id| id == node_id)
+    }
+
+    fn check_exists(&self, node_id: NodeId) -> Result<(), DomError> {
+        if self.nodes.contains_key(node_id.into()) { Ok(()) } else { Err(DomError::NodeNotFound(node_id)) }
+    }
+
+    /// Whether `parent_id` can have children: it has to exist and be an element.
+    pub(crate) fn check_parent(&self, parent_id: NodeId) -> Result<(), DomError> {
+        self.check_exists(parent_id)?;
+        if self.is_element(parent_id) { Ok(()) } else { Err(DomError::NotAnElement(parent_id)) }
+    }
+
+    fn check_insertion(&self, parent_id: NodeId, child_id: NodeId) -> Result<(), DomError> {
+        self.check_parent(parent_id)?;
+        self.check_exists(child_id)?;
+        if child_id == self.root {
+            return Err(DomError::RootNode);
+        }
+        if self.is_inclusive_ancestor(child_id, parent_id) {
+            return Err(DomError::HierarchyCycle(child_id));
+        }
+        Ok(())
+    }
+
+    fn child_index(&self, parent_id: NodeId, child_id: NodeId) -> Result<usize, DomError> {
+        self.check_exists(parent_id)?;
+        self.check_exists(child_id)?;
+        self.children.get(parent_id.into()).and_then(|children| children.iter().position(|&id| id == child_id)).ok_or(DomError::NotAChild { parent: parent_id, child: child_id })
+    }
+
+    /// Like `move_node`, but positioned relative to a sibling, which must not be the node itself.
+    fn insert_at(&mut self, parent_id: NodeId, child_id: NodeId, index: usize, reference_id: Option<NodeId>) -> Result<(), DomError> {
+        if reference_id == Some(child_id) {
+            return self.check_insertion(parent_id, child_id);
+        }
+        // Taking the node out first shifts the reference left when the node came before it
+        let shift = match self.index_in_parent(child_id) {
+            Some(current) if self.parent(child_id) == Some(parent_id) && current < index => 1,
+            _ => 0,
+        };
+        self.move_node(child_id, parent_id, index - shift)
+    }
+
+    /// Takes a node out of its parent's children. The siblings after it now have a different
+    /// previous sibling, which sibling selectors can see, and the parent has a box less to lay out.
+    fn unlink(&mut self, node_id: NodeId) {
+        let Some(parent_id) = self.parent(node_id) else {
+            return;
+        };
+        if self.is_observed() {
+            let change = MutationKind::ChildList {
+                added: Vec::new(),
+                removed: vec![node_id],
+                previous_sibling: self.previous_sibling(node_id),
+                next_sibling: self.next_sibling(node_id),
+            };
+            self.queue_mutation(parent_id, change);
+        }
+        if let Some(index) = self.index_in_parent(node_id) {
+            if let Some(children) = self.children.get_mut(parent_id.into()) {
+                children.remove(index);
+            }
+            self.mark_next_sibling_dirty(parent_id, index);
+        }
+        self.parents.insert(node_id.into(), None);
+        self.mark_layout_dirty(parent_id);
+    }
+
+    fn link(&mut self, parent_id: NodeId, child_id: NodeId, index: usize) {
+        if let Some(children) = self.children.get_mut(parent_id.into()) {
+            children.insert(index, child_id);
+        }
+        self.parents.insert(child_id.into(), Some(parent_id));
+
+        // The child now inherits from and matches selectors against a new parent, and the parent has a new box to lay out
+        self.set_dirty(child_id, true);
+        self.mark_next_sibling_dirty(parent_id, index + 1);
+        self.mark_layout_dirty(parent_id);
+
+        if self.is_observed() {
+            let change = MutationKind::ChildList {
+                added: vec![child_id],
+                removed: Vec::new(),
+                previous_sibling: self.previous_sibling(child_id),
+                next_sibling: self.next_sibling(child_id),
+            };
+            self.queue_mutation(parent_id, change);
+        }
+    }
+
+    fn mark_next_sibling_dirty(&self, parent_id: NodeId, index: usize) {
+        if let Some(&sibling_id) = self.children.get(parent_id.into()).and_then(|children| children.get(index)) {
+            self.set_dirty(sibling_id, true);
+        }
+    }
+
     pub fn create_text_node(&mut self, text: String) -> NodeId {
         let key = self.nodes.insert(());
         let node_id = NodeId(key);
@@ -326,3 +616,101 @@ impl Dom {
         node_id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A root with children `a`, `b` and `c`, where `a` has a child `d`.
+    fn tree() -> (Dom, [NodeId; 4]) {
+        let mut dom = Dom::new();
+        let root = dom.root;
+        let [a, b, c] = [(); 3].map(|_| dom.append_new_element(root, Element::new()).unwrap());
+        let d = dom.append_new_element(a, Element::new()).unwrap();
+        (dom, [a, b, c, d])
+    }
+
+    fn children(dom: &Dom, node_id: NodeId) -> Vec<NodeId> {
+        dom.child_nodes(node_id).collect()
+    }
+
+    #[test]
+    fn append_child_reports_why_it_refused() {
+        let (mut dom, [a, b, c, d]) = tree();
+        let root = dom.root;
+        assert_eq!(dom.append_child(d, a), Err(DomError::HierarchyCycle(a)));
+        assert_eq!(dom.append_child(a, a), Err(DomError::HierarchyCycle(a)));
+        assert_eq!(dom.append_child(a, root), Err(DomError::RootNode));
+        let text = dom.create_text_node("x".to_string());
+        assert_eq!(dom.append_child(text, b), Err(DomError::NotAnElement(text)));
+        dom.remove_node(c);
+        assert_eq!(dom.append_child(root, c), Err(DomError::NodeNotFound(c)));
+        assert_eq!(children(&dom, root), [a, b]);
+
+        // Appending a child again moves it to the end
+        assert_eq!(dom.append_child(root, a), Ok(()));
+        assert_eq!(children(&dom, root), [b, a]);
+    }
+
+    #[test]
+    fn insert_before_and_replace_child_keep_the_order() {
+        let (mut dom, [a, b, c, d]) = tree();
+        let root = dom.root;
+        dom.insert_before(root, c, a).unwrap();
+        assert_eq!(children(&dom, root), [c, a, b]);
+        dom.insert_before(root, c, b).unwrap();
+        assert_eq!(children(&dom, root), [a, c, b]);
+        dom.insert_before(root, d, c).unwrap();
+        assert_eq!(children(&dom, root), [a, d, c, b]);
+        assert_eq!(children(&dom, a), []);
+        assert_eq!(dom.insert_before(a, b, c), Err(DomError::NotAChild { parent: a, child: c }));
+
+        dom.replace_child(root, b, d).unwrap();
+        assert_eq!(children(&dom, root), [a, b, c]);
+        // The replaced node is detached, not freed
+        assert_eq!(dom.parent(d), None);
+        assert!(dom.append_child(a, d).is_ok());
+        assert_eq!(dom.replace_child(root, b, b), Ok(()));
+        assert_eq!(dom.replace_child(a, b, c), Err(DomError::NotAChild { parent: a, child: c }));
+        assert_eq!(children(&dom, root), [a, b, c]);
+    }
+
+    #[test]
+    fn detach_and_move_node_refuse_invalid_moves() {
+        let (mut dom, [a, b, c, d]) = tree();
+        let root = dom.root;
+        assert_eq!(dom.detach(root), Err(DomError::RootNode));
+        dom.detach(a).unwrap();
+        assert_eq!(children(&dom, root), [b, c]);
+        assert_eq!(dom.parent(a), None);
+        assert_eq!(dom.parent(d), Some(a));
+
+        assert_eq!(dom.move_node(a, d, 0), Err(DomError::HierarchyCycle(a)));
+        assert_eq!(dom.move_node(a, root, 3), Err(DomError::IndexOutOfBounds { index: 3, len: 2 }));
+        dom.move_node(a, root, 1).unwrap();
+        assert_eq!(children(&dom, root), [b, a, c]);
+        // The index counts the children without the node being moved
+        dom.move_node(b, root, 2).unwrap();
+        assert_eq!(children(&dom, root), [a, c, b]);
+    }
+
+    #[test]
+    fn clone_subtree_copies_content_and_styles() {
+        let (mut dom, [a, _, _, d]) = tree();
+        dom.set_width(a, 40.0);
+        dom.set_bg_color(d, Color::new(1.0, 0.0, 0.0, 1.0));
+        let text = dom.create_text_node("hello".to_string());
+        dom.append_child(d, text).unwrap();
+
+        let clone = dom.clone_subtree(a).unwrap();
+        assert_eq!(dom.parent(clone), None);
+        assert_eq!(dom.styles[clone.into()], dom.styles[a.into()]);
+        let cloned_d = dom.first_child(clone).unwrap();
+        assert_ne!(cloned_d, d);
+        assert_eq!(dom.styles[cloned_d.into()].bg_color, Some(Color::new(1.0, 0.0, 0.0, 1.0)));
+        let cloned_text = dom.first_child(cloned_d).unwrap();
+        assert!(matches!(&dom.content[cloned_text.into()], NodeContent::Text(text) if text.content == "hello"));
+        // The original subtree is untouched
+        assert_eq!(children(&dom, d), [text]);
+    }
+}
@@ -1,4 +1,9 @@
-use crate::dom::styleengine::{AlignContent, AlignItems, AlignSelf, BorderStyle, BoxModelValues, BoxSizing, Color, ComputedStyle, Display, Style, FlexDirection, FlexWrap, Float, JustifyContent, Position, PositionOffsets, Font};
+use std::collections::BTreeMap;
+
+use crate::dom::grid::{GridAutoFlow, GridLine, GridPlacement, GridTemplateAreas, TrackList, TrackSize};
+use crate::dom::layoutengine::EdgeSizes;
+use crate::dom::length::Length;
+use crate::dom::styleengine::{AlignContent, AlignItems, AlignSelf, BorderStyle, BoxModelValues, BoxSizing, Color, ComputedStyle, Display, Style, FlexDirection, FlexWrap, Float, JustifyContent, LineHeight, Overflow, Position, PositionOffsets, Font, FontSlant, FontWeight, Size, TextAlign, VerticalAlign, WhiteSpace};
 
 impl Default for BoxSizing {
     fn default() -> Self {
@@ -28,10 +33,34 @@ impl Default for ComputedStyle {
             flex_wrap: FlexWrap::default(),
             align_self: AlignSelf::default(),
             flex_grow: 0.0,
-            flex_shrink: 0.0,
+            flex_shrink: 1.0,
+            flex_basis: Size::Auto,
+            order: 0,
+            row_gap: Length::ZERO,
+            column_gap: Length::ZERO,
+            grid_template_columns: TrackList::default(),
+            grid_template_rows: TrackList::default(),
+            grid_template_areas: GridTemplateAreas::default(),
+            grid_auto_columns: TrackSize::AUTO,
+            grid_auto_rows: TrackSize::AUTO,
+            grid_auto_flow: GridAutoFlow::default(),
+            grid_column: GridPlacement::default(),
+            grid_row: GridPlacement::default(),
+            overflow_x: Overflow::Visible,
+            overflow_y: Overflow::Visible,
+            vertical_align: VerticalAlign::Baseline,
+            line_height: LineHeight::Normal,
+            text_align: TextAlign::Start,
+            text_indent: Length::Px(0.0),
+            letter_spacing: Length::Px(0.0),
+            word_spacing: Length::Px(0.0),
             color: Color::WHITE,
-            font_family: "Arial".to_owned(),
-            font_size: 16.0
+            font_family: vec!["Arial".to_owned()],
+            font_size: 16.0,
+            font_weight: FontWeight::NORMAL,
+            font_style: FontSlant::Normal,
+            font_stretch: 100.0,
+            white_space: WhiteSpace::Normal,
         }
     }
 }
@@ -61,6 +90,28 @@ impl Default for Style {
             align_self: None,
             flex_grow: None,
             flex_shrink: None,
+            white_space: None,
+            flex_basis: None,
+            order: None,
+            row_gap: None,
+            column_gap: None,
+            grid_template_columns: None,
+            grid_template_rows: None,
+            grid_template_areas: None,
+            grid_auto_columns: None,
+            grid_auto_rows: None,
+            grid_auto_flow: None,
+            grid_column: None,
+            grid_row: None,
+            overflow_x: None,
+            overflow_y: None,
+            vertical_align: None,
+            line_height: None,
+            text_align: None,
+            text_indent: None,
+            letter_spacing: None,
+            word_spacing: None,
+            keywords: BTreeMap::new(),
         }
     }
 }
@@ -71,6 +122,9 @@ impl Default for Font {
             color: None, // Will default to white in computed text style
             font_size: None,
             font_family: None,
+            font_weight: None,
+            font_style: None,
+            font_stretch: None,
         }
     }
 }
@@ -90,19 +144,19 @@ impl Default for FlexDirection {
 
 impl Default for JustifyContent {
     fn default() -> Self {
-        JustifyContent::Center
+        JustifyContent::FlexStart
     }
 }
 
 impl Default for AlignItems {
     fn default() -> Self {
-        AlignItems::Center
+        AlignItems::Stretch
     }
 }
 
 impl Default for AlignContent {
     fn default() -> Self {
-        AlignContent::Center
+        AlignContent::Stretch
     }
 }
 
@@ -119,7 +173,8 @@ impl Default for AlignSelf {
 }
 
 impl BoxModelValues{
-    pub fn all(value: f32) -> Self {
+    pub fn all(value: impl Into<Length>) -> Self {
+        let value = value.into();
         Self {
             top: Some(value),
             right: Some(value),
@@ -128,48 +183,35 @@ impl BoxModelValues{
         }
     }
 
-    pub fn top(mut self, value: f32) -> Self {
-        self.top = Some(value);
+    pub fn top(mut self, value: impl Into<Length>) -> Self {
+        self.top = Some(value.into());
         self
     }
     
-    pub fn right(mut self, value: f32) -> Self {
-        self.right = Some(value);
+    pub fn right(mut self, value: impl Into<Length>) -> Self {
+        self.right = Some(value.into());
         self
     }
     
-    pub fn bottom(mut self, value: f32) -> Self {
-        self.bottom = Some(value);
+    pub fn bottom(mut self, value: impl Into<Length>) -> Self {
+        self.bottom = Some(value.into());
         self
     }
     
-    pub fn left(mut self, value: f32) -> Self {
-        self.left = Some(value);
+    pub fn left(mut self, value: impl Into<Length>) -> Self {
+        self.left = Some(value.into());
         self
     }
 
-    pub fn horizontal_padding(&self) -> f32 {
-        self.left.unwrap_or(0.0) + self.right.unwrap_or(0.0)
-    }
-    
-    pub fn vertical_padding(&self) -> f32 {
-        self.top.unwrap_or(0.0) + self.bottom.unwrap_or(0.0)
-    }
-
-    pub fn get_right(&self) -> f32 {
-        self.right.unwrap_or(0.0)
-    }
-    
-    pub fn get_bottom(&self) -> f32 {
-        self.bottom.unwrap_or(0.0)
-    }
-    
-    pub fn get_left(&self) -> f32 {
-        self.left.unwrap_or(0.0)
-    }
-    
-    pub fn get_top(&self) -> f32 {
-        self.top.unwrap_or(0.0)
+    /// Used sizes in pixels. Percentages on every side refer to the containing block's width.
+    pub fn resolve(&self, containing_width: f32) -> EdgeSizes {
+        let side = |value: Option<Length>| value.map_or(0.0, |value| value.resolve(containing_width));
+        EdgeSizes {
+            top: side(self.top),
+            right: side(self.right),
+            bottom: side(self.bottom),
+            left: side(self.left),
+        }
     }
 }
 
@@ -190,4 +232,21 @@ impl Color {
     pub const TRANSPARENT: Self = Self { r: 0.0, g: 0.0, b: 0.0, a: 0.0 };
     pub const WHITE: Self = Self { r: 1.0, g: 1.0, b: 1.0, a: 1.0 };
     pub const YELLOW: Self = Self { r: 1.0, g: 1.0, b: 0.0, a: 1.0 };
-}
\ No newline at end of file
+}
+impl Default for GridAutoFlow {
+    fn default() -> Self {
+        GridAutoFlow::Row
+    }
+}
+
+impl Default for GridLine {
+    fn default() -> Self {
+        GridLine::Auto
+    }
+}
+
+impl Default for TrackSize {
+    fn default() -> Self {
+        TrackSize::AUTO
+    }
+}
@@ -1,7 +1,36 @@
-use serde::{de::Error, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+use serde::{de::Error, Deserialize, Serialize};
 use serde_json::{Map, Value};
 
-use crate::dom::{dom::{NodeContent, NodeId}, styleengine::{BorderSide, BorderStyle, BoxSizing, Color}, Dom};
+use crate::dom::{dom::{NodeContent, NodeId}, element::{ClassList, Element}, styleengine::Style, text::Text, Dom};
+
+/// A node and its subtree as `Dom::to_json` saves them and `Dom::from_json` loads them back:
+/// structure, element names, attributes, inline styles and text. Computed styles and layout are
+/// left out, since they are recomputed after loading.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SerializedNode {
+    Element {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        class_list: Vec<String>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        attributes: BTreeMap<String, String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        style: Option<Style>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        children: Vec<SerializedNode>,
+    },
+    Text {
+        text_content: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        style: Option<Style>,
+    },
+}
 
 impl Dom{
     pub fn node_to_json_value(&self, node_id: NodeId) -> Result<Value, serde_json::Error> {
@@ -19,6 +48,15 @@ impl Dom{
                 if let Some(name) = &e.name {
                     result.insert("name".to_string(), Value::String(name.clone()));
                 }
+                if let Some(id) = &e.id {
+                    result.insert("id".to_string(), Value::String(id.clone()));
+                }
+                if !e.class_list.is_empty() {
+                    result.insert("class_list".to_string(), Value::Array(e.class_list.iter().map(|class| Value::String(class.to_string())).collect()));
+                }
+                if !e.attributes.is_empty() {
+                    result.insert("attributes".to_string(), serde_json::to_value(&e.attributes)?);
+                }
                 }
                 NodeContent::Text(t) => {
                     result.insert("type".to_string(), Value::String("text".to_string()));
@@ -69,64 +107,133 @@ impl Dom{
         
         serde_json::to_string_pretty(current)
     }
-}
 
-impl Serialize for BoxSizing {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        match self {
-            BoxSizing::ContentBox => serializer.serialize_str("content-box"),
-            BoxSizing::BorderBox => serializer.serialize_str("border-box"),
-            BoxSizing::Inherit => serializer.serialize_str("Inherit"),
+    /// The subtree at `node_id` in its saved form.
+    pub fn serialize_subtree(&self, node_id: NodeId) -> Option<SerializedNode> {
+        let key: slotmap::DefaultKey = node_id.into();
+        // Only inline styles that set something are saved
+        let style = self.styles.get(key).filter(|style| **style != Style::default()).cloned();
+        match self.content.get(key)? {
+            NodeContent::Element(element) => Some(SerializedNode::Element {
+                name: element.name.clone(),
+                id: element.id.clone(),
+                class_list: element.class_list.iter().map(str::to_string).collect(),
+                attributes: element.attributes.clone(),
+                style,
+                children: self.children(node_id).into_iter().flatten().filter_map(|&child_id| self.serialize_subtree(child_id)).collect(),
+            }),
+            NodeContent::Text(text) => Some(SerializedNode::Text { text_content: text.content.clone(), style }),
         }
     }
-}
-
-impl Serialize for BorderStyle {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        use serde::ser::SerializeMap;
 
-        let mut map = serializer.serialize_map(None)?;
+    /// The whole tree as JSON, starting at the root.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&self.serialize_subtree(self.root))
+    }
 
-        if let Some(top) = &self.top {
-            map.serialize_entry("top", top)?;
-        }
-        if let Some(right) = &self.right {
-            map.serialize_entry("right", right)?;
+    /// Builds a new `Dom` from JSON written by `to_json`. The saved root, which must be an
+    /// element, becomes the root of the new tree.
+    pub fn from_json(json: &str) -> Result<Dom, serde_json::Error> {
+        let SerializedNode::Element { name, id, class_list, attributes, style, children } = serde_json::from_str(json)? else {
+            return Err(serde_json::Error::custom("the root node must be an element"));
+        };
+        let mut dom = Dom::new();
+        let root_id = dom.root;
+        dom.content.insert(root_id.into(), NodeContent::Element(Element { name, id, class_list: collect_class_list(class_list), attributes }));
+        if let Some(style) = style {
+            dom.replace_style(root_id, style);
         }
-        if let Some(bottom) = &self.bottom {
-            map.serialize_entry("bottom", bottom)?;
-        }
-        if let Some(left) = &self.left {
-            map.serialize_entry("left", left)?;
+        for child in &children {
+            let child_id = dom.build_serialized(child);
+            dom.append_child(root_id, child_id).expect("a new element takes any new node");
         }
+        Ok(dom)
+    }
+
+    /// Loads a subtree saved with `serialize_subtree` and appends it to `parent_id`'s children, which must be an element.
+    pub fn load_json(&mut self, parent_id: NodeId, json: &str) -> Result<NodeId, serde_json::Error> {
+        let node: SerializedNode = serde_json::from_str(json)?;
+        let node_id = self.build_serialized(&node);
+        let appended = self.append_child(parent_id, node_id);
+        debug_assert!(appended.is_ok(), "can't load JSON into {:?}: {:?}", parent_id, appended);
+        Ok(node_id)
+    }
 
-        map.end()
+    /// Creates detached nodes for a saved subtree.
+    pub fn build_serialized(&mut self, node: &SerializedNode) -> NodeId {
+        match node {
+            SerializedNode::Element { name, id, class_list, attributes, style, children } => {
+                let element = Element { name: name.clone(), id: id.clone(), class_list: collect_class_list(class_list.clone()), attributes: attributes.clone() };
+                let node_id = self.create_element(element);
+                if let Some(style) = style {
+                    self.replace_style(node_id, style.clone());
+                }
+                for child in children {
+                    let child_id = self.build_serialized(child);
+                    self.append_child(node_id, child_id).expect("a new element takes any new node");
+                }
+                node_id
+            }
+            SerializedNode::Text { text_content, style } => {
+                let node_id = self.create_text(Text::new(text_content.clone()));
+                if let Some(style) = style {
+                    self.replace_style(node_id, style.clone());
+                }
+                node_id
+            }
+        }
     }
 }
 
-impl Serialize for BorderSide {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let border_str = format!("width:{:.1}, color:rgba({:.2}, {:.2}, {:.2}, {:.2})", self.width, self.color.r, self.color.g, self.color.b, self.color.a);
-        serializer.serialize_str(&border_str)
+fn collect_class_list(classes: Vec<String>) -> ClassList {
+    let mut class_list = ClassList::default();
+    for class in classes {
+        class_list.add(&class);
     }
+    class_list
 }
 
-impl Serialize for Color {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        // Format as a compact string: "rgba(r, g, b, a)"
-        let color_str = format!("rgba({:.2}, {:.2}, {:.2}, {:.2})", self.r, self.g, self.b, self.a);
-        serializer.serialize_str(&color_str)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::traversal::NodeKind;
+
+    fn styled_tree() -> Dom {
+        let html = r#"<div id="main" class="a b" data-x="1" style="margin: 1px 2px; border-left: 2px solid #ff0000; border-top: none"><p title="t">hello <span class="c">world</span></p></div>"#;
+        let mut dom = Dom::from_html(html).unwrap();
+        let text = dom.descendants(dom.root).find(|&node_id| dom.node_kind(node_id) == Some(NodeKind::Text)).unwrap();
+        dom.set_style(text, Style::from_css("font-size: 20px; font-weight: bold; color: #00ff00").unwrap());
+        dom
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn saved_trees_load_back_unchanged() {
+        let dom = styled_tree();
+        let saved = dom.serialize_subtree(dom.root).unwrap();
+        let SerializedNode::Element { children, .. } = &saved else {
+            panic!("the root is an element");
+        };
+        let SerializedNode::Element { class_list, attributes, style: Some(style), .. } = &children[0] else {
+            panic!("the div keeps its classes, attributes and style");
+        };
+        assert_eq!((class_list.len(), attributes.get("data-x").map(String::as_str)), (2, Some("1")));
+        assert!(style.border.as_ref().is_some_and(|border| border.top.is_some() && border.left.is_some() && border.right.is_none()));
+
+        let loaded = Dom::from_json(&dom.to_json().unwrap()).unwrap();
+        assert_eq!(loaded.serialize_subtree(loaded.root), Some(saved));
+    }
+
+    #[test]
+    fn loaded_subtrees_are_appended_to_the_parent() {
+        let dom = styled_tree();
+        let main = dom.get_element_by_id("main").unwrap();
+        let json = serde_json::to_string(&dom.serialize_subtree(main)).unwrap();
+
+        let mut other = Dom::new();
+        let root = other.root;
+        let copy = other.load_json(root, &json).unwrap();
+        assert_eq!(other.parent(copy), Some(root));
+        assert_eq!(other.serialize_subtree(copy), dom.serialize_subtree(main));
+        assert!(other.load_json(root, "{\"type\": \"comment\"}").is_err());
+    }
+}
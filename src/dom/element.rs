@@ -1,19 +1,140 @@
 // /src/dom/element.rs
 
+use std::{borrow::Cow, collections::BTreeMap, fmt};
+
 use crate::dom::{dom::NodeId, styleengine::Style, Dom};
 
 #[derive(Debug, Clone)]
 pub struct Element {
     pub name: Option<String>,
+    pub id: Option<String>,
+    pub class_list: ClassList,
+    /// Attributes other than `id` and `class`, which have fields of their own.
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// An element's classes in the order they were added, without duplicates.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassList(Vec<String>);
+
+impl ClassList {
+    /// Parses a `class` attribute value: class names separated by whitespace.
+    pub fn parse(value: &str) -> Self {
+        let mut class_list = Self::default();
+        for class in value.split_whitespace() {
+            class_list.add(class);
+        }
+        class_list
+    }
+
+    /// Returns false when the class was already there.
+    pub fn add(&mut self, class: &str) -> bool {
+        if self.contains(class) {
+            return false;
+        }
+        self.0.push(class.to_string());
+        true
+    }
+
+    /// Returns false when the class wasn't there.
+    pub fn remove(&mut self, class: &str) -> bool {
+        let len = self.0.len();
+        self.0.retain(|c| c != class);
+        self.0.len() != len
+    }
+
+    /// Adds the class if it's missing and removes it otherwise. Returns whether it's there now.
+    pub fn toggle(&mut self, class: &str) -> bool {
+        if self.remove(class) {
+            return false;
+        }
+        self.add(class)
+    }
+
+    pub fn contains(&self, class: &str) -> bool {
+        self.0.iter().any(|c| c == class)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Display for ClassList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.join(" "))
+    }
 }
 
 impl Element {
     pub fn new() -> Self {
-        Self { name: None }
+        Self {
+            name: None,
+            id: None,
+            class_list: ClassList::default(),
+            attributes: BTreeMap::new(),
+        }
     }
 
     pub fn with_name(mut self, name: &str) -> Self {
         self.name = Some(name.to_string());
         self
     }
-}
\ No newline at end of file
+
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.id = Some(id.to_string());
+        self
+    }
+
+    pub fn with_class(mut self, class: &str) -> Self {
+        self.class_list.add(class);
+        self
+    }
+
+    pub fn with_attribute(mut self, name: &str, value: &str) -> Self {
+        self.attributes.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Looks up an attribute by name, treating `id` and `class` as attributes like HTML does.
+    pub fn get_attribute(&self, name: &str) -> Option<Cow<'_, str>> {
+        match name {
+            "id" => self.id.as_deref().map(Cow::Borrowed),
+            "class" if !self.class_list.is_empty() => Some(Cow::Owned(self.class_list.to_string())),
+            "class" => None,
+            _ => self.attributes.get(name).map(|value| Cow::Borrowed(value.as_str())),
+        }
+    }
+
+    /// Sets an attribute, with `id` and `class` going to their own fields.
+    pub fn set_attribute(&mut self, name: &str, value: &str) {
+        match name {
+            "id" => self.id = Some(value.to_string()),
+            "class" => self.class_list = ClassList::parse(value),
+            _ => {
+                self.attributes.insert(name.to_string(), value.to_string());
+            }
+        }
+    }
+
+    /// Returns false when the attribute wasn't set.
+    pub fn remove_attribute(&mut self, name: &str) -> bool {
+        match name {
+            "id" => self.id.take().is_some(),
+            "class" => !std::mem::take(&mut self.class_list).is_empty(),
+            _ => self.attributes.remove(name).is_some(),
+        }
+    }
+
+    pub fn has_attribute(&self, name: &str) -> bool {
+        self.get_attribute(name).is_some()
+    }
+}
@@ -7,6 +7,8 @@ pub enum MouseEventType {
     Enter,
     Leave,
     Click,
+    // Fired on the scroll container a wheel event scrolled
+    Scroll,
     // Add more event types as needed
 }
 
@@ -48,6 +50,7 @@ impl EventSystem {
             MouseEventType::Enter => "mouseenter",
             MouseEventType::Leave => "mouseleave",
             MouseEventType::Click => "click",
+            MouseEventType::Scroll => "scroll",
         };
         
         if let Some(listeners) = self.event_listeners.get(event_name) {
@@ -97,6 +100,22 @@ impl EventSystem {
         }
     }
     
+    // Scrolls whatever is under the pointer by a wheel delta in pixels; returns whether anything moved
+    pub fn process_mouse_wheel(&mut self, dom: &mut Dom, x: f32, y: f32, delta_x: f32, delta_y: f32) -> bool {
+        match dom.scroll_at_position(x, y, delta_x, delta_y) {
+            Some(node_id) => {
+                self.trigger_event(MouseEvent {
+                    event_type: MouseEventType::Scroll,
+                    node_id,
+                    x,
+                    y,
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
     // Add this method to get the currently hovered node
     pub fn get_hovered_node(&self) -> Option<NodeId> {
         self.current_hover
@@ -1,227 +1,278 @@
 // /src/dom/flex.rs
 
-use super::dom::{Dom, NodeId};
-use super::layoutengine::Rect;
-use super::styleengine::{
-    AlignContent, AlignItems, AlignSelf, ComputedElementStyle, FlexDirection, JustifyContent, Size,
-};
+use super::dom::{Dom, NodeContent, NodeId};
+use super::layoutengine::{resolve_size, EdgeSizes, LayoutData, Rect};
+use super::styleengine::{AlignContent, AlignItems, AlignSelf, BoxSizing, ComputedStyle, Display, FlexDirection, FlexWrap, Float, JustifyContent, Size};
 
 /// A temporary structure to hold information about a flex item during layout.
 /// This helps avoid borrow-checker issues with the DOM.
 struct FlexItem {
     node_id: NodeId,
-    style: ComputedElementStyle,
-    // The final computed main and cross sizes of the item
+    style: ComputedStyle,
+    margin: EdgeSizes,
+    // Sizes are border-box sizes
+    flex_base_size: f32,
+    min_main_size: f32,
     main_size: f32,
     cross_size: f32,
-    // The final computed position of the item relative to the container's content box
-    x: f32,
-    y: f32,
+    // Set once the item's main size is final while resolving flexible lengths
+    frozen: bool,
+    // Whether the cross size came from `align-self: stretch` rather than the item itself
+    stretched: bool,
+    // Distance from the item's cross-start margin edge to its first baseline (rows only)
+    baseline: f32,
+    // Border-box position relative to the container's content box
+    main_position: f32,
+    cross_position: f32,
 }
 
 /// Represents a single line in a flex container.
 struct FlexLine {
     items: Vec<FlexItem>,
-    main_size: f32,
     cross_size: f32,
+    cross_position: f32,
+}
+
+impl FlexItem {
+    fn main_margins(&self, is_row: bool) -> (f32, f32) {
+        if is_row { (self.margin.left, self.margin.right) } else { (self.margin.top, self.margin.bottom) }
+    }
+
+    fn cross_margins(&self, is_row: bool) -> (f32, f32) {
+        if is_row { (self.margin.top, self.margin.bottom) } else { (self.margin.left, self.margin.right) }
+    }
+
+    fn outer_main_size(&self, is_row: bool) -> f32 {
+        let (start, end) = self.main_margins(is_row);
+        self.main_size + start + end
+    }
+
+    fn outer_cross_size(&self, is_row: bool) -> f32 {
+        let (start, end) = self.cross_margins(is_row);
+        self.cross_size + start + end
+    }
+
+    fn alignment(&self, container: &ComputedStyle) -> AlignItems {
+        if self.style.align_self == AlignSelf::Auto { container.align_items } else { self.style.align_self.into() }
+    }
 }
 
 impl Dom {
     /// Lays out a node with `display: flex`.
-    pub fn layout_flex_node(
-        &mut self,
-        node_id: NodeId,
-        available_space: Rect,
-        element_style: &ComputedElementStyle,
-    ) -> Rect {
+    pub fn layout_flex_node(&mut self, node_id: NodeId, available_space: Rect, element_style: &ComputedStyle, child_ids: &[NodeId]) -> Rect {
         let key = node_id.into();
+        let is_row = matches!(element_style.flex_direction, FlexDirection::Row | FlexDirection::RowReverse);
+        let is_reverse = matches!(element_style.flex_direction, FlexDirection::RowReverse | FlexDirection::ColumnReverse);
+        let is_wrap = element_style.flex_wrap != FlexWrap::NoWrap;
 
         // --- 1. Calculate Container's Content Box ---
-        // This is the inner area where flex items will be placed.
-        let padding = &element_style.padding;
-        let border = &element_style.border;
-        let border_left = border.left.map_or(0.0, |b| b.width);
-        let border_right = border.right.map_or(0.0, |b| b.width);
-        let border_top = border.top.map_or(0.0, |b| b.width);
-        let border_bottom = border.bottom.map_or(0.0, |b| b.width);
-
-        let container_width = match element_style.width {
-            Some(Size::Points(w)) => w,
-            Some(Size::Percent(p)) => available_space.width * p / 100.0,
-            _ => available_space.width,
-        };
+        let border = element_style.border.widths();
+        let padding = element_style.padding.resolve(available_space.width);
+        let margin = element_style.margin.resolve(available_space.width);
+        let horizontal_edges = border.horizontal() + padding.horizontal();
+        let vertical_edges = border.vertical() + padding.vertical();
+        let is_border_box = element_style.box_sizing == BoxSizing::BorderBox;
 
-        // The content area for the flex items
-        let content_area_width = container_width
-            - padding.left
-            - padding.right
-            - border_left
-            - border_right;
-        let content_area_height = match element_style.height {
-            Some(Size::Points(h)) => h - padding.top - padding.bottom - border_top - border_bottom,
-            Some(Size::Percent(p)) => (available_space.height * p / 100.0) - padding.top - padding.bottom - border_top - border_bottom,
-            _ => f32::INFINITY, // Auto height initially
-        };
+        let content_width = match resolve_size(&element_style.width, available_space.width) {
+            Some(width) if is_border_box => width - horizontal_edges,
+            Some(width) => width,
+            // A floated container shrinks to fit its items
+            None if element_style.float != Float::None => self.max_content_width(node_id).min(available_space.width - margin.horizontal()) - horizontal_edges,
+            None => available_space.width - margin.horizontal() - horizontal_edges,
+        }
+        .max(0.0);
+        let content_height = resolve_size(&element_style.height, available_space.height).filter(|height| height.is_finite()).map(|height| if is_border_box { height - vertical_edges } else { height }.max(0.0));
 
-        let is_row = matches!(element_style.flex_direction, FlexDirection::Row | FlexDirection::RowReverse);
-        let main_axis_size = if is_row { content_area_width } else { content_area_height };
+        let content_x = available_space.x + margin.left + border.left + padding.left;
+        let content_y = available_space.y + margin.top + border.top + padding.top;
+
+        let inner_main = if is_row { Some(content_width) } else { content_height };
+        let inner_cross = if is_row { content_height } else { Some(content_width) };
+        let (main_gap, cross_gap) = if is_row { (element_style.column_gap, element_style.row_gap) } else { (element_style.row_gap, element_style.column_gap) };
+        let main_gap = main_gap.resolve(inner_main.unwrap_or(0.0));
+        let cross_gap = cross_gap.resolve(inner_cross.unwrap_or(0.0));
 
         // --- 2. Collect and Size Flex Items ---
-        let child_ids = self.children.get(key).cloned().unwrap_or_default();
         let mut flex_items: Vec<FlexItem> = Vec::new();
-        for child_id in child_ids {
-            let child_key = child_id.into();
-            if let Some(child_style) = self.computed_element_styles.get(child_key).cloned() {
-                // To get intrinsic size, we do a preliminary layout pass
-                let preliminary_space = Rect {
-                    x: 0.0, y: 0.0,
-                    width: content_area_width,
-                    height: content_area_height,
-                };
-                let preliminary_rect = self.layout_node(child_id, preliminary_space);
-
-                let item_main_size = if is_row { preliminary_rect.width } else { preliminary_rect.height };
-                let item_cross_size = if is_row { preliminary_rect.height } else { preliminary_rect.width };
-
-                flex_items.push(FlexItem {
-                    node_id: child_id,
-                    style: child_style,
-                    main_size: item_main_size,
-                    cross_size: item_cross_size,
-                    x: 0.0, y: 0.0,
-                });
+        for &child_id in child_ids {
+            let child_style = self.computed_styles.get(child_id.into()).cloned().unwrap_or_default();
+            if child_style.display == Display::None {
+                self.layout_node(child_id, Rect { x: content_x, y: content_y, width: 0.0, height: 0.0 });
+                continue;
             }
+            if child_style.position.is_out_of_flow() {
+                self.static_positions.insert(child_id.into(), Rect { x: content_x, y: content_y, width: content_width, height: f32::INFINITY });
+                continue;
+            }
+            let item = self.measure_flex_item(child_id, child_style, is_row, content_x, content_y, content_width, inner_main, element_style.align_items);
+            flex_items.push(item);
         }
-
-        // Handle flex-direction: *-reverse
-        if matches!(element_style.flex_direction, FlexDirection::RowReverse | FlexDirection::ColumnReverse) {
-            flex_items.reverse();
-        }
+        // `order` is a stable sort, so equal values keep document order
+        flex_items.sort_by_key(|item| item.style.order);
 
         // --- 3. Determine Flex Lines (Wrapping) ---
+        let available_main = inner_main.unwrap_or(f32::INFINITY);
         let mut flex_lines: Vec<FlexLine> = Vec::new();
-        if element_style.flex_wrap == super::styleengine::FlexWrap::NoWrap {
-             // All items go on a single line
-             let total_main_size: f32 = flex_items.iter().map(|item| item.main_size).sum();
-             let max_cross_size: f32 = flex_items.iter().map(|item| item.cross_size).fold(0.0, f32::max);
-             flex_lines.push(FlexLine { items: flex_items, main_size: total_main_size, cross_size: max_cross_size });
-        } else {
-            // Handle wrapping
-            let mut current_line = FlexLine { items: vec![], main_size: 0.0, cross_size: 0.0 };
-            for item in flex_items {
-                if !current_line.items.is_empty() && current_line.main_size + item.main_size > main_axis_size {
-                    flex_lines.push(current_line);
-                    current_line = FlexLine { items: vec![], main_size: 0.0, cross_size: 0.0 };
-                }
-                current_line.main_size += item.main_size;
-                current_line.cross_size = current_line.cross_size.max(item.cross_size);
-                current_line.items.push(item);
+        let mut current_items: Vec<FlexItem> = Vec::new();
+        let mut current_main = 0.0;
+        for item in flex_items {
+            let outer = item.outer_main_size(is_row);
+            if is_wrap && !current_items.is_empty() && current_main + main_gap + outer > available_main {
+                flex_lines.push(FlexLine { items: std::mem::take(&mut current_items), cross_size: 0.0, cross_position: 0.0 });
+                current_main = 0.0;
             }
-            if !current_line.items.is_empty() {
-                flex_lines.push(current_line);
+            current_main += if current_items.is_empty() { outer } else { main_gap + outer };
+            current_items.push(item);
+        }
+        if !current_items.is_empty() {
+            flex_lines.push(FlexLine { items: current_items, cross_size: 0.0, cross_position: 0.0 });
+        }
+
+        // --- 4. Resolve Flexible Lengths and Cross Sizes ---
+        for line in &mut flex_lines {
+            resolve_flexible_lengths(&mut line.items, available_main, main_gap, is_row);
+            for item in &mut line.items {
+                self.measure_cross_size(item, is_row, content_x, content_y, content_width, element_style.align_items);
             }
         }
 
-        // --- 4. Main Axis Alignment (Justify Content) ---
         for line in &mut flex_lines {
-            let free_space = main_axis_size - line.main_size;
-            let mut spacing = 0.0;
-            let mut offset = 0.0;
-
-            if free_space > 0.0 {
-                match element_style.justify_content {
-                    JustifyContent::FlexStart => {},
-                    JustifyContent::FlexEnd => offset = free_space,
-                    JustifyContent::Center => offset = free_space / 2.0,
-                    JustifyContent::SpaceBetween => {
-                        if line.items.len() > 1 {
-                            spacing = free_space / (line.items.len() - 1) as f32;
-                        }
-                    },
-                    JustifyContent::SpaceAround => {
-                        spacing = free_space / line.items.len() as f32;
-                        offset = spacing / 2.0;
-                    },
-                    JustifyContent::SpaceEvenly => {
-                        spacing = free_space / (line.items.len() + 1) as f32;
-                        offset = spacing;
-                    }
+            line.cross_size = match inner_cross {
+                // A single-line container's line always fills it
+                Some(cross) if !is_wrap => cross,
+                _ => {
+                    let uses_baseline = |item: &FlexItem| is_row && item.alignment(element_style) == AlignItems::Baseline;
+                    let max_outer = line.items.iter().filter(|item| !uses_baseline(item)).map(|item| item.outer_cross_size(is_row)).fold(0.0, f32::max);
+                    let max_above = line.items.iter().filter(|item| uses_baseline(item)).map(|item| item.baseline).fold(0.0, f32::max);
+                    let max_below = line.items.iter().filter(|item| uses_baseline(item)).map(|item| item.outer_cross_size(is_row) - item.baseline).fold(0.0, f32::max);
+                    max_outer.max(max_above + max_below)
+                }
+            };
+        }
+
+        // --- 5. Cross Axis Alignment (Align Content & Align Items) ---
+        let total_cross_size: f32 = flex_lines.iter().map(|line| line.cross_size).sum::<f32>() + cross_gap * flex_lines.len().saturating_sub(1) as f32;
+        let free_cross_space = match inner_cross {
+            Some(cross) if is_wrap => cross - total_cross_size,
+            _ => 0.0,
+        };
+        let line_count = flex_lines.len() as f32;
+        let (mut cross_offset, mut line_spacing) = (0.0, 0.0);
+        match element_style.align_content {
+            AlignContent::FlexStart => {}
+            AlignContent::FlexEnd => cross_offset = free_cross_space,
+            AlignContent::Center => cross_offset = free_cross_space / 2.0,
+            AlignContent::Stretch if free_cross_space > 0.0 => {
+                for line in &mut flex_lines {
+                    line.cross_size += free_cross_space / line_count;
                 }
             }
-            
-            let mut current_main = offset;
-            for item in &mut line.items {
-                if is_row { item.x = current_main; } else { item.y = current_main; }
-                current_main += item.main_size + spacing;
+            AlignContent::Stretch => {}
+            AlignContent::SpaceBetween if free_cross_space > 0.0 && line_count > 1.0 => line_spacing = free_cross_space / (line_count - 1.0),
+            AlignContent::SpaceBetween => {}
+            AlignContent::SpaceAround if free_cross_space > 0.0 => {
+                line_spacing = free_cross_space / line_count;
+                cross_offset = line_spacing / 2.0;
             }
-        }
-        
-        // --- 5. Cross Axis Alignment (Align Items & Align Content) ---
-        let total_cross_size: f32 = flex_lines.iter().map(|line| line.cross_size).sum();
-        let mut cross_offset = 0.0;
-        
-        let free_cross_space = if content_area_height.is_finite() { content_area_height - total_cross_size } else { 0.0 };
-
-        if free_cross_space > 0.0 {
-             match element_style.align_content {
-                AlignContent::FlexStart => {},
-                AlignContent::FlexEnd => cross_offset = free_cross_space,
-                AlignContent::Center => cross_offset = free_cross_space / 2.0,
-                // Other align-content logic would go here...
-                _ => {}
+            AlignContent::SpaceEvenly if free_cross_space > 0.0 => {
+                line_spacing = free_cross_space / (line_count + 1.0);
+                cross_offset = line_spacing;
             }
+            AlignContent::SpaceAround | AlignContent::SpaceEvenly => cross_offset = free_cross_space / 2.0,
         }
 
-
         let mut current_cross = cross_offset;
         for line in &mut flex_lines {
+            line.cross_position = current_cross;
+            current_cross += line.cross_size + cross_gap + line_spacing;
+
+            let max_baseline = line.items.iter().filter(|item| is_row && item.alignment(element_style) == AlignItems::Baseline).map(|item| item.baseline).fold(0.0, f32::max);
             for item in &mut line.items {
-                let align = if item.style.align_self == AlignSelf::Auto { element_style.align_items } else { item.style.align_self.into() };
+                let (margin_start, margin_end) = item.cross_margins(is_row);
+                let alignment = item.alignment(element_style);
+                let cross_property = if is_row { item.style.height } else { item.style.width };
+                if alignment == AlignItems::Stretch && matches!(cross_property, None | Some(Size::Auto)) {
+                    item.cross_size = (line.cross_size - margin_start - margin_end).max(0.0);
+                    item.stretched = true;
+                }
 
-                let item_cross_pos = match align {
-                    AlignItems::FlexStart | AlignItems::Stretch => current_cross,
-                    AlignItems::FlexEnd => current_cross + line.cross_size - item.cross_size,
-                    AlignItems::Center => current_cross + (line.cross_size - item.cross_size) / 2.0,
-                    _ => current_cross, // Baseline not implemented
+                let free = line.cross_size - item.outer_cross_size(is_row);
+                let offset = match alignment {
+                    AlignItems::FlexStart | AlignItems::Stretch => 0.0,
+                    AlignItems::FlexEnd => free,
+                    AlignItems::Center => free / 2.0,
+                    AlignItems::Baseline if is_row => max_baseline - item.baseline,
+                    // Column items have no shared baseline, so they align to the start
+                    AlignItems::Baseline => 0.0,
                 };
-                
-                if is_row { item.y = item_cross_pos; } else { item.x = item_cross_pos; }
+                item.cross_position = line.cross_position + offset + margin_start;
+            }
+        }
+
+        // --- 6. Main Axis Alignment (Justify Content) ---
+        let mut used_main: f32 = 0.0;
+        for line in &mut flex_lines {
+            let item_count = line.items.len() as f32;
+            let line_main: f32 = line.items.iter().map(|item| item.outer_main_size(is_row)).sum::<f32>() + main_gap * (item_count - 1.0).max(0.0);
+            used_main = used_main.max(line_main);
+            let free_space = if available_main.is_finite() { available_main - line_main } else { 0.0 };
+
+            let (offset, spacing) = match element_style.justify_content {
+                JustifyContent::FlexStart => (0.0, 0.0),
+                JustifyContent::FlexEnd => (free_space, 0.0),
+                JustifyContent::Center => (free_space / 2.0, 0.0),
+                JustifyContent::SpaceBetween if free_space > 0.0 && item_count > 1.0 => (0.0, free_space / (item_count - 1.0)),
+                JustifyContent::SpaceBetween => (0.0, 0.0),
+                JustifyContent::SpaceAround if free_space > 0.0 => (free_space / item_count / 2.0, free_space / item_count),
+                JustifyContent::SpaceEvenly if free_space > 0.0 => (free_space / (item_count + 1.0), free_space / (item_count + 1.0)),
+                JustifyContent::SpaceAround | JustifyContent::SpaceEvenly => (free_space / 2.0, 0.0),
+            };
+
+            let mut current_main = offset;
+            for item in &mut line.items {
+                let (margin_start, _) = item.main_margins(is_row);
+                item.main_position = current_main + margin_start;
+                current_main += item.outer_main_size(is_row) + main_gap + spacing;
             }
-            current_cross += line.cross_size;
         }
 
-        // --- 6. Finalize Layout and Update DOM ---
-        let content_start_x = available_space.x + element_style.margin.left + border_left + padding.left;
-        let content_start_y = available_space.y + element_style.margin.top + border_top + padding.top;
-        
+        let final_main = inner_main.unwrap_or(used_main);
+        let final_cross = inner_cross.unwrap_or(total_cross_size);
+
+        // --- 7. Finalize Layout and Update DOM ---
         for line in flex_lines {
-            for item in line.items {
-                self.layout.insert(
-                    item.node_id.into(),
-                    super::layoutengine::LayoutData {
-                        computed_x: content_start_x + item.x,
-                        computed_y: content_start_y + item.y,
-                        actual_width: if is_row { item.main_size } else { item.cross_size },
-                        actual_height: if is_row { item.cross_size } else { item.main_size },
-                    },
-                );
+            for mut item in line.items {
+                // Reversed directions mirror positions within the content box
+                if is_reverse {
+                    item.main_position = final_main - item.main_position - item.main_size;
+                }
+                if element_style.flex_wrap == FlexWrap::WrapReverse {
+                    item.cross_position = final_cross - item.cross_position - item.cross_size;
+                }
+
+                let (x, y, width, height) = if is_row { (item.main_position, item.cross_position, item.main_size, item.cross_size) } else { (item.cross_position, item.main_position, item.cross_size, item.main_size) };
+                let item_space = Rect {
+                    x: content_x + x - item.margin.left,
+                    y: content_y + y - item.margin.top,
+                    width: width + item.margin.horizontal(),
+                    height: height + item.margin.vertical(),
+                };
+                // Only force the cross size when the item was stretched to it
+                let forced_cross = item.stretched.then_some(item.cross_size);
+                let (forced_width, forced_height) = if is_row { (Some(width), forced_cross) } else { (forced_cross.or(Some(width)), Some(height)) };
+                self.layout_sized_node(item.node_id, item_space, forced_width, forced_height);
             }
         }
-        
-        let final_content_height = if element_style.height.is_some() {
-            content_area_height
-        } else {
-            total_cross_size
-        };
 
-        let final_width = container_width;
-        let final_height = final_content_height + padding.top + padding.bottom + border_top + border_bottom;
+        let (final_content_width, final_content_height) = if is_row { (content_width, final_cross) } else { (content_width, final_main) };
+        let final_width = final_content_width + horizontal_edges;
+        let final_height = final_content_height + vertical_edges;
 
         self.layout.insert(
             key,
-            super::layoutengine::LayoutData {
-                computed_x: available_space.x + element_style.margin.left,
-                computed_y: available_space.y + element_style.margin.top,
+            LayoutData {
+                computed_x: available_space.x + margin.left,
+                computed_y: available_space.y + margin.top,
                 actual_width: final_width,
                 actual_height: final_height,
             },
@@ -230,12 +281,159 @@ impl Dom {
         Rect {
             x: available_space.x,
             y: available_space.y,
-            width: final_width + element_style.margin.left + element_style.margin.right,
-            height: final_height + element_style.margin.top + element_style.margin.bottom,
+            width: final_width + margin.horizontal(),
+            height: final_height + margin.vertical(),
         }
     }
+
+    /// Finds an item's flex base size from `flex-basis`, its main size property or its content.
+    #[allow(clippy::too_many_arguments)]
+    fn measure_flex_item(&mut self, node_id: NodeId, style: ComputedStyle, is_row: bool, content_x: f32, content_y: f32, content_width: f32, inner_main: Option<f32>, align_items: AlignItems) -> FlexItem {
+        // Percentage margins and paddings refer to the container's width on both axes
+        let margin = style.margin.resolve(content_width);
+        let padding = style.padding.resolve(content_width);
+        let border = style.border.widths();
+        let main_edges = if is_row { padding.horizontal() + border.horizontal() } else { padding.vertical() + border.vertical() };
+        let is_border_box = style.box_sizing == BoxSizing::BorderBox;
+
+        // Percentages against an indefinite main size behave as `auto`
+        let definite = |size: Option<Size>| match size {
+            Some(Size::Length(length)) if inner_main.is_some() || !length.has_percent() => {
+                let size = length.resolve(inner_main.unwrap_or(0.0));
+                Some(if is_border_box { size } else { size + main_edges })
+            }
+            _ => None,
+        };
+        let main_property = if is_row { style.width } else { style.height };
+
+        let mut item = FlexItem {
+            node_id,
+            style,
+            margin,
+            flex_base_size: 0.0,
+            min_main_size: main_edges,
+            main_size: 0.0,
+            cross_size: 0.0,
+            frozen: false,
+            stretched: false,
+            baseline: 0.0,
+            main_position: 0.0,
+            cross_position: 0.0,
+        };
+
+        item.flex_base_size = match definite(Some(item.style.flex_basis)).or_else(|| definite(main_property)) {
+            Some(size) => size,
+            None if is_row => self.max_content_width(node_id),
+            None => {
+                // A column item's content height depends on the width it will be given
+                let width = self.column_item_width(&item, content_width, align_items);
+                let space = Rect { x: content_x, y: content_y, width: width + margin.horizontal(), height: f32::INFINITY };
+                self.layout_sized_node(node_id, space, Some(width), None);
+                self.layout.get(node_id.into()).map_or(0.0, |layout| layout.actual_height)
+            }
+        };
+        item.main_size = item.flex_base_size.max(item.min_main_size);
+        item
+    }
+
+    /// The border-box width a column item gets: its own width, the container's when stretched, or its content width.
+    fn column_item_width(&mut self, item: &FlexItem, content_width: f32, align_items: AlignItems) -> f32 {
+        let available = (content_width - item.margin.horizontal()).max(0.0);
+        if let Some(width) = resolve_size(&item.style.width, content_width) {
+            let edges = item.style.padding.resolve(content_width).horizontal() + item.style.border.widths().horizontal();
+            return if item.style.box_sizing == BoxSizing::BorderBox { width } else { width + edges };
+        }
+        let alignment = if item.style.align_self == AlignSelf::Auto { align_items } else { item.style.align_self.into() };
+        if alignment == AlignItems::Stretch {
+            available
+        } else {
+            self.max_content_width(item.node_id).min(available)
+        }
+    }
+
+    /// Lays the item out at its final main size to find its hypothetical cross size and baseline.
+    fn measure_cross_size(&mut self, item: &mut FlexItem, is_row: bool, content_x: f32, content_y: f32, content_width: f32, align_items: AlignItems) {
+        if !is_row {
+            item.cross_size = self.column_item_width(item, content_width, align_items);
+            return;
+        }
+
+        let space = Rect { x: content_x, y: content_y, width: item.main_size + item.margin.horizontal(), height: f32::INFINITY };
+        self.layout_sized_node(item.node_id, space, Some(item.main_size), None);
+        item.cross_size = self.layout.get(item.node_id.into()).map_or(0.0, |layout| layout.actual_height);
+        // Items without text synthesize a baseline from their bottom border edge
+        item.baseline = self.first_baseline(item.node_id).map_or(item.margin.top + item.cross_size, |baseline| baseline - content_y);
+    }
+
+    /// The absolute y position of the first line of text laid out inside a node.
+    pub(crate) fn first_baseline(&self, node_id: NodeId) -> Option<f32> {
+        let key = node_id.into();
+        if let Some(NodeContent::Text(_)) = self.content.get(key) {
+            let layout = self.layout.get(key)?;
+            let text_info = self.text_info.get(key)?;
+            return (!text_info.lines.is_empty()).then_some(layout.computed_y + text_info.ascent);
+        }
+        self.children.get(key)?.iter().find_map(|&child_id| self.first_baseline(child_id))
+    }
 }
 
+/// Grows or shrinks the items of one line to fill `available_main`, freezing items
+/// that would go below their minimum size and redistributing until none do.
+fn resolve_flexible_lengths(items: &mut [FlexItem], available_main: f32, main_gap: f32, is_row: bool) {
+    if !available_main.is_finite() || items.is_empty() {
+        return;
+    }
+
+    let gaps = main_gap * (items.len() - 1) as f32;
+    let hypothetical: f32 = items.iter().map(|item| item.outer_main_size(is_row)).sum::<f32>() + gaps;
+    let growing = hypothetical < available_main;
+
+    for item in items.iter_mut() {
+        let factor = if growing { item.style.flex_grow } else { item.style.flex_shrink };
+        item.frozen = factor == 0.0 || (growing && item.flex_base_size > item.main_size) || (!growing && item.flex_base_size < item.main_size);
+    }
+
+    let margins = |item: &FlexItem| item.outer_main_size(is_row) - item.main_size;
+    let initial_free: f32 = available_main - gaps - items.iter().map(|item| if item.frozen { item.outer_main_size(is_row) } else { item.flex_base_size + margins(item) }).sum::<f32>();
+
+    loop {
+        if items.iter().all(|item| item.frozen) {
+            break;
+        }
+
+        let used: f32 = items.iter().map(|item| if item.frozen { item.outer_main_size(is_row) } else { item.flex_base_size + margins(item) }).sum();
+        let mut free_space = available_main - gaps - used;
+        let total_factor: f32 = items.iter().filter(|item| !item.frozen).map(|item| if growing { item.style.flex_grow } else { item.style.flex_shrink }).sum();
+        // Factors summing below 1 only take that fraction of the free space
+        if total_factor < 1.0 && (initial_free * total_factor).abs() < free_space.abs() {
+            free_space = initial_free * total_factor;
+        }
+
+        if growing {
+            for item in items.iter_mut().filter(|item| !item.frozen) {
+                item.main_size = item.flex_base_size + free_space * item.style.flex_grow / total_factor;
+            }
+        } else {
+            let total_scaled: f32 = items.iter().filter(|item| !item.frozen).map(|item| item.style.flex_shrink * item.flex_base_size).sum();
+            for item in items.iter_mut().filter(|item| !item.frozen) {
+                let share = if total_scaled > 0.0 { item.style.flex_shrink * item.flex_base_size / total_scaled } else { 0.0 };
+                item.main_size = item.flex_base_size + free_space * share;
+            }
+        }
+
+        let mut violated = false;
+        for item in items.iter_mut().filter(|item| !item.frozen) {
+            if item.main_size < item.min_main_size {
+                item.main_size = item.min_main_size;
+                item.frozen = true;
+                violated = true;
+            }
+        }
+        if !violated {
+            break;
+        }
+    }
+}
 
 // Helper to convert AlignSelf to AlignItems
 impl From<AlignSelf> for AlignItems {
@@ -249,4 +447,4 @@ impl From<AlignSelf> for AlignItems {
             AlignSelf::Auto => panic!("Cannot convert AlignSelf::Auto to AlignItems"),
         }
     }
-}
\ No newline at end of file
+}
@@ -1,10 +1,77 @@
 // src/dom/fontmanager.rs
 
-use std::cell::RefCell;
-use skia_safe::FontMgr;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use skia_safe::font_style::{Slant, Weight, Width};
+use skia_safe::{FontMgr, FontStyle, Typeface};
+use skia_safe::textlayout::{FontCollection, TypefaceFontProvider};
+
+use crate::dom::styleengine::FontSlant;
+
+/// Installed families tried, in order, for each generic family. The first one found is used,
+/// the others stay in the list for the characters it has no glyphs for.
+const GENERIC_FAMILIES: &[(&str, &[&str])] = &[
+    ("serif", &["Times New Roman", "Times", "Noto Serif", "DejaVu Serif", "Liberation Serif"]),
+    ("sans-serif", &["Arial", "Helvetica", "Segoe UI", "Noto Sans", "DejaVu Sans", "Liberation Sans"]),
+    ("monospace", &["Menlo", "SF Mono", "Consolas", "DejaVu Sans Mono", "Liberation Mono", "Noto Sans Mono", "Courier New"]),
+    ("cursive", &["Comic Sans MS", "Apple Chancery", "URW Chancery L"]),
+    ("fantasy", &["Impact", "Papyrus"]),
+    ("system-ui", &["Segoe UI", ".AppleSystemUIFont", "Ubuntu", "Cantarell", "Noto Sans", "DejaVu Sans"]),
+    ("emoji", &["Apple Color Emoji", "Segoe UI Emoji", "Noto Color Emoji"]),
+];
+
+/// File extensions `register_fonts_in_dir` picks up.
+const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc", "otc", "woff", "woff2"];
+
+/// Why a font couldn't be registered.
+#[derive(Debug)]
+pub enum FontError {
+    Io { path: PathBuf, error: io::Error },
+    /// The data isn't in a format the font manager can read. WOFF and WOFF2 are only read
+    /// when Skia was built with support for them.
+    UnsupportedFormat { path: Option<PathBuf> },
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontError::Io { path, error } => write!(f, "couldn't read {}: {}", path.display(), error),
+            FontError::UnsupportedFormat { path: Some(path) } => write!(f, "{} is not a font that can be read", path.display()),
+            FontError::UnsupportedFormat { path: None } => write!(f, "the data is not a font that can be read"),
+        }
+    }
+}
+
+impl std::error::Error for FontError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FontError::Io { error, .. } => Some(error),
+            FontError::UnsupportedFormat { .. } => None,
+        }
+    }
+}
+
+/// What a typeface lookup is keyed by.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct TypefaceKey {
+    family: String,
+    weight: u16,
+    slant: FontSlant,
+    width: i32,
+}
 
 thread_local! {
     static THREAD_FONT_MGR: RefCell<Option<FontMgr>> = RefCell::new(None);
+    static THREAD_FONT_COLLECTION: RefCell<Option<FontCollection>> = const { RefCell::new(None) };
+    /// Fonts registered by the application, which win over installed fonts of the same family
+    static THREAD_FONT_PROVIDER: RefCell<Option<TypefaceFontProvider>> = const { RefCell::new(None) };
+    static SYSTEM_FONTS_ENABLED: Cell<bool> = const { Cell::new(true) };
+    /// Lookups that found nothing are cached too, so a missing family is only asked for once
+    static TYPEFACE_CACHE: RefCell<HashMap<TypefaceKey, Option<Typeface>>> = RefCell::new(HashMap::new());
 }
 
 pub fn get_thread_local_font_mgr() -> FontMgr {
@@ -15,4 +82,170 @@ pub fn get_thread_local_font_mgr() -> FontMgr {
         }
         mgr.as_ref().unwrap().clone()
     })
-}
\ No newline at end of file
+}
+
+/// The fonts text is shaped with: registered fonts first, then installed ones unless they are
+/// disabled. The collection caches typefaces and shaped runs, and falls back to other fonts for
+/// characters the requested family has no glyphs for.
+pub fn get_thread_local_font_collection() -> FontCollection {
+    THREAD_FONT_COLLECTION.with(|cell| {
+        let mut collection = cell.borrow_mut();
+        if collection.is_none() {
+            let mut font_collection = FontCollection::new();
+            font_collection.set_asset_font_manager(registered_font_mgr());
+            if SYSTEM_FONTS_ENABLED.get() {
+                font_collection.set_default_font_manager(get_thread_local_font_mgr(), None);
+            }
+            font_collection.enable_font_fallback();
+            *collection = Some(font_collection);
+        }
+        collection.as_ref().unwrap().clone()
+    })
+}
+
+/// The registered fonts as a font manager, if any were registered on this thread.
+fn registered_font_mgr() -> Option<FontMgr> {
+    THREAD_FONT_PROVIDER.with(|cell| cell.borrow().clone().map(FontMgr::from))
+}
+
+/// Registers a font from memory under `family`, or under the family name stored in the font.
+/// Returns the family it was registered under. Fonts are registered for the current thread,
+/// which should be the one that lays out and paints; text that is already laid out keeps its
+/// old font until it is laid out again.
+pub fn register_font_bytes(data: &[u8], family: Option<&str>) -> Result<String, FontError> {
+    register_font(data, family, None)
+}
+
+/// Registers the font in a file, like `register_font_bytes`.
+pub fn register_font_file(path: impl AsRef<Path>, family: Option<&str>) -> Result<String, FontError> {
+    let path = path.as_ref();
+    let data = fs::read(path).map_err(|error| FontError::Io { path: path.to_path_buf(), error })?;
+    register_font(&data, family, Some(path))
+}
+
+/// Registers every font file in a directory and its subdirectories, each under the family
+/// name stored in it, and returns those families. Files that aren't fonts are skipped.
+pub fn register_fonts_in_dir(dir: impl AsRef<Path>) -> Result<Vec<String>, FontError> {
+    let mut families = Vec::new();
+    let mut pending = vec![dir.as_ref().to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = fs::read_dir(&dir).map_err(|error| FontError::Io { path: dir.clone(), error })?;
+        let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect();
+        // Sorted, so that the same directory registers the same way everywhere
+        paths.sort();
+        for path in paths {
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let is_font = path.extension().and_then(|extension| extension.to_str()).is_some_and(|extension| FONT_EXTENSIONS.iter().any(|known| known.eq_ignore_ascii_case(extension)));
+            if !is_font {
+                continue;
+            }
+            match register_font_file(&path, None) {
+                Ok(family) => {
+                    if !families.contains(&family) {
+                        families.push(family);
+                    }
+                }
+                Err(FontError::UnsupportedFormat { .. }) => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+    Ok(families)
+}
+
+/// Turns installed fonts on or off for this thread. With them off only registered fonts are
+/// used, fallback included, so text looks the same on every machine.
+pub fn set_system_fonts_enabled(enabled: bool) {
+    SYSTEM_FONTS_ENABLED.set(enabled);
+    fonts_changed();
+}
+
+/// The families text can use: registered ones, then installed ones when they are enabled.
+pub fn available_families() -> Vec<String> {
+    let mut families: Vec<String> = registered_font_mgr().map(|mgr| mgr.family_names().collect()).unwrap_or_default();
+    if SYSTEM_FONTS_ENABLED.get() {
+        for family in get_thread_local_font_mgr().family_names() {
+            if !families.contains(&family) {
+                families.push(family);
+            }
+        }
+    }
+    families
+}
+
+fn register_font(data: &[u8], family: Option<&str>, path: Option<&Path>) -> Result<String, FontError> {
+    let typeface = get_thread_local_font_mgr().new_from_data(data, None).ok_or_else(|| FontError::UnsupportedFormat { path: path.map(Path::to_path_buf) })?;
+    let family = family.map_or_else(|| typeface.family_name(), str::to_string);
+    THREAD_FONT_PROVIDER.with(|cell| cell.borrow_mut().get_or_insert_with(TypefaceFontProvider::new).register_typeface(typeface, Some(family.as_str())));
+    fonts_changed();
+    Ok(family)
+}
+
+/// Forgets every lookup made with the fonts as they were.
+fn fonts_changed() {
+    TYPEFACE_CACHE.with(|cache| cache.borrow_mut().clear());
+    THREAD_FONT_COLLECTION.with(|cell| cell.borrow_mut().take());
+}
+
+pub fn is_generic_family(family: &str) -> bool {
+    GENERIC_FAMILIES.iter().any(|(name, _)| name.eq_ignore_ascii_case(family))
+}
+
+/// The Skia style for a computed `font-weight`, `font-style` and `font-stretch`.
+pub fn font_style(weight: u16, slant: FontSlant, stretch: f32) -> FontStyle {
+    let slant = match slant {
+        FontSlant::Normal => Slant::Upright,
+        FontSlant::Italic => Slant::Italic,
+        FontSlant::Oblique => Slant::Oblique,
+    };
+    FontStyle::new(Weight::from(weight as i32), Width::from(font_width(stretch)), slant)
+}
+
+/// The nearest of the nine widths fonts come in, from ultra-condensed (1) to ultra-expanded (9).
+fn font_width(stretch: f32) -> i32 {
+    const WIDTHS: [f32; 9] = [50.0, 62.5, 75.0, 87.5, 100.0, 112.5, 125.0, 150.0, 200.0];
+    let nearest = WIDTHS.iter().enumerate().min_by(|(_, a), (_, b)| (*a - stretch).abs().total_cmp(&(*b - stretch).abs())).map_or(4, |(index, _)| index);
+    nearest as i32 + 1
+}
+
+/// The closest installed face of a family, looked up once per family and style on each thread.
+pub fn match_typeface(family: &str, weight: u16, slant: FontSlant, stretch: f32) -> Option<Typeface> {
+    let key = TypefaceKey {
+        family: family.to_ascii_lowercase(),
+        weight,
+        slant,
+        width: font_width(stretch),
+    };
+    if let Some(cached) = TYPEFACE_CACHE.with(|cache| cache.borrow().get(&key).cloned()) {
+        return cached;
+    }
+    let style = font_style(weight, slant, stretch);
+    let registered = registered_font_mgr().and_then(|mgr| mgr.match_family_style(family, style));
+    let typeface = registered.or_else(|| SYSTEM_FONTS_ENABLED.get().then(|| get_thread_local_font_mgr().match_family_style(family, style)).flatten());
+    TYPEFACE_CACHE.with(|cache| cache.borrow_mut().insert(key, typeface.clone()));
+    typeface
+}
+
+/// Expands generic families into installed ones and drops families that aren't installed,
+/// keeping the order of preference. Text is shaped with the first family that has a glyph for
+/// each character, and with any system font that has one after that.
+pub fn resolve_families(families: &[String], weight: u16, slant: FontSlant, stretch: f32) -> Vec<String> {
+    let mut resolved: Vec<String> = Vec::new();
+    for family in families {
+        let candidates: Vec<&str> = match GENERIC_FAMILIES.iter().find(|(name, _)| name.eq_ignore_ascii_case(family)) {
+            Some((_, installed)) => installed.to_vec(),
+            None => vec![family.as_str()],
+        };
+        for candidate in candidates {
+            let is_new = !resolved.iter().any(|known| known.eq_ignore_ascii_case(candidate));
+            if is_new && match_typeface(candidate, weight, slant, stretch).is_some() {
+                resolved.push(candidate.to_string());
+            }
+        }
+    }
+    resolved
+}
+
@@ -1,10 +1,12 @@
 // /src/dom/fragment.rs
 use slotmap::{SecondaryMap, SlotMap};
 use crate::dom::{
-    dom::{NodeContent, NodeId}, element::Element, styleengine::{Style, Font}, text::Text, Dom
+    dom::{NodeContent, NodeId}, domapi::DomError, element::Element, styleengine::{Style, Font}, text::Text, Dom
 };
 
-#[derive(Debug)]
+/// A detached tree of nodes that can be inserted into a `Dom`, once with `merge_into` or any
+/// number of times with `instantiate`, like a template.
+#[derive(Debug, Clone, Default)]
 pub struct DocumentFragment {
     // Similar structure to Dom but for a subtree
     nodes: SlotMap<slotmap::DefaultKey, ()>,
@@ -12,88 +14,190 @@ pub struct DocumentFragment {
     pub content: SecondaryMap<slotmap::DefaultKey, NodeContent>,
     pub element_styles: SecondaryMap<slotmap::DefaultKey, Style>,
     pub text_styles: SecondaryMap<slotmap::DefaultKey, Font>,
-    pub root: Option<NodeId>,
+    /// The nodes inserted directly into the target parent, in order. Nodes that are neither
+    /// listed here nor below one of these are never inserted.
+    pub top_level: Vec<NodeId>,
+    /// Where each node was appended: under a parent, or `None` at the top level.
+    parents: SecondaryMap<slotmap::DefaultKey, Option<NodeId>>,
+}
+
+/// Where the nodes of a fragment ended up after inserting it into a `Dom`.
+#[derive(Debug, Clone, Default)]
+pub struct FragmentMapping {
+    /// The inserted copies of the fragment's top-level nodes, in order.
+    pub roots: Vec<NodeId>,
+    ids: SecondaryMap<slotmap::DefaultKey, NodeId>,
+}
+
+impl FragmentMapping {
+    /// The `Dom` node created for a node of the fragment.
+    pub fn get(&self, fragment_node_id: NodeId) -> Option<NodeId> {
+        self.ids.get(fragment_node_id.into()).copied()
+    }
+
+    /// Pairs of fragment node and `Dom` node.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, NodeId)> + '_ {
+        self.ids.iter().map(|(key, &dom_node_id)| (NodeId::from(key), dom_node_id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
 }
 
 impl DocumentFragment {
     pub fn new() -> Self {
-        Self {
-            nodes: SlotMap::with_key(),
-            children: SecondaryMap::new(),
-            content: SecondaryMap::new(),
-            element_styles: SecondaryMap::new(),
-            text_styles: SecondaryMap::new(),
-            root: None,
-        }
+        Self::default()
     }
 
     pub fn create_element(&mut self, element: Element) -> NodeId {
         let key = self.nodes.insert(());
         let node_id = NodeId(key);
-        
+
         self.content.insert(key, NodeContent::Element(element));
         self.element_styles.insert(key, Style::default());
         self.children.insert(key, Vec::new());
-        
+
         node_id
     }
 
     pub fn create_text(&mut self, text: Text) -> NodeId {
         let key = self.nodes.insert(());
         let node_id = NodeId(key);
-        
+
         self.content.insert(key, NodeContent::Text(text));
-        self.text_styles.insert(key, Font::default());
         self.children.insert(key, Vec::new());
-        
+
         node_id
     }
 
+    /// Adds a node to the end of `parent_id`'s children, moving it from where it was appended
+    /// before. Does nothing when `parent_id` isn't an element or is the node itself or inside it.
     pub fn append_child(&mut self, parent_id: NodeId, child_id: NodeId) {
-        if let Some(children) = self.children.get_mut(parent_id.into()) {
-            children.push(child_id);
+        let is_element = matches!(self.content.get(parent_id.into()), Some(NodeContent::Element(_)));
+        if !self.nodes.contains_key(child_id.into()) || !is_element || self.is_inclusive_ancestor(child_id, parent_id) {
+            return;
+        }
+        self.detach(child_id);
+        self.children[parent_id.into()].push(child_id);
+        self.parents.insert(child_id.into(), Some(parent_id));
+    }
+
+    /// Adds a node to the end of the top-level nodes, moving it from where it was appended before.
+    pub fn append(&mut self, node_id: NodeId) {
+        if self.nodes.contains_key(node_id.into()) {
+            self.detach(node_id);
+            self.top_level.push(node_id);
+            self.parents.insert(node_id.into(), None);
+        }
+    }
+
+    fn detach(&mut self, node_id: NodeId) {
+        match self.parents.remove(node_id.into()) {
+            Some(Some(parent_id)) => self.children[parent_id.into()].retain(|&id| id != node_id),
+            Some(None) => self.top_level.retain(|&id| id != node_id),
+            None => {}
+        }
+    }
+
+    /// Whether `ancestor_id` is `node_id` or one of its ancestors.
+    fn is_inclusive_ancestor(&self, ancestor_id: NodeId, node_id: NodeId) -> bool {
+        std::iter::successors(Some(node_id), |&id| self.parents.get(id.into()).copied().flatten()).any(|id| id == ancestor_id)
+    }
+
+    pub fn set_style(&mut self, node_id: NodeId, style: Style) {
+        if let Some(current_style) = self.element_styles.get_mut(node_id.into()) {
+            current_style.apply(&style);
+        }
+    }
+
+    /// Sets the font of a text node, overriding what it would inherit from its element.
+    pub fn set_text_font(&mut self, node_id: NodeId, font: Font) {
+        if let Some(NodeContent::Text(_)) = self.content.get(node_id.into()) {
+            self.text_styles.insert(node_id.into(), font);
         }
     }
 
     // Method to merge fragment into main DOM
-    pub fn merge_into(self, dom: &mut Dom, parent_id: NodeId) -> Vec<NodeId> {
-        let mut id_mapping = SecondaryMap::new();
-        let mut new_node_ids = Vec::new();
-        
-        // First pass: create all nodes in the DOM
-        for (old_key, content) in self.content {
-            let new_node_id = match content {
-                NodeContent::Element(el) => dom.create_element(el),
-                NodeContent::Text(text) => dom.create_text(text),
-            };
-            
-            // Copy styles if they exist
-            if let Some(style) = self.element_styles.get(old_key) {
-                dom.set_style(new_node_id, style.clone());
+    pub fn merge_into(self, dom: &mut Dom, parent_id: NodeId) -> Result<FragmentMapping, DomError> {
+        self.instantiate(dom, parent_id)
+    }
+
+    /// Inserts a copy of the fragment at the end of `parent_id`'s children, leaving the fragment
+    /// as it was so it can be inserted again. Nothing is inserted when `parent_id` can't have children.
+    pub fn instantiate(&self, dom: &mut Dom, parent_id: NodeId) -> Result<FragmentMapping, DomError> {
+        dom.check_parent(parent_id)?;
+        let mut mapping = FragmentMapping::default();
+        for &top_level_id in &self.top_level {
+            // Each subtree is built while detached and attached once it's complete
+            if let Some(root_id) = self.copy_subtree(dom, top_level_id, &mut mapping) {
+                dom.append_child(parent_id, root_id).expect("just checked");
+                mapping.roots.push(root_id);
             }
-            
-            id_mapping.insert(old_key, new_node_id);
-            new_node_ids.push(new_node_id);
         }
-        
-        // Second pass: rebuild parent-child relationships
-        for (old_key, children) in self.children {
-            if let Some(&new_parent_id) = id_mapping.get(old_key) {
-                for &old_child_key in &children {
-                    if let Some(&new_child_id) = id_mapping.get(old_child_key.into()) {
-                        dom.append_child(new_parent_id, new_child_id);
-                    }
-                }
-            }
+        Ok(mapping)
+    }
+
+    fn copy_subtree(&self, dom: &mut Dom, node_id: NodeId, mapping: &mut FragmentMapping) -> Option<NodeId> {
+        let key: slotmap::DefaultKey = node_id.into();
+        let new_node_id = match self.content.get(key)? {
+            NodeContent::Element(element) => dom.create_element(element.clone()),
+            NodeContent::Text(text) => dom.create_text(text.clone()),
+        };
+        // Most template nodes have no style of their own, and setting an empty one is a pointless style change
+        if let Some(style) = self.element_styles.get(key).filter(|style| **style != Style::default()) {
+            dom.set_style(new_node_id, style.clone());
+        }
+        if let Some(font) = self.text_styles.get(key) {
+            dom.set_style(new_node_id, Style { font: Some(font.clone()), ..Style::default() });
         }
-        
-        // Attach fragment's root children to the parent in the main DOM
-        if let Some(fragment_root_id) = self.root {
-            if let Some(&new_root_id) = id_mapping.get(fragment_root_id.into()) {
-                dom.append_child(parent_id, new_root_id);
+        mapping.ids.insert(key, new_node_id);
+
+        for &child_id in self.children.get(key).into_iter().flatten() {
+            if let Some(new_child_id) = self.copy_subtree(dom, child_id, mapping) {
+                dom.append_child(new_node_id, new_child_id).expect("only elements have children in a fragment");
             }
         }
-        
-        new_node_ids
+        Some(new_node_id)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn appending_rejects_cycles_and_moves_nodes() {
+        let mut fragment = DocumentFragment::new();
+        let outer = fragment.create_element(Element::new());
+        let inner = fragment.create_element(Element::new());
+        fragment.append(outer);
+        fragment.append_child(outer, inner);
+        fragment.append_child(inner, outer);
+        fragment.append_child(inner, inner);
+        assert_eq!(fragment.children[inner.into()], []);
+
+        // Appending again moves the node instead of giving it a second parent
+        fragment.append(inner);
+        assert_eq!(fragment.children[outer.into()], []);
+        assert_eq!(fragment.top_level, [outer, inner]);
+        fragment.append_child(inner, outer);
+        assert_eq!(fragment.top_level, [inner]);
+        // Only elements take children
+        let text = fragment.create_text(Text::new("x"));
+        fragment.append_child(text, outer);
+        assert_eq!(fragment.children[text.into()], []);
+
+        let mut dom = Dom::new();
+        let root = dom.root;
+        let text_node = dom.create_text_node("y".to_string());
+        assert_eq!(fragment.instantiate(&mut dom, text_node).map(|mapping| mapping.len()), Err(DomError::NotAnElement(text_node)));
+        let mapping = fragment.instantiate(&mut dom, root).unwrap();
+        assert_eq!(mapping.len(), 2);
+        assert!(mapping.iter().all(|(_, node_id)| dom.styles[node_id.into()] == Style::default()));
+    }
+}
@@ -0,0 +1,590 @@
+// /src/dom/grid.rs
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dom::length::{Length, LengthContext};
+
+/// One side of a track size; `minmax()` gives a track a different breadth for each side.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TrackBreadth {
+    Length(Length),
+    /// A share of the space left once the other tracks are sized
+    Fr(f32),
+    Auto,
+    MinContent,
+    MaxContent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrackSize {
+    pub min: TrackBreadth,
+    pub max: TrackBreadth,
+}
+
+/// A `grid-template-columns`/`grid-template-rows` value. `repeat()` with a count is expanded while parsing.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct TrackList {
+    pub tracks: Vec<TrackSize>,
+    pub auto_repeat: Option<AutoRepeat>,
+}
+
+/// `repeat(auto-fill, ...)`, inserted before `tracks[index]` as many times as fits in the container.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutoRepeat {
+    pub index: usize,
+    pub tracks: Vec<TrackSize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct GridTemplateAreas {
+    pub rows: usize,
+    pub columns: usize,
+    pub areas: Vec<GridArea>,
+}
+
+/// A named rectangle of cells, as 0-based line indices with exclusive ends.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GridArea {
+    pub name: String,
+    pub row_start: usize,
+    pub row_end: usize,
+    pub column_start: usize,
+    pub column_end: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GridAutoFlow {
+    Row,
+    Column,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GridLine {
+    Auto,
+    /// 1-based; negative lines count back from the end of the explicit grid
+    Line(i32),
+    Span(u32),
+    /// An area name, or `<area>-start`/`<area>-end`
+    Named(String),
+}
+
+/// `grid-column`/`grid-row`: the lines an item starts and ends at on one axis. In `Style`, `None`
+/// leaves a line unspecified; once computed, `None` and `GridLine::Auto` both mean `auto`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct GridPlacement {
+    pub start: Option<GridLine>,
+    pub end: Option<GridLine>,
+}
+
+/// Where an item sits on one axis: its first track, if placement fixes it, and how many tracks it covers.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GridSpan {
+    pub start: Option<usize>,
+    pub span: usize,
+}
+
+/// What an item needs from the tracks it spans, margins included.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TrackContribution {
+    pub start: usize,
+    pub span: usize,
+    pub min_content: f32,
+    pub max_content: f32,
+}
+
+impl TrackBreadth {
+    fn is_intrinsic(self) -> bool {
+        matches!(self, TrackBreadth::Auto | TrackBreadth::MinContent | TrackBreadth::MaxContent)
+    }
+
+    /// The breadth in pixels when it doesn't depend on content. Percentages need a definite `base`.
+    fn fixed(self, base: Option<f32>) -> Option<f32> {
+        match self {
+            TrackBreadth::Length(length) if base.is_some() || !length.has_percent() => Some(length.resolve(base.unwrap_or(0.0))),
+            _ => None,
+        }
+    }
+}
+
+impl TrackSize {
+    pub const AUTO: TrackSize = TrackSize { min: TrackBreadth::Auto, max: TrackBreadth::Auto };
+
+    /// A track with the same breadth on both sides; a flexible track keeps an automatic minimum.
+    pub fn new(breadth: TrackBreadth) -> Self {
+        let min = if let TrackBreadth::Fr(_) = breadth { TrackBreadth::Auto } else { breadth };
+        Self { min, max: breadth }
+    }
+
+    pub fn flex_factor(&self) -> Option<f32> {
+        match self.max {
+            TrackBreadth::Fr(factor) => Some(factor),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn absolutize(self, context: &LengthContext) -> Self {
+        let breadth = |breadth: TrackBreadth| match breadth {
+            TrackBreadth::Length(length) => TrackBreadth::Length(length.absolutize(context)),
+            other => other,
+        };
+        Self { min: breadth(self.min), max: breadth(self.max) }
+    }
+}
+
+impl TrackList {
+    /// The explicit tracks, with any auto-repeated tracks repeated to fill `available`.
+    pub fn expand(&self, available: Option<f32>, gap: f32) -> Vec<TrackSize> {
+        let Some(repeat) = &self.auto_repeat else {
+            return self.tracks.clone();
+        };
+
+        let track_size = |track: &TrackSize| track.max.fixed(available).or(track.min.fixed(available));
+        // Repeat as often as fits, but always at least once
+        let repetitions = match available {
+            Some(available) => {
+                let others: f32 = self.tracks.iter().map(|track| track_size(track).unwrap_or(0.0) + gap).sum();
+                let repeat_size = repeat.tracks.iter().map(track_size).sum::<Option<f32>>().map(|size| size + gap * repeat.tracks.len() as f32);
+                match repeat_size {
+                    Some(size) if size > 0.0 => (((available - others + gap) / size).floor().max(1.0)) as usize,
+                    _ => 1,
+                }
+            }
+            None => 1,
+        };
+
+        let mut tracks = self.tracks[..repeat.index].to_vec();
+        for _ in 0..repetitions {
+            tracks.extend_from_slice(&repeat.tracks);
+        }
+        tracks.extend_from_slice(&self.tracks[repeat.index..]);
+        tracks
+    }
+
+    pub(crate) fn absolutize(&self, context: &LengthContext) -> Self {
+        Self {
+            tracks: self.tracks.iter().map(|track| track.absolutize(context)).collect(),
+            auto_repeat: self.auto_repeat.as_ref().map(|repeat| AutoRepeat {
+                index: repeat.index,
+                tracks: repeat.tracks.iter().map(|track| track.absolutize(context)).collect(),
+            }),
+        }
+    }
+}
+
+impl GridTemplateAreas {
+    /// Builds the areas from the rows of `grid-template-areas`, where `.` marks an unnamed cell.
+    pub fn parse(rows: &[String]) -> Result<Self, String> {
+        let cells: Vec<Vec<&str>> = rows.iter().map(|row| row.split_whitespace().collect()).collect();
+        let columns = cells.first().map_or(0, Vec::len);
+        if columns == 0 || cells.iter().any(|row| row.len() != columns) {
+            return Err("every row of grid-template-areas must have the same, non-zero number of cells".to_string());
+        }
+
+        let mut areas: Vec<GridArea> = Vec::new();
+        for (row, names) in cells.iter().enumerate() {
+            for (column, &name) in names.iter().enumerate() {
+                if name.chars().all(|c| c == '.') {
+                    continue;
+                }
+                match areas.iter_mut().find(|area| area.name == name) {
+                    Some(area) => {
+                        area.row_end = area.row_end.max(row + 1);
+                        area.column_start = area.column_start.min(column);
+                        area.column_end = area.column_end.max(column + 1);
+                    }
+                    None => areas.push(GridArea { name: name.to_string(), row_start: row, row_end: row + 1, column_start: column, column_end: column + 1 }),
+                }
+            }
+        }
+
+        for area in &areas {
+            let filled = (area.row_start..area.row_end).all(|row| (area.column_start..area.column_end).all(|column| cells[row][column] == area.name));
+            if !filled {
+                return Err(format!("grid area `{}` is not a rectangle", area.name));
+            }
+        }
+
+        Ok(Self { rows: cells.len(), columns, areas })
+    }
+
+    pub fn area(&self, name: &str) -> Option<&GridArea> {
+        self.areas.iter().find(|area| area.name == name)
+    }
+
+    /// The 0-based line a name refers to on one axis; a bare area name means its start or end depending on the side.
+    fn line(&self, name: &str, is_start: bool, is_row: bool) -> Option<usize> {
+        let (area, use_start) = match self.area(name) {
+            Some(area) => (area, is_start),
+            None => match (name.strip_suffix("-start"), name.strip_suffix("-end")) {
+                (Some(area), _) => (self.area(area)?, true),
+                (_, Some(area)) => (self.area(area)?, false),
+                _ => return None,
+            },
+        };
+        Some(match (is_row, use_start) {
+            (true, true) => area.row_start,
+            (true, false) => area.row_end,
+            (false, true) => area.column_start,
+            (false, false) => area.column_end,
+        })
+    }
+}
+
+impl GridPlacement {
+    /// Overwrites the lines `other` specifies.
+    pub(crate) fn merge(&mut self, other: &GridPlacement) {
+        if other.start.is_some() {
+            self.start = other.start.clone();
+        }
+        if other.end.is_some() {
+            self.end = other.end.clone();
+        }
+    }
+
+    /// Resolves the placement against the explicit grid, which has `explicit_tracks` tracks on this axis.
+    /// Lines before the start of the grid are clamped to its first line.
+    pub(crate) fn resolve(&self, explicit_tracks: usize, areas: &GridTemplateAreas, is_row: bool) -> GridSpan {
+        let line = |line: Option<&GridLine>, is_start: bool| match line? {
+            GridLine::Line(number) if *number > 0 => Some(*number as usize - 1),
+            GridLine::Line(number) => Some((explicit_tracks as i32 + 1 + number).max(0) as usize),
+            GridLine::Named(name) => areas.line(name, is_start, is_row),
+            GridLine::Auto | GridLine::Span(_) => None,
+        };
+        let span = |line: Option<&GridLine>| match line? {
+            GridLine::Span(span) => Some((*span).max(1) as usize),
+            _ => None,
+        };
+        let (start, end) = (self.start.as_ref(), self.end.as_ref());
+
+        match (line(start, true), line(end, false)) {
+            (Some(start), Some(end)) if end > start => GridSpan { start: Some(start), span: end - start },
+            (Some(start), Some(end)) if end < start => GridSpan { start: Some(end), span: start - end },
+            (Some(start), Some(_)) => GridSpan { start: Some(start), span: 1 },
+            (Some(start), None) => GridSpan { start: Some(start), span: span(end).unwrap_or(1) },
+            (None, Some(end)) => {
+                let span = span(start).unwrap_or(1);
+                GridSpan { start: Some(end.saturating_sub(span)), span }
+            }
+            (None, None) => GridSpan { start: None, span: span(start).or(span(end)).unwrap_or(1) },
+        }
+    }
+}
+
+/// Auto-placement. Takes each item's `(row, column)` spans in order-modified document order
+/// and returns the `(row, column)` track each one starts at. Placement is sparse: the cursor never moves back.
+pub(crate) fn place_items(spans: &[(GridSpan, GridSpan)], flow: GridAutoFlow, explicit_rows: usize, explicit_columns: usize) -> Vec<(usize, usize)> {
+    match flow {
+        GridAutoFlow::Row => place_along(spans, explicit_columns),
+        GridAutoFlow::Column => {
+            let swapped: Vec<(GridSpan, GridSpan)> = spans.iter().map(|&(row, column)| (column, row)).collect();
+            place_along(&swapped, explicit_rows).into_iter().map(|(column, row)| (row, column)).collect()
+        }
+    }
+}
+
+/// Places items along the minor axis, starting a new major track whenever the minor one is full.
+fn place_along(spans: &[(GridSpan, GridSpan)], explicit_minor: usize) -> Vec<(usize, usize)> {
+    let minor_count = spans.iter().map(|(_, minor)| minor.start.unwrap_or(0) + minor.span).fold(explicit_minor.max(1), usize::max);
+    let mut occupied: HashSet<(usize, usize)> = HashSet::new();
+    let fits = |occupied: &HashSet<(usize, usize)>, major: usize, minor: usize, (major_span, minor_span): (usize, usize)| (major..major + major_span).all(|a| (minor..minor + minor_span).all(|b| !occupied.contains(&(a, b))));
+    let occupy = |occupied: &mut HashSet<(usize, usize)>, major: usize, minor: usize, (major_span, minor_span): (usize, usize)| {
+        for a in major..major + major_span {
+            for b in minor..minor + minor_span {
+                occupied.insert((a, b));
+            }
+        }
+    };
+
+    let mut placed: Vec<Option<(usize, usize)>> = vec![None; spans.len()];
+
+    // Items fixed on both axes go first, then those fixed to a major track, then everything else in order
+    for (index, (major, minor)) in spans.iter().enumerate() {
+        if let (Some(major_start), Some(minor_start)) = (major.start, minor.start) {
+            occupy(&mut occupied, major_start, minor_start, (major.span, minor.span));
+            placed[index] = Some((major_start, minor_start));
+        }
+    }
+
+    for (index, (major, minor)) in spans.iter().enumerate() {
+        if let (Some(major_start), None) = (major.start, minor.start) {
+            let minor_start = (0..).find(|&minor_start| fits(&occupied, major_start, minor_start, (major.span, minor.span))).unwrap_or(0);
+            occupy(&mut occupied, major_start, minor_start, (major.span, minor.span));
+            placed[index] = Some((major_start, minor_start));
+        }
+    }
+
+    let (mut major_cursor, mut minor_cursor) = (0, 0);
+    for (index, (major, minor)) in spans.iter().enumerate() {
+        if major.start.is_some() {
+            continue;
+        }
+        match minor.start {
+            Some(minor_start) => {
+                if minor_start < minor_cursor {
+                    major_cursor += 1;
+                }
+                while !fits(&occupied, major_cursor, minor_start, (major.span, minor.span)) {
+                    major_cursor += 1;
+                }
+                minor_cursor = minor_start;
+            }
+            None => loop {
+                if minor_cursor + minor.span > minor_count {
+                    major_cursor += 1;
+                    minor_cursor = 0;
+                }
+                if fits(&occupied, major_cursor, minor_cursor, (major.span, minor.span)) {
+                    break;
+                }
+                minor_cursor += 1;
+            },
+        }
+        occupy(&mut occupied, major_cursor, minor_cursor, (major.span, minor.span));
+        placed[index] = Some((major_cursor, minor_cursor));
+        minor_cursor += minor.span;
+    }
+
+    placed.into_iter().map(|position| position.unwrap_or_default()).collect()
+}
+
+/// Sizes the tracks of one axis from their sizing functions and the items placed in them.
+/// `available` is the container's inner size on this axis, when definite.
+pub(crate) fn size_tracks(tracks: &[TrackSize], items: &[TrackContribution], available: Option<f32>, gap: f32) -> Vec<f32> {
+    let gaps = gap * tracks.len().saturating_sub(1) as f32;
+    let mut base: Vec<f32> = tracks.iter().map(|track| track.min.fixed(available).unwrap_or(0.0)).collect();
+    // `None` is an infinite growth limit
+    let mut limit: Vec<Option<f32>> = tracks.iter().map(|track| track.max.fixed(available)).collect();
+    let crosses_flexible = |item: &TrackContribution| tracks[item.start..item.start + item.span].iter().any(|track| track.flex_factor().is_some());
+
+    // Items spanning a single track size it directly
+    for item in items.iter().filter(|item| item.span == 1) {
+        let track = tracks[item.start];
+        if track.min.is_intrinsic() {
+            let contribution = if track.min == TrackBreadth::MaxContent { item.max_content } else { item.min_content };
+            base[item.start] = base[item.start].max(contribution);
+        }
+        if track.max.is_intrinsic() {
+            let contribution = if track.max == TrackBreadth::MinContent { item.min_content } else { item.max_content };
+            limit[item.start] = Some(limit[item.start].unwrap_or(0.0).max(contribution));
+        }
+    }
+
+    // Wider items share whatever they still need equally between the intrinsic tracks they span,
+    // and only grow flexible tracks when they cross one
+    let mut spanning: Vec<&TrackContribution> = items.iter().filter(|item| item.span > 1).collect();
+    spanning.sort_by_key(|item| item.span);
+    for item in spanning {
+        let range = item.start..item.start + item.span;
+        let spanned_gaps = gap * (item.span - 1) as f32;
+        let flexible = crosses_flexible(item);
+        let growable: Vec<usize> = range.clone().filter(|&index| tracks[index].min.is_intrinsic() && (!flexible || tracks[index].flex_factor().is_some())).collect();
+        if growable.is_empty() {
+            continue;
+        }
+
+        let extra = item.min_content - base[range.clone()].iter().sum::<f32>() - spanned_gaps;
+        if extra > 0.0 {
+            for &index in &growable {
+                base[index] += extra / growable.len() as f32;
+            }
+        }
+        if !flexible {
+            let current: f32 = range.clone().map(|index| limit[index].unwrap_or(base[index])).sum();
+            let extra = item.max_content - current - spanned_gaps;
+            let limited: Vec<usize> = range.filter(|&index| tracks[index].max.is_intrinsic()).collect();
+            for &index in &limited {
+                let share = if extra > 0.0 { extra / limited.len() as f32 } else { 0.0 };
+                limit[index] = Some(limit[index].unwrap_or(base[index]) + share);
+            }
+        }
+    }
+
+    let is_flexible: Vec<bool> = tracks.iter().map(|track| track.flex_factor().is_some()).collect();
+    // Tracks without content keep their base size as their limit; flexible ones are sized separately below
+    let limit: Vec<f32> = (0..tracks.len()).map(|index| if is_flexible[index] { base[index] } else { limit[index].map_or(base[index], |limit| limit.max(base[index])) }).collect();
+
+    // Grow tracks towards their growth limits while there is space
+    if let Some(available) = available {
+        let mut free = available - gaps - base.iter().sum::<f32>();
+        while free > 0.01 {
+            let growing: Vec<usize> = (0..tracks.len()).filter(|&index| !is_flexible[index] && base[index] < limit[index]).collect();
+            if growing.is_empty() {
+                break;
+            }
+            let share = free / growing.len() as f32;
+            for index in growing {
+                let grow = share.min(limit[index] - base[index]);
+                base[index] += grow;
+                free -= grow;
+            }
+        }
+    }
+
+    // Flexible tracks split the leftover space by their factors, but never shrink below their base size
+    if is_flexible.iter().any(|&flexible| flexible) {
+        let factor = |index: usize| tracks[index].flex_factor().unwrap_or(0.0);
+        let fr_size = match available {
+            Some(available) => {
+                let mut inflexible: Vec<bool> = is_flexible.iter().map(|flexible| !flexible).collect();
+                loop {
+                    let space = available - gaps - (0..tracks.len()).filter(|&index| inflexible[index]).map(|index| base[index]).sum::<f32>();
+                    let factors: f32 = (0..tracks.len()).filter(|&index| !inflexible[index]).map(factor).sum::<f32>().max(1.0);
+                    let fr_size = (space / factors).max(0.0);
+                    let too_small: Vec<usize> = (0..tracks.len()).filter(|&index| !inflexible[index] && factor(index) * fr_size < base[index]).collect();
+                    if too_small.is_empty() {
+                        break fr_size;
+                    }
+                    for index in too_small {
+                        inflexible[index] = true;
+                    }
+                }
+            }
+            // Without a definite size, an `fr` is as big as the largest content needs it to be
+            None => {
+                let per_fr = |size: f32, factor: f32| if factor > 1.0 { size / factor } else { size };
+                let from_tracks = (0..tracks.len()).filter(|&index| is_flexible[index]).map(|index| per_fr(base[index], factor(index))).fold(0.0, f32::max);
+                items
+                    .iter()
+                    .filter(|item| crosses_flexible(item))
+                    .map(|item| {
+                        let range = item.start..item.start + item.span;
+                        let fixed: f32 = range.clone().filter(|&index| !is_flexible[index]).map(|index| base[index]).sum();
+                        let factors: f32 = range.map(factor).sum();
+                        per_fr(item.max_content - fixed - gap * (item.span - 1) as f32, factors)
+                    })
+                    .fold(from_tracks, f32::max)
+            }
+        };
+        for (size, track) in base.iter_mut().zip(tracks) {
+            if let Some(factor) = track.flex_factor() {
+                *size = size.max(factor * fr_size);
+            }
+        }
+    } else if let Some(available) = available {
+        // With no flexible tracks, `auto` tracks stretch to fill what is left
+        let free = available - gaps - base.iter().sum::<f32>();
+        let auto_tracks: Vec<usize> = (0..tracks.len()).filter(|&index| tracks[index].max == TrackBreadth::Auto).collect();
+        if free > 0.0 && !auto_tracks.is_empty() {
+            for &index in &auto_tracks {
+                base[index] += free / auto_tracks.len() as f32;
+            }
+        }
+    }
+
+    base
+}
+
+/// The start offset of each track from the content edge.
+pub(crate) fn track_offsets(sizes: &[f32], gap: f32) -> Vec<f32> {
+    let mut offset = 0.0;
+    sizes
+        .iter()
+        .map(|size| {
+            let start = offset;
+            offset += size + gap;
+            start
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn areas(rows: &[&str]) -> Result<GridTemplateAreas, String> {
+        GridTemplateAreas::parse(&rows.iter().map(|row| row.to_string()).collect::<Vec<_>>())
+    }
+
+    fn placement(start: GridLine, end: GridLine) -> GridPlacement {
+        GridPlacement { start: Some(start), end: Some(end) }
+    }
+
+    fn fixed(start: usize, span: usize) -> GridSpan {
+        GridSpan { start: Some(start), span }
+    }
+
+    fn auto(span: usize) -> GridSpan {
+        GridSpan { start: None, span }
+    }
+
+    #[test]
+    fn parses_template_areas() {
+        let template = areas(&["head head", "side main", ". main"]).unwrap();
+        assert_eq!((template.rows, template.columns), (3, 2));
+        assert_eq!(template.area("head"), Some(&GridArea { name: "head".to_string(), row_start: 0, row_end: 1, column_start: 0, column_end: 2 }));
+        assert_eq!(template.area("main"), Some(&GridArea { name: "main".to_string(), row_start: 1, row_end: 3, column_start: 1, column_end: 2 }));
+        assert_eq!(template.area("."), None);
+
+        assert!(areas(&["a b", "c"]).is_err());
+        assert!(areas(&[]).is_err());
+        assert!(areas(&["a b", "b b"]).is_err());
+        assert!(areas(&["a b a"]).is_err());
+    }
+
+    #[test]
+    fn resolves_lines_spans_and_names() {
+        let none = GridTemplateAreas::default();
+        let resolve = |start: GridLine, end: GridLine| {
+            let span = placement(start, end).resolve(3, &none, false);
+            (span.start, span.span)
+        };
+        assert_eq!(resolve(GridLine::Line(2), GridLine::Line(4)), (Some(1), 2));
+        assert_eq!(resolve(GridLine::Line(4), GridLine::Line(2)), (Some(1), 2));
+        assert_eq!(resolve(GridLine::Line(2), GridLine::Line(2)), (Some(1), 1));
+        assert_eq!(resolve(GridLine::Line(1), GridLine::Line(-1)), (Some(0), 3));
+        assert_eq!(resolve(GridLine::Line(-10), GridLine::Line(2)), (Some(0), 1));
+        assert_eq!(resolve(GridLine::Line(2), GridLine::Span(2)), (Some(1), 2));
+        assert_eq!(resolve(GridLine::Span(2), GridLine::Line(2)), (Some(0), 2));
+        assert_eq!(resolve(GridLine::Span(3), GridLine::Auto), (None, 3));
+        assert_eq!(resolve(GridLine::Auto, GridLine::Auto), (None, 1));
+        let unspecified = GridPlacement { start: Some(GridLine::Line(3)), end: None }.resolve(3, &none, false);
+        assert_eq!((unspecified.start, unspecified.span), (Some(2), 1));
+
+        let template = areas(&["a a b", "c c b"]).unwrap();
+        let resolve_named = |start: &str, end: &str, is_row: bool| {
+            let span = placement(GridLine::Named(start.to_string()), GridLine::Named(end.to_string())).resolve(3, &template, is_row);
+            (span.start, span.span)
+        };
+        assert_eq!(resolve_named("a", "a", false), (Some(0), 2));
+        assert_eq!(resolve_named("b", "b", true), (Some(0), 2));
+        assert_eq!(resolve_named("a-start", "b-end", false), (Some(0), 3));
+        // An unknown name leaves the item to auto-placement
+        assert_eq!(resolve_named("x", "x", false), (None, 1));
+    }
+
+    #[test]
+    fn auto_places_items_around_fixed_ones() {
+        // A three-column grid where the first item takes the middle of the first row
+        let spans = [(fixed(0, 1), fixed(1, 1)), (auto(1), auto(1)), (auto(1), auto(2)), (auto(1), auto(1)), (fixed(2, 1), auto(1))];
+        assert_eq!(place_items(&spans, GridAutoFlow::Row, 0, 3), [(0, 1), (0, 0), (1, 0), (1, 2), (2, 0)]);
+
+        // Sparse placement doesn't go back to fill the hole left by the wide item
+        let spans = [(auto(1), auto(1)), (auto(1), auto(3)), (auto(1), auto(1))];
+        assert_eq!(place_items(&spans, GridAutoFlow::Row, 0, 3), [(0, 0), (1, 0), (2, 0)]);
+
+        let spans = [(auto(1), auto(1)), (auto(1), auto(1)), (auto(1), auto(1))];
+        assert_eq!(place_items(&spans, GridAutoFlow::Column, 2, 0), [(0, 0), (1, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn repeats_auto_fill_tracks_to_fit() {
+        let px = |size: f32| TrackSize::new(TrackBreadth::Length(Length::Px(size)));
+        let list = TrackList { tracks: vec![px(50.0)], auto_repeat: Some(AutoRepeat { index: 1, tracks: vec![px(100.0)] }) };
+        assert_eq!(list.expand(Some(380.0), 10.0).len(), 4);
+        assert_eq!(list.expand(Some(379.0), 10.0).len(), 3);
+        assert_eq!(list.expand(Some(100.0), 10.0).len(), 2);
+        assert_eq!(list.expand(None, 10.0).len(), 2);
+    }
+
+    #[test]
+    fn flexible_tracks_share_the_free_space() {
+        let tracks = [TrackSize::new(TrackBreadth::Length(Length::Px(100.0))), TrackSize::new(TrackBreadth::Fr(1.0)), TrackSize::new(TrackBreadth::Fr(2.0))];
+        assert_eq!(size_tracks(&tracks, &[], Some(410.0), 5.0), [100.0, 100.0, 200.0]);
+
+        // Content wider than its share keeps its track from shrinking, and the other track takes what is left
+        let items = [TrackContribution { start: 1, span: 1, min_content: 250.0, max_content: 250.0 }];
+        assert_eq!(size_tracks(&tracks, &items, Some(410.0), 5.0), [100.0, 250.0, 50.0]);
+    }
+}
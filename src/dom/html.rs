@@ -0,0 +1,562 @@
+// /src/dom/html.rs
+
+use std::fmt;
+
+use crate::dom::cssparser::{CssParseError, Stylesheet};
+use crate::dom::dom::{Dom, NodeContent, NodeId};
+use crate::dom::element::Element;
+use crate::dom::styleengine::Style;
+use crate::dom::text::Text;
+
+/// A parse failure with the 1-based line and column it occurred at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlParseError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for HtmlParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for HtmlParseError {}
+
+/// Elements that have no content and no closing tag.
+const VOID_ELEMENTS: [&str; 14] = ["area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr"];
+
+/// Elements that are blocks by default; white space between them and their siblings isn't rendered.
+const BLOCK_ELEMENTS: [&str; 43] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "dd",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+
+impl Dom {
+    /// Builds a new `Dom` with the nodes of an HTML document or fragment under its root.
+    pub fn from_html(html: &str) -> Result<Dom, HtmlParseError> {
+        let mut dom = Dom::new();
+        let root_id = dom.root;
+        dom.append_html(root_id, html)?;
+        Ok(dom)
+    }
+
+    /// Parses HTML and appends the nodes to `parent_id`'s children, returning the top-level ones.
+    ///
+    /// Supports elements, attributes, text, character references, `style` attributes and `<style>`
+    /// blocks, which are added as stylesheets. Comments, doctypes and `<script>` are skipped, as is
+    /// whitespace-only text at the top level or next to a block-level element such as `div` or `p`.
+    /// Other whitespace is kept for inline layout to collapse, so `<b>a</b> <i>b</i>` keeps its
+    /// space. Closing tags may be omitted. Nothing is added when the input fails to parse; `parent_id` must be an element.
+    pub fn append_html(&mut self, parent_id: NodeId, html: &str) -> Result<Vec<NodeId>, HtmlParseError> {
+        let mut parser = HtmlParser { input: html, pos: 0, stylesheets: Vec::new() };
+        let nodes = parser.parse()?;
+
+        for stylesheet in parser.stylesheets {
+            self.add_stylesheet(stylesheet);
+        }
+        let mut top_level = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            let node_id = self.build_html_node(node);
+            let appended = self.append_child(parent_id, node_id);
+            debug_assert!(appended.is_ok(), "can't append HTML to {:?}: {:?}", parent_id, appended);
+            top_level.push(node_id);
+        }
+        Ok(top_level)
+    }
+
+    /// Writes a subtree as HTML, with inline styles in `style` attributes. Unnamed elements are
+    /// written as `div`; stylesheets and the fonts of text nodes are not included.
+    pub fn to_html(&self, node_id: NodeId) -> String {
+        let mut html = String::new();
+        self.write_html(node_id, &mut html, None);
+        html
+    }
+
+    /// Like `to_html`, with each element that only contains block-level elements spreading them over
+    /// indented lines. Parsing the result gives back the same tree, since whitespace next to block-level
+    /// elements is skipped; elements with inline children are written on one line.
+    pub fn to_html_pretty(&self, node_id: NodeId) -> String {
+        let mut html = String::new();
+        self.write_html(node_id, &mut html, Some(0));
+        html
+    }
+
+    fn build_html_node(&mut self, node: HtmlNode) -> NodeId {
+        match node {
+            HtmlNode::Text(content) => self.create_text(Text::new(content)),
+            HtmlNode::Element { element, style, children } => {
+                let node_id = self.create_element(element);
+                if let Some(style) = style {
+                    self.set_style(node_id, *style);
+                }
+                for child in children {
+                    let child_id = self.build_html_node(child);
+                    self.append_child(node_id, child_id).expect("a new element takes any new node");
+                }
+                node_id
+            }
+        }
+    }
+
+    fn write_html(&self, node_id: NodeId, html: &mut String, depth: Option<usize>) {
+        let element = match self.content.get(node_id.into()) {
+            Some(NodeContent::Text(text)) => {
+                html.push_str(&escape(&text.content, false));
+                return;
+            }
+            Some(NodeContent::Element(element)) => element,
+            None => return,
+        };
+
+        let name = element.name.as_deref().unwrap_or("div");
+        html.push('<');
+        html.push_str(name);
+        let class = (!element.class_list.is_empty()).then(|| element.class_list.to_string());
+        let style = self.styles.get(node_id.into()).map(Style::to_css).filter(|css| !css.is_empty());
+        let attributes = element
+            .id
+            .iter()
+            .map(|id| ("id", id))
+            .chain(class.iter().map(|class| ("class", class)))
+            .chain(element.attributes.iter().map(|(name, value)| (name.as_str(), value)))
+            .chain(style.iter().map(|style| ("style", style)));
+        for (attribute, value) in attributes {
+            html.push_str(&format!(" {}=\"{}\"", attribute, escape(value, true)));
+        }
+        html.push('>');
+
+        let children = self.children(node_id).map_or(&[][..], Vec::as_slice);
+        if children.is_empty() && VOID_ELEMENTS.contains(&name) {
+            return;
+        }
+        // Indenting around text or inline elements would add whitespace to the text
+        let indent = depth.filter(|_| !children.is_empty() && children.iter().all(|&child_id| self.is_block_element(child_id)));
+        for &child_id in children {
+            if let Some(depth) = indent {
+                html.push('\n');
+                html.push_str(&"  ".repeat(depth + 1));
+            }
+            self.write_html(child_id, html, depth.map(|depth| depth + 1));
+        }
+        if let Some(depth) = indent {
+            html.push('\n');
+            html.push_str(&"  ".repeat(depth));
+        }
+        html.push_str(&format!("</{}>", name));
+    }
+
+    /// Whether a node is an element `to_html` writes with a block-level name.
+    fn is_block_element(&self, node_id: NodeId) -> bool {
+        match self.content.get(node_id.into()) {
+            Some(NodeContent::Element(element)) => element.name.as_deref().is_none_or(|name| BLOCK_ELEMENTS.contains(&name)),
+            _ => false,
+        }
+    }
+}
+
+enum HtmlNode {
+    Element { element: Element, style: Option<Box<Style>>, children: Vec<HtmlNode> },
+    Text(String),
+}
+
+impl HtmlNode {
+    fn is_block(&self) -> bool {
+        matches!(self, HtmlNode::Element { element, .. } if element.name.as_deref().is_some_and(|name| BLOCK_ELEMENTS.contains(&name)))
+    }
+
+    fn is_whitespace(&self) -> bool {
+        matches!(self, HtmlNode::Text(text) if text.chars().all(Dom::is_collapsible_space))
+    }
+}
+
+/// An element whose closing tag hasn't been reached yet.
+struct OpenElement {
+    element: Element,
+    style: Option<Box<Style>>,
+    children: Vec<HtmlNode>,
+}
+
+impl OpenElement {
+    fn close(mut self) -> HtmlNode {
+        let keep_whitespace = self.element.name.as_deref() == Some("pre");
+        if !keep_whitespace {
+            drop_whitespace_around_blocks(&mut self.children);
+        }
+        HtmlNode::Element {
+            element: self.element,
+            style: self.style,
+            children: self.children,
+        }
+    }
+}
+
+struct HtmlParser<'a> {
+    input: &'a str,
+    pos: usize,
+    stylesheets: Vec<Stylesheet>,
+}
+
+impl HtmlParser<'_> {
+    fn parse(&mut self) -> Result<Vec<HtmlNode>, HtmlParseError> {
+        let mut top_level = Vec::new();
+        let mut open: Vec<OpenElement> = Vec::new();
+
+        while self.pos < self.input.len() {
+            let rest = &self.input[self.pos..];
+            if rest.starts_with("<!--") {
+                let end = rest.find("-->").ok_or_else(|| self.error_at(self.pos, "unterminated comment"))?;
+                self.pos += end + 3;
+            } else if rest.starts_with("<!") || rest.starts_with("<?") {
+                let end = rest.find('>').ok_or_else(|| self.error_at(self.pos, "unterminated declaration"))?;
+                self.pos += end + 1;
+            } else if rest.starts_with("</") {
+                let start = self.pos;
+                self.pos += 2;
+                let name = self.name().to_ascii_lowercase();
+                self.skip_whitespace();
+                if !self.eat(">") {
+                    return Err(self.error_at(self.pos, "expected `>` after the closing tag name"));
+                }
+                // Closing an outer element closes the ones left open inside it
+                let depth = open
+                    .iter()
+                    .rposition(|element| element.element.name.as_deref() == Some(name.as_str()))
+                    .ok_or_else(|| self.error_at(start, format!("`</{}>` doesn't close any open element", name)))?;
+                while open.len() > depth {
+                    let node = open.pop().expect("just checked").close();
+                    push_node(&mut open, &mut top_level, node);
+                }
+            } else if rest.starts_with('<') && rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+                self.pos += 1;
+                let (element, style, self_closing) = self.start_tag()?;
+                let name = element.name.clone().unwrap_or_default();
+                match name.as_str() {
+                    "style" | "script" if !self_closing => {
+                        let (content_start, content_end) = self.raw_text(&name)?;
+                        if name == "style" {
+                            let stylesheet = Stylesheet::parse(&self.input[content_start..content_end]).map_err(|error| self.css_error_at(content_start, error))?;
+                            self.stylesheets.push(stylesheet);
+                        }
+                    }
+                    _ if self_closing || VOID_ELEMENTS.contains(&name.as_str()) => {
+                        push_node(&mut open, &mut top_level, HtmlNode::Element { element, style, children: Vec::new() });
+                    }
+                    _ => open.push(OpenElement { element, style, children: Vec::new() }),
+                }
+            } else {
+                // A `<` that doesn't start a tag is text
+                let skip = rest.chars().next().map_or(0, char::len_utf8);
+                let end = rest[skip..].find('<').map_or(rest.len(), |end| end + skip);
+                let text = decode_entities(&rest[..end]);
+                self.pos += end;
+                push_node(&mut open, &mut top_level, HtmlNode::Text(text));
+            }
+        }
+
+        // Elements still open at the end are closed there
+        while let Some(element) = open.pop() {
+            push_node(&mut open, &mut top_level, element.close());
+        }
+        top_level.retain(|node| !node.is_whitespace());
+        Ok(top_level)
+    }
+
+    /// Parses a start tag after its `<`, up to and including its `>`. Also returns whether it ended with `/>`.
+    fn start_tag(&mut self) -> Result<(Element, Option<Box<Style>>, bool), HtmlParseError> {
+        let name = self.name().to_ascii_lowercase();
+        let mut element = Element::new().with_name(&name);
+        let mut style = None;
+
+        loop {
+            self.skip_whitespace();
+            if self.eat("/>") {
+                return Ok((element, style, true));
+            }
+            if self.eat(">") {
+                return Ok((element, style, false));
+            }
+            let attribute_start = self.pos;
+            let attribute = self.name().to_ascii_lowercase();
+            if attribute.is_empty() {
+                return match self.input[self.pos..].chars().next() {
+                    Some(c) => Err(self.error_at(self.pos, format!("unexpected `{}` in tag", c))),
+                    None => Err(self.error_at(self.pos, format!("unterminated `<{}>` tag", name))),
+                };
+            }
+            self.skip_whitespace();
+            let value = if self.eat("=") {
+                self.skip_whitespace();
+                self.attribute_value()?
+            } else {
+                String::new()
+            };
+
+            if attribute == "style" {
+                let value_start = attribute_start + attribute.len();
+                style = Some(Box::new(Style::from_css(&value).map_err(|error| self.error_at(value_start, format!("invalid style attribute: {}", error.message)))?));
+            } else {
+                element.set_attribute(&attribute, &value);
+            }
+        }
+    }
+
+    fn attribute_value(&mut self) -> Result<String, HtmlParseError> {
+        let rest = &self.input[self.pos..];
+        let value = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let end = rest[1..].find(quote).ok_or_else(|| self.error_at(self.pos, "unterminated attribute value"))?;
+                self.pos += end + 2;
+                &rest[1..end + 1]
+            }
+            _ => {
+                let end = rest.find(|c: char| c.is_whitespace() || c == '>').unwrap_or(rest.len());
+                self.pos += end;
+                &rest[..end]
+            }
+        };
+        Ok(decode_entities(value))
+    }
+
+    /// Where the content of a `<style>` or `<script>` element starts and ends; only its closing tag ends it.
+    fn raw_text(&mut self, name: &str) -> Result<(usize, usize), HtmlParseError> {
+        let start = self.pos;
+        let closing = format!("</{}", name);
+        let end = self.input[start..].to_ascii_lowercase().find(&closing).map(|end| start + end).ok_or_else(|| self.error_at(start, format!("missing `</{}>`", name)))?;
+        let close_end = self.input[end..].find('>').map(|close| end + close + 1).ok_or_else(|| self.error_at(end, format!("unterminated `</{}>` tag", name)))?;
+        self.pos = close_end;
+        Ok((start, end))
+    }
+
+    fn name(&mut self) -> &str {
+        let rest = &self.input[self.pos..];
+        let end = rest.find(|c: char| c.is_whitespace() || matches!(c, '>' | '/' | '=' | '<' | '"' | '\'')).unwrap_or(rest.len());
+        self.pos += end;
+        &rest[..end]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, expected: &str) -> bool {
+        let found = self.input[self.pos..].starts_with(expected);
+        if found {
+            self.pos += expected.len();
+        }
+        found
+    }
+
+    fn line_and_column(&self, offset: usize) -> (usize, usize) {
+        let before = &self.input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().map_or(0, |current| current.chars().count()) + 1;
+        (line, column)
+    }
+
+    fn error_at(&self, offset: usize, message: impl Into<String>) -> HtmlParseError {
+        let (line, column) = self.line_and_column(offset);
+        HtmlParseError { message: message.into(), line, column }
+    }
+
+    /// Moves an error in a `<style>` block to where it is in the whole document.
+    fn css_error_at(&self, content_start: usize, error: CssParseError) -> HtmlParseError {
+        let (line, column) = self.line_and_column(content_start);
+        HtmlParseError {
+            message: format!("invalid stylesheet: {}", error.message),
+            line: line + error.line - 1,
+            column: if error.line == 1 { column + error.column - 1 } else { error.column },
+        }
+    }
+}
+
+fn push_node(open: &mut [OpenElement], top_level: &mut Vec<HtmlNode>, node: HtmlNode) {
+    let siblings = match open.last_mut() {
+        Some(parent) => &mut parent.children,
+        None => top_level,
+    };
+    // Text split by a comment is still one text node
+    if let (Some(HtmlNode::Text(previous)), HtmlNode::Text(text)) = (siblings.last_mut(), &node) {
+        previous.push_str(text);
+    } else {
+        siblings.push(node);
+    }
+}
+
+/// Removes whitespace-only text before or after a block-level element, which isn't rendered.
+fn drop_whitespace_around_blocks(children: &mut Vec<HtmlNode>) {
+    let next_to_block: Vec<bool> = (0..children.len()).map(|index| (index > 0 && children[index - 1].is_block()) || children.get(index + 1).is_some_and(HtmlNode::is_block)).collect();
+    let mut index = 0;
+    children.retain(|node| {
+        let keep = !(next_to_block[index] && node.is_whitespace());
+        index += 1;
+        keep
+    });
+}
+
+/// Replaces character references such as `&amp;`, `&#160;` and `&#xA0;`. Unknown ones are kept as written.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let reference = rest[1..].find(';').map(|end| &rest[1..end + 1]).filter(|reference| !reference.is_empty() && reference.len() <= 8);
+        let character = reference.and_then(|reference| match reference {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => {
+                let code = match reference.strip_prefix('#') {
+                    Some(hex) if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16).ok(),
+                    Some(decimal) => decimal.parse().ok(),
+                    None => None,
+                };
+                code.and_then(char::from_u32)
+            }
+        });
+        match (reference, character) {
+            (Some(reference), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[reference.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn escape(text: &str, in_attribute: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if in_attribute => escaped.push_str("&quot;"),
+            '\u{a0}' => escaped.push_str("&nbsp;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_of(dom: &Dom, node_id: NodeId) -> &str {
+        match dom.content.get(node_id.into()) {
+            Some(NodeContent::Text(text)) => &text.content,
+            _ => panic!("not a text node"),
+        }
+    }
+
+    #[test]
+    fn parses_non_ascii_text() {
+        let dom = Dom::from_html("<p>été</p><p>日本語 &amp; テキスト</p>").unwrap();
+        let paragraphs = dom.children(dom.root).unwrap().clone();
+        assert_eq!(paragraphs.len(), 2);
+        assert_eq!(text_of(&dom, dom.children(paragraphs[0]).unwrap()[0]), "été");
+        assert_eq!(text_of(&dom, dom.children(paragraphs[1]).unwrap()[0]), "日本語 & テキスト");
+    }
+
+    #[test]
+    fn decodes_character_references() {
+        let dom = Dom::from_html("<p>&lt;a&gt; &#65;&#x42; &nbsp;&amp;&unknown; & x</p>").unwrap();
+        let paragraph = dom.children(dom.root).unwrap()[0];
+        assert_eq!(text_of(&dom, dom.children(paragraph).unwrap()[0]), "<a> AB \u{a0}&&unknown; & x");
+    }
+
+    #[test]
+    fn closes_elements_left_open_and_skips_comments() {
+        let dom = Dom::from_html("<!DOCTYPE html><div id=\"a\"><p>one<br><img src=\"x\"/><!-- note --></div><p>two").unwrap();
+        let top_level = dom.children(dom.root).unwrap();
+        assert_eq!(top_level.len(), 2);
+        assert_eq!(dom.to_html(top_level[0]), "<div id=\"a\"><p>one<br><img src=\"x\"></p></div>");
+        assert_eq!(dom.to_html(top_level[1]), "<p>two</p>");
+    }
+
+    #[test]
+    fn reports_where_errors_are() {
+        let error = |html: &str| Dom::from_html(html).map(|_| ()).unwrap_err();
+        let unmatched = error("<div>\n  </span>");
+        assert_eq!((unmatched.line, unmatched.column), (2, 3));
+        let unterminated = error("<p>é</p><!-- open");
+        assert_eq!((unterminated.line, unterminated.column), (1, 9));
+        assert!(error("<div style=\"color: \">").message.starts_with("invalid style attribute"));
+    }
+
+    #[test]
+    fn keeps_whitespace_between_inline_elements() {
+        let dom = Dom::from_html("<div>\n  <p><span class=\"kw\">let</span> <span>x</span></p>\n  <p>y</p>\n</div>\n").unwrap();
+        let div = dom.children(dom.root).unwrap()[0];
+        let paragraphs = dom.children(div).unwrap().clone();
+        assert_eq!(paragraphs.len(), 2);
+        let spans = dom.children(paragraphs[0]).unwrap();
+        assert_eq!(spans.len(), 3);
+        assert_eq!(text_of(&dom, spans[1]), " ");
+        assert_eq!(dom.to_html(div), "<div><p><span class=\"kw\">let</span> <span>x</span></p><p>y</p></div>");
+    }
+
+    #[test]
+    fn pretty_html_parses_back_to_the_same_tree() {
+        let html = "<div><p><b>a</b> <i>b</i></p><ul><li>c</li><li><span>d</span></li></ul></div>";
+        let dom = Dom::from_html(html).unwrap();
+        let div = dom.children(dom.root).unwrap()[0];
+        let pretty = dom.to_html_pretty(div);
+        assert!(pretty.contains("\n    <li>c</li>"));
+        let reparsed = Dom::from_html(&pretty).unwrap();
+        assert_eq!(reparsed.to_html(reparsed.children(reparsed.root).unwrap()[0]), html);
+    }
+}
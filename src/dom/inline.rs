@@ -0,0 +1,588 @@
+// /src/dom/inline.rs
+
+use std::ops::Range;
+
+use skia_safe::Point;
+use skia_safe::textlayout::{Paragraph, ParagraphBuilder, PlaceholderAlignment, PlaceholderStyle, RectHeightStyle, RectWidthStyle, TextBaseline, TextStyle};
+
+use super::dom::{Dom, NodeContent, NodeId};
+use super::fontmanager::get_thread_local_font_collection;
+use super::layoutengine::{GlyphRun, LayoutData, Rect, TextInfo, get_line_bounds};
+use super::styleengine::{ComputedStyle, Display, Float, VerticalAlign, WhiteSpace};
+
+/// The part of an inline element's box on one line. An element that wraps gets one per line;
+/// only the first has the border and padding of its start edge, and only the last those of its end edge.
+#[derive(Debug, Clone, Copy)]
+pub struct InlineFragment {
+    /// The border box of the part, relative to the element's layout origin
+    pub rect: Rect,
+    pub first: bool,
+    pub last: bool,
+}
+
+/// Something placed on the lines, covering a byte range of the paragraph text. Everything
+/// but text is a placeholder character.
+struct InlineItem {
+    node_id: NodeId,
+    kind: ItemKind,
+    range: Range<usize>,
+    range16: Range<usize>,
+}
+
+enum ItemKind {
+    Text(TextStyle),
+    /// The margin, border and padding on one edge of an inline element, or the first line's indent
+    Space(f32),
+    /// An inline-block or other box laid out on its own and placed whole, like a glyph
+    Atomic {
+        width: f32,
+        height: f32,
+        alignment: PlaceholderAlignment,
+        baseline_offset: f32,
+        available_width: f32,
+    },
+    /// Where an out-of-flow box would have been
+    Marker,
+}
+
+/// An inline element whose contents were spread over the lines, and how far `vertical-align`
+/// moved its text down from the line's baseline.
+struct InlineBox {
+    node_id: NodeId,
+    range16: Range<usize>,
+    shift: f32,
+}
+
+/// The text and placeholders of a run of inline content, in the order they go on the lines.
+#[derive(Default)]
+struct InlineContent {
+    text: String,
+    len16: usize,
+    items: Vec<InlineItem>,
+    boxes: Vec<InlineBox>,
+    // Whether collapsible spaces at this point are dropped
+    after_space: bool,
+    // Whether anything makes a line, rather than only collapsed white space
+    has_content: bool,
+}
+
+impl InlineContent {
+    fn push(&mut self, node_id: NodeId, kind: ItemKind, text: &str) {
+        let (start, start16) = (self.text.len(), self.len16);
+        self.text.push_str(text);
+        self.len16 += text.encode_utf16().count();
+        self.items.push(InlineItem {
+            node_id,
+            kind,
+            range: start..self.text.len(),
+            range16: start16..self.len16,
+        });
+    }
+
+    fn push_placeholder(&mut self, node_id: NodeId, kind: ItemKind) {
+        self.push(node_id, kind, "\u{fffc}");
+    }
+}
+
+/// A line box, in absolute coordinates.
+struct LineBox {
+    top: f32,
+    height: f32,
+    baseline: f32,
+    /// Where the line would end if it were aligned to the left, for sizing boxes to their content
+    right: f32,
+    /// The text on the line, without trailing white space
+    range: Range<usize>,
+}
+
+/// Lines laid out in one paragraph, all starting at the same x. A float starting or ending
+/// beside the lines changes their width, and the lines after that go in a new segment.
+struct Segment {
+    paragraph: Paragraph,
+    origin: Point,
+    from: usize,
+    from16: usize,
+    lines: Range<usize>,
+}
+
+impl Dom {
+    /// Lays out a run of inline-level siblings as the lines of one paragraph starting at `y`, so
+    /// text wraps across element boundaries and around floats. Inline elements are broken into
+    /// a fragment per line, and inline-blocks sit on the lines like glyphs. Returns the width of
+    /// the widest line and the bottom of the last. `text_indent` is only given for the block's first line.
+    pub(crate) fn layout_inline_run(&mut self, node_ids: &[NodeId], content_box: Rect, container_style: &ComputedStyle, text_indent: f32, y: f32, floats: (&[Rect], &[Rect])) -> (f32, f32) {
+        let (left_floats, right_floats) = floats;
+        let mut content = InlineContent { after_space: true, ..InlineContent::default() };
+        // Placeholders can't be narrower than nothing, so negative indents are ignored
+        if text_indent > 0.0 {
+            content.push_placeholder(node_ids[0], ItemKind::Space(text_indent));
+        }
+        for &node_id in node_ids {
+            self.collect_inline_items(node_id, &mut content, 0.0, container_style.font_size, content_box.width);
+        }
+
+        if !content.has_content {
+            // Collapsed white space makes no lines
+            let empty = Rect { x: content_box.x, y, width: 0.0, height: 0.0 };
+            for item in &content.items {
+                match item.kind {
+                    ItemKind::Marker => self.set_static_position(item.node_id, content_box, y),
+                    ItemKind::Space(_) | ItemKind::Atomic { .. } => {}
+                    ItemKind::Text(_) => {
+                        self.text_info.insert(
+                            item.node_id.into(),
+                            TextInfo {
+                                lines: Vec::new(),
+                                line_heights: Vec::new(),
+                                line_widths: Vec::new(),
+                                ascent: 0.0,
+                                runs: Vec::new(),
+                            },
+                        );
+                        self.place_inline_node(item.node_id, empty, None);
+                    }
+                }
+            }
+            for inline_box in &content.boxes {
+                self.place_inline_node(inline_box.node_id, empty, Some(Vec::new()));
+            }
+            return (0.0, y);
+        }
+
+        let wrap = !matches!(container_style.white_space, WhiteSpace::NoWrap | WhiteSpace::Pre);
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut lines: Vec<LineBox> = Vec::new();
+        let mut from = 0;
+        let mut line_y = y;
+        loop {
+            let bounds = get_line_bounds(line_y, left_floats, right_floats, &content_box);
+            let mut paragraph = Self::build_inline_paragraph(&content, from, container_style);
+            Self::layout_paragraph(&mut paragraph, (bounds.1 - bounds.0).max(0.0), wrap);
+
+            let first_line = lines.len();
+            let mut restart = None;
+            let mut top = line_y;
+            for (index, line) in paragraph.get_line_metrics().iter().enumerate() {
+                let start = from + line.start_index;
+                if index > 0 && start > from && content.text.is_char_boundary(start) && get_line_bounds(top, left_floats, right_floats, &content_box) != bounds {
+                    restart = Some(start);
+                    break;
+                }
+                lines.push(LineBox {
+                    top,
+                    height: line.height as f32,
+                    baseline: line_y + line.baseline as f32,
+                    right: bounds.0 + line.width as f32,
+                    range: start..from + line.end_excluding_whitespaces,
+                });
+                top += line.height as f32;
+            }
+
+            let from16 = content.text[..from].encode_utf16().count();
+            segments.push(Segment {
+                paragraph,
+                origin: Point::new(bounds.0, line_y),
+                from,
+                from16,
+                lines: first_line..lines.len(),
+            });
+            match restart {
+                Some(start) => {
+                    from = start;
+                    line_y = top;
+                }
+                None => break,
+            }
+        }
+
+        // Where each placeholder ended up, with the line it is on
+        let mut placed: Vec<Option<(Rect, usize)>> = (0..content.items.len()).map(|_| None).collect();
+        for segment in &segments {
+            let placeholders = content.items.iter().enumerate().filter(|(_, item)| item.range.start >= segment.from && !matches!(item.kind, ItemKind::Text(_)));
+            for ((index, _), text_box) in placeholders.zip(segment.paragraph.get_rects_for_placeholders()) {
+                let rect = text_box.rect;
+                let absolute = Rect {
+                    x: segment.origin.x + rect.left,
+                    y: segment.origin.y + rect.top,
+                    width: rect.right - rect.left,
+                    height: rect.bottom - rect.top,
+                };
+                if let Some(line) = line_at(&lines, segment, absolute.y + absolute.height / 2.0) {
+                    placed[index] = Some((absolute, line));
+                }
+            }
+        }
+
+        // Glyph runs split between the text nodes they came from, in absolute coordinates
+        let mut runs: Vec<Vec<GlyphRun>> = (0..content.items.len()).map(|_| Vec::new()).collect();
+        for segment in &mut segments {
+            let (from, origin, segment_lines) = (segment.from, segment.origin, segment.lines.clone());
+            segment.paragraph.visit(|line, info| {
+                let Some(info) = info else {
+                    return;
+                };
+                let line = segment_lines.start + line;
+                if line >= segment_lines.end {
+                    return;
+                }
+                let run_origin = info.origin();
+                let starts = info.utf8_starts();
+                let mut current: Option<(usize, GlyphRun)> = None;
+                for (index, (glyph, position)) in info.glyphs().iter().zip(info.positions()).enumerate() {
+                    let byte = from + starts.get(index).copied().unwrap_or(0) as usize;
+                    let item = content.items.partition_point(|item| item.range.end <= byte);
+                    if item >= content.items.len() || !matches!(content.items[item].kind, ItemKind::Text(_)) {
+                        continue;
+                    }
+                    if current.as_ref().is_some_and(|(current_item, _)| *current_item != item) {
+                        let (finished_item, run) = current.take().unwrap();
+                        runs[finished_item].push(run);
+                    }
+                    let (_, run) = current.get_or_insert_with(|| {
+                        (
+                            item,
+                            GlyphRun {
+                                font: info.font().clone(),
+                                glyphs: Vec::new(),
+                                positions: Vec::new(),
+                                line,
+                            },
+                        )
+                    });
+                    run.glyphs.push(*glyph);
+                    run.positions.push(Point::new(origin.x + run_origin.x + position.x, origin.y + run_origin.y + position.y));
+                }
+                if let Some((item, run)) = current {
+                    runs[item].push(run);
+                }
+            });
+        }
+
+        for (index, item) in content.items.iter().enumerate() {
+            match &item.kind {
+                ItemKind::Text(_) => {
+                    // White space hanging off the end of a line doesn't count as being on it
+                    let extents: Vec<(usize, f32, f32)> = segments
+                        .iter()
+                        .flat_map(|segment| range_extents(segment, &lines, item.range16.clone()))
+                        .filter(|&(line, _, _)| item.range.start.max(lines[line].range.start) < item.range.end.min(lines[line].range.end))
+                        .collect();
+                    let mut text_info = TextInfo {
+                        lines: Vec::new(),
+                        line_heights: Vec::new(),
+                        line_widths: Vec::new(),
+                        ascent: 0.0,
+                        runs: Vec::new(),
+                    };
+                    let mut rect: Option<Rect> = None;
+                    for &(line, left, right) in &extents {
+                        let line_box = &lines[line];
+                        let range = item.range.start.max(line_box.range.start)..item.range.end.min(line_box.range.end);
+                        text_info.lines.push(content.text.get(range).unwrap_or_default().to_string());
+                        text_info.line_heights.push(line_box.height);
+                        text_info.line_widths.push(right - left);
+                        let line_rect = Rect {
+                            x: left,
+                            y: line_box.top,
+                            width: right - left,
+                            height: line_box.height,
+                        };
+                        rect = Some(rect.map_or(line_rect, |rect| rect.union(&line_rect)));
+                    }
+                    let rect = rect.unwrap_or(Rect { x: content_box.x, y, width: 0.0, height: 0.0 });
+                    if let Some(&(line, _, _)) = extents.first() {
+                        text_info.ascent = lines[line].baseline - rect.y;
+                    }
+
+                    // Glyphs are painted relative to the text's own box, on the lines it has
+                    for mut run in std::mem::take(&mut runs[index]) {
+                        let Some(line) = extents.iter().position(|&(line, _, _)| line == run.line) else {
+                            continue;
+                        };
+                        run.line = line;
+                        for position in &mut run.positions {
+                            position.x -= rect.x;
+                            position.y -= rect.y;
+                        }
+                        text_info.runs.push(run);
+                    }
+                    self.text_info.insert(item.node_id.into(), text_info);
+                    self.place_inline_node(item.node_id, rect, None);
+                }
+                ItemKind::Space(_) => {}
+                ItemKind::Atomic { available_width, .. } => {
+                    let position = placed[index].map_or(Rect { x: content_box.x, y, width: 0.0, height: 0.0 }, |(rect, _)| rect);
+                    self.layout_node(
+                        item.node_id,
+                        Rect {
+                            x: position.x,
+                            y: position.y,
+                            width: *available_width,
+                            height: f32::INFINITY,
+                        },
+                    );
+                }
+                ItemKind::Marker => {
+                    // A box after something on its line would have gone below it
+                    let static_y = match placed[index] {
+                        Some((rect, line)) if rect.x > segment_of(&segments, line).origin.x => lines[line].top + lines[line].height,
+                        Some((_, line)) => lines[line].top,
+                        None => y,
+                    };
+                    self.set_static_position(item.node_id, content_box, static_y);
+                }
+            }
+        }
+
+        for inline_box in &content.boxes {
+            let key = inline_box.node_id.into();
+            let style = self.computed_styles.get(key).cloned().unwrap_or_default();
+            let (ascent, descent) = Self::font_extents(&style);
+            let border = style.border.widths();
+            let padding = style.padding.resolve(content_box.width);
+            let margin = style.margin.resolve(content_box.width);
+
+            let extents: Vec<(usize, f32, f32)> = segments.iter().flat_map(|segment| range_extents(segment, &lines, inline_box.range16.clone())).collect();
+            let count = extents.len();
+            let rects: Vec<Rect> = extents
+                .iter()
+                .enumerate()
+                .map(|(index, &(line, left, right))| {
+                    // The placeholders on the edges hold the margins, which the border box doesn't include
+                    let left = if index == 0 { left + margin.left } else { left };
+                    let right = if index + 1 == count { right - margin.right } else { right };
+                    let baseline = lines[line].baseline + inline_box.shift;
+                    Rect {
+                        x: left,
+                        y: baseline - ascent - padding.top - border.top,
+                        width: (right - left).max(0.0),
+                        height: ascent + descent + padding.vertical() + border.vertical(),
+                    }
+                })
+                .collect();
+
+            let rect = rects.iter().skip(1).fold(rects.first().copied().unwrap_or(Rect { x: content_box.x, y, width: 0.0, height: 0.0 }), |union, rect| union.union(rect));
+            let fragments = rects
+                .iter()
+                .enumerate()
+                .map(|(index, fragment)| InlineFragment {
+                    rect: Rect {
+                        x: fragment.x - rect.x,
+                        y: fragment.y - rect.y,
+                        width: fragment.width,
+                        height: fragment.height,
+                    },
+                    first: index == 0,
+                    last: index + 1 == count,
+                })
+                .collect();
+            self.place_inline_node(inline_box.node_id, rect, Some(fragments));
+        }
+
+        let widest_line = lines.iter().map(|line| line.right - content_box.x).fold(0.0, f32::max);
+        let bottom = lines.last().map_or(y, |line| line.top + line.height);
+        (widest_line, bottom)
+    }
+
+    /// Whether an inline element's contents can join the lines of the block around it. Elements
+    /// holding blocks or floats are laid out as a single box instead.
+    fn is_inline_box(&self, node_id: NodeId) -> bool {
+        let key = node_id.into();
+        let Some(style) = self.computed_styles.get(key) else {
+            return false;
+        };
+        if !matches!(self.content.get(key), Some(NodeContent::Element(_))) || style.display != Display::Inline || style.float != Float::None || style.position.is_out_of_flow() {
+            return false;
+        }
+        self.children.get(key).into_iter().flatten().all(|&child_id| match self.computed_styles.get(child_id.into()) {
+            Some(child) => child.display == Display::None || child.position.is_out_of_flow() || (child.float == Float::None && !child.display.is_block_level()),
+            None => true,
+        })
+    }
+
+    /// Adds a node's text, edges and boxes to the inline content, descending into inline elements.
+    fn collect_inline_items(&mut self, node_id: NodeId, content: &mut InlineContent, shift: f32, parent_font_size: f32, available_width: f32) {
+        let key = node_id.into();
+        let style = self.computed_styles.get(key).cloned().unwrap_or_default();
+
+        if let Some(NodeContent::Text(text)) = self.content.get(key) {
+            let collapsed = Self::collapse_inline_text(&text.content, style.white_space, &mut content.after_space);
+            let collapsible = matches!(style.white_space, WhiteSpace::Normal | WhiteSpace::NoWrap | WhiteSpace::PreLine);
+            content.has_content |= if collapsible {
+                // Kept newlines end a line, but no-break spaces are content like any other character
+                let kept_newlines = style.white_space == WhiteSpace::PreLine;
+                text.content.chars().any(|character| !Self::is_collapsible_space(character) || (character == '\n' && kept_newlines))
+            } else {
+                !collapsed.is_empty()
+            };
+
+            let mut text_style = Self::text_style(&style);
+            if shift != 0.0 {
+                text_style.set_baseline_shift(shift);
+            }
+            content.push(node_id, ItemKind::Text(text_style), &collapsed);
+            return;
+        }
+
+        if style.display == Display::None {
+            self.layout_node(node_id, Rect::default());
+            return;
+        }
+        if style.position.is_out_of_flow() {
+            content.push_placeholder(node_id, ItemKind::Marker);
+            return;
+        }
+
+        if !self.is_inline_box(node_id) {
+            let size = self.layout_node(
+                node_id,
+                Rect {
+                    x: 0.0,
+                    y: 0.0,
+                    width: available_width,
+                    height: f32::INFINITY,
+                },
+            );
+            // A box without lines of text sits on the baseline by its bottom margin edge
+            let baseline = self.first_baseline(node_id).unwrap_or(size.height);
+            // Skia aligns placeholders to the surrounding font, so the line's top and bottom use the text's
+            let (alignment, baseline_offset) = match style.vertical_align {
+                VerticalAlign::Middle => (PlaceholderAlignment::Middle, 0.0),
+                VerticalAlign::TextTop | VerticalAlign::Top => (PlaceholderAlignment::Top, 0.0),
+                VerticalAlign::TextBottom | VerticalAlign::Bottom => (PlaceholderAlignment::Bottom, 0.0),
+                _ => (PlaceholderAlignment::Baseline, baseline - shift - baseline_shift(&style, parent_font_size)),
+            };
+            content.push_placeholder(
+                node_id,
+                ItemKind::Atomic {
+                    width: size.width,
+                    height: size.height,
+                    alignment,
+                    baseline_offset,
+                    available_width,
+                },
+            );
+            content.after_space = false;
+            content.has_content = true;
+            return;
+        }
+
+        let border = style.border.widths();
+        let padding = style.padding.resolve(available_width);
+        let margin = style.margin.resolve(available_width);
+        let shift = shift + baseline_shift(&style, parent_font_size);
+        let start16 = content.len16;
+
+        let start_edge = margin.left + border.left + padding.left;
+        if start_edge > 0.0 {
+            content.push_placeholder(node_id, ItemKind::Space(start_edge));
+            content.has_content = true;
+        }
+        for child_id in self.children.get(key).cloned().unwrap_or_default() {
+            self.collect_inline_items(child_id, content, shift, style.font_size, available_width);
+        }
+        let end_edge = margin.right + border.right + padding.right;
+        if end_edge > 0.0 {
+            content.push_placeholder(node_id, ItemKind::Space(end_edge));
+            content.has_content = true;
+        }
+
+        content.boxes.push(InlineBox { node_id, range16: start16..content.len16, shift });
+    }
+
+    /// The paragraph of the inline content from byte `from` on, with each text node in its own style.
+    fn build_inline_paragraph(content: &InlineContent, from: usize, container_style: &ComputedStyle) -> Paragraph {
+        let mut builder = ParagraphBuilder::new(&Self::paragraph_style(container_style), get_thread_local_font_collection());
+        for item in content.items.iter().filter(|item| item.range.end > from) {
+            let placeholder = match &item.kind {
+                ItemKind::Text(text_style) => {
+                    builder.push_style(text_style);
+                    builder.add_text(&content.text[item.range.start.max(from)..item.range.end]);
+                    builder.pop();
+                    continue;
+                }
+                ItemKind::Space(width) => PlaceholderStyle::new(*width, 0.0, PlaceholderAlignment::Baseline, TextBaseline::Alphabetic, 0.0),
+                ItemKind::Atomic { width, height, alignment, baseline_offset, .. } => PlaceholderStyle::new(*width, *height, *alignment, TextBaseline::Alphabetic, *baseline_offset),
+                ItemKind::Marker => PlaceholderStyle::new(0.0, 0.0, PlaceholderAlignment::Baseline, TextBaseline::Alphabetic, 0.0),
+            };
+            builder.add_placeholder(&placeholder);
+        }
+        builder.build()
+    }
+
+    /// Gives a text node or inline element the box the lines put it in, along with the
+    /// fragments of an element.
+    fn place_inline_node(&mut self, node_id: NodeId, rect: Rect, fragments: Option<Vec<InlineFragment>>) {
+        let key = node_id.into();
+        self.layout.insert(
+            key,
+            LayoutData {
+                computed_x: rect.x,
+                computed_y: rect.y,
+                actual_width: rect.width,
+                actual_height: rect.height,
+            },
+        );
+        if let Some(fragments) = fragments {
+            self.inline_fragments.insert(key, fragments);
+        }
+    }
+
+    fn set_static_position(&mut self, node_id: NodeId, content_box: Rect, y: f32) {
+        self.static_positions.insert(
+            node_id.into(),
+            Rect {
+                x: content_box.x,
+                y,
+                width: content_box.width,
+                height: f32::INFINITY,
+            },
+        );
+    }
+}
+
+/// How far `vertical-align` moves text down from its parent's baseline. Only the shifts
+/// along the baseline apply to text; aligning to the line's edges applies to atomic boxes.
+/// Percentages are of the box's own line height.
+fn baseline_shift(style: &ComputedStyle, parent_font_size: f32) -> f32 {
+    match style.vertical_align {
+        VerticalAlign::Sub => parent_font_size * 0.2,
+        VerticalAlign::Super => -parent_font_size / 3.0,
+        VerticalAlign::Length(length) => -length.resolve(Dom::calculate_line_height(style)),
+        _ => 0.0,
+    }
+}
+
+/// The line of a segment at height `y`, if that line was kept.
+fn line_at(lines: &[LineBox], segment: &Segment, y: f32) -> Option<usize> {
+    segment.lines.clone().find(|&line| y < lines[line].top + lines[line].height)
+}
+
+fn segment_of(segments: &[Segment], line: usize) -> &Segment {
+    segments.iter().find(|segment| segment.lines.contains(&line)).unwrap_or(&segments[0])
+}
+
+/// The left and right edges of a UTF-16 range of the text on each kept line of a segment it is on.
+fn range_extents(segment: &Segment, lines: &[LineBox], range16: Range<usize>) -> Vec<(usize, f32, f32)> {
+    if range16.end <= segment.from16 || range16.is_empty() {
+        return Vec::new();
+    }
+    let range = range16.start.max(segment.from16) - segment.from16..range16.end - segment.from16;
+
+    let mut extents: Vec<(usize, f32, f32)> = Vec::new();
+    for text_box in segment.paragraph.get_rects_for_range(range, RectHeightStyle::Tight, RectWidthStyle::Tight) {
+        let rect = text_box.rect;
+        let Some(line) = line_at(lines, segment, segment.origin.y + (rect.top + rect.bottom) / 2.0) else {
+            continue;
+        };
+        let (left, right) = (segment.origin.x + rect.left, segment.origin.x + rect.right);
+        match extents.iter_mut().find(|(extent_line, _, _)| *extent_line == line) {
+            Some(extent) => {
+                extent.1 = extent.1.min(left);
+                extent.2 = extent.2.max(right);
+            }
+            None => extents.push((line, left, right)),
+        }
+    }
+    extents
+}
@@ -0,0 +1,150 @@
+// /src/dom/invalidation.rs
+
+use super::dom::{Dom, NodeId};
+use super::layoutengine::Rect;
+use super::styleengine::Size;
+use super::traversal::DescendantCursor;
+
+/// The space a node was last laid out in and the rect that produced. A clean node offered
+/// the same size again gets its old layout back, moved if the space itself has moved.
+#[derive(Debug, Clone, Copy)]
+pub struct CachedLayout {
+    width: f32,
+    height: f32,
+    // Flex and grid layout force the sizes of their items through the computed style
+    style_width: Option<Size>,
+    style_height: Option<Size>,
+    origin: (f32, f32),
+    result: Rect,
+}
+
+/// Intrinsic widths of a text node, which only change with its text or font.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextWidths {
+    min_content: Option<f32>,
+    max_content: Option<f32>,
+}
+
+impl Dom {
+    /// Marks a node and its ancestors as needing layout; the next layout pass recomputes them
+    /// and reuses everything else.
+    pub fn mark_layout_dirty(&self, node_id: NodeId) {
+        let mut layout_dirty = self.layout_dirty.borrow_mut();
+        let mut current = Some(node_id);
+        while let Some(id) = current {
+            // The ancestors of a dirty node are always dirty already
+            if layout_dirty.insert(id.into(), true) == Some(true) {
+                break;
+            }
+            current = self.parent(id);
+        }
+    }
+
+    /// Drops what a text node measured after its content changed and marks it for layout.
+    /// Other text nodes keep their measurements.
+    pub(crate) fn invalidate_text(&mut self, node_id: NodeId) {
+        let key = node_id.into();
+        self.text_info.remove(key);
+        self.text_widths.remove(key);
+        self.mark_layout_dirty(node_id);
+    }
+
+    pub fn is_layout_dirty(&self, node_id: NodeId) -> bool {
+        *self.layout_dirty.borrow().get(node_id.into()).unwrap_or(&false)
+    }
+
+    /// Undoes the moves made after normal flow last time (relative, sticky and scroll offsets),
+    /// so cached layouts are in flow positions again, then forgets what dirty nodes measured.
+    pub(crate) fn prepare_layout_caches(&mut self) {
+        for (key, (dx, dy)) in self.post_layout_offsets.drain() {
+            if let Some(layout) = self.layout.get_mut(key) {
+                layout.computed_x -= dx;
+                layout.computed_y -= dy;
+            }
+            if let Some(static_position) = self.static_positions.get_mut(key) {
+                static_position.x -= dx;
+                static_position.y -= dy;
+            }
+        }
+
+        let dirty: Vec<slotmap::DefaultKey> = self.layout_dirty.borrow().keys().collect();
+        for key in dirty {
+            self.layout_cache.remove(key);
+            self.text_widths.remove(key);
+        }
+    }
+
+    pub(crate) fn cached_layout(&mut self, node_id: NodeId, available_space: Rect) -> Option<Rect> {
+        let key = node_id.into();
+        let cached = *self.layout_cache.get(key)?;
+        let style = self.computed_styles.get(key)?;
+        if self.is_layout_dirty(node_id) || cached.width != available_space.width || cached.height != available_space.height || cached.style_width != style.width || cached.style_height != style.height {
+            return None;
+        }
+
+        let (dx, dy) = (available_space.x - cached.origin.0, available_space.y - cached.origin.1);
+        if dx != 0.0 || dy != 0.0 {
+            self.shift_cached_subtree(node_id, dx, dy);
+        }
+        self.layout_cache.get(key).map(|cached| cached.result)
+    }
+
+    pub(crate) fn store_layout(&mut self, node_id: NodeId, available_space: Rect, result: Rect) {
+        let key = node_id.into();
+        let Some(style) = self.computed_styles.get(key) else {
+            return;
+        };
+        let cached = CachedLayout {
+            width: available_space.width,
+            height: available_space.height,
+            style_width: style.width,
+            style_height: style.height,
+            origin: (available_space.x, available_space.y),
+            result,
+        };
+        self.layout_cache.insert(key, cached);
+    }
+
+    /// Moves a reused layout along with the cache entries inside it, so they stay relative to where their boxes now are.
+    fn shift_cached_subtree(&mut self, node_id: NodeId, dx: f32, dy: f32) {
+        let mut cursor = DescendantCursor::new(node_id);
+        let mut current = Some(node_id);
+        while let Some(id) = current {
+            current = cursor.next(self);
+            let key = id.into();
+            if let Some(layout) = self.layout.get_mut(key) {
+                layout.computed_x += dx;
+                layout.computed_y += dy;
+            }
+            if let Some(static_position) = self.static_positions.get_mut(key) {
+                static_position.x += dx;
+                static_position.y += dy;
+            }
+            if let Some(cached) = self.layout_cache.get_mut(key) {
+                cached.origin = (cached.origin.0 + dx, cached.origin.1 + dy);
+                cached.result.x += dx;
+                cached.result.y += dy;
+            }
+        }
+    }
+
+    /// A text node's min-content or max-content width, measured once until the node is marked dirty.
+    pub(crate) fn cached_text_width(&mut self, node_id: NodeId, min_content: bool, measure: impl FnOnce() -> f32) -> f32 {
+        let key = node_id.into();
+        let widths = self.text_widths.get(key).copied().unwrap_or_default();
+        if let Some(width) = if min_content { widths.min_content } else { widths.max_content } {
+            return width;
+        }
+
+        let width = measure();
+        if let Some(entry) = self.text_widths.entry(key) {
+            let widths = entry.or_default();
+            if min_content {
+                widths.min_content = Some(width);
+            } else {
+                widths.max_content = Some(width);
+            }
+        }
+        width
+    }
+}
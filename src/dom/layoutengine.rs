@@ -1,10 +1,13 @@
 // /src/dom/layoutengine.rs
 
 use crate::dom::dom::{Dom, NodeContent, NodeId};
-use crate::dom::styleengine::{BorderStyle, BoxSizing, ComputedStyle, Display, Float, Size};
+use crate::dom::grid::{place_items, size_tracks, track_offsets, TrackContribution};
+use crate::dom::length::Length;
+use crate::dom::styleengine::{AlignItems, AlignSelf, BoxSizing, ComputedStyle, Display, FlexDirection, Float, Size, TextAlign};
 use serde::Serialize;
+use skia_safe::{Font, GlyphId, Point};
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct Rect {
     pub x: f32,
     pub y: f32,
@@ -12,6 +15,55 @@ pub struct Rect {
     pub height: f32,
 }
 
+impl Rect {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height
+    }
+
+    /// The overlap of two rectangles, empty (zero-sized) when they don't overlap.
+    pub fn intersection(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        Rect {
+            x,
+            y,
+            width: ((self.x + self.width).min(other.x + other.width) - x).max(0.0),
+            height: ((self.y + self.height).min(other.y + other.height) - y).max(0.0),
+        }
+    }
+
+    /// The smallest rectangle covering both.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Rect {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+}
+
+/// Used pixel sizes of the four sides of a margin, border or padding.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EdgeSizes {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl EdgeSizes {
+    pub fn horizontal(&self) -> f32 {
+        self.left + self.right
+    }
+
+    pub fn vertical(&self) -> f32 {
+        self.top + self.bottom
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize)]
 pub struct LayoutData {
     pub computed_x: f32,
@@ -25,6 +77,22 @@ pub struct TextInfo {
     pub lines: Vec<String>,
     pub line_heights: Vec<f32>,
     pub line_widths: Vec<f32>,
+    /// Distance from the top of a line to its baseline
+    pub ascent: f32,
+    /// The shaped glyphs, ready to paint
+    pub runs: Vec<GlyphRun>,
+}
+
+/// Consecutive glyphs shaped with one font. Fallback fonts, emoji and mixed scripts split a
+/// line into several runs.
+#[derive(Debug, Clone)]
+pub struct GlyphRun {
+    pub font: Font,
+    pub glyphs: Vec<GlyphId>,
+    /// Where each glyph's origin sits on its baseline, relative to the top-left of the text
+    pub positions: Vec<Point>,
+    /// The line the run is on
+    pub line: usize,
 }
 
 impl Dom {
@@ -35,10 +103,25 @@ impl Dom {
             width: viewport_width,
             height: viewport_height,
         };
+        self.prepare_layout_caches();
+        self.viewport = available_space;
         self.layout_node(self.root, available_space);
+        self.layout_positioned_boxes();
+        self.apply_scroll_offsets();
+        self.layout_dirty.borrow_mut().clear();
     }
 
+    /// Lays out a node and its subtree, reusing the previous layout of clean nodes given the same space.
     pub fn layout_node(&mut self, node_id: NodeId, available_space: Rect) -> Rect {
+        if let Some(rect) = self.cached_layout(node_id, available_space) {
+            return rect;
+        }
+        let rect = self.compute_node_layout(node_id, available_space);
+        self.store_layout(node_id, available_space, rect);
+        rect
+    }
+
+    fn compute_node_layout(&mut self, node_id: NodeId, available_space: Rect) -> Rect {
         let key: slotmap::DefaultKey = node_id.into();
 
         if let Some(style) = self.computed_styles.get(key) {
@@ -66,37 +149,31 @@ impl Dom {
         match node_content_type {
             Some(NodeContent::Element(_)) => {
                 let element_style = self.computed_styles.get(key).unwrap().clone();
+                // Laid out as a box of its own, not spread over the lines around it
+                self.inline_fragments.remove(key);
                 let child_ids = self.children.get(key).cloned().unwrap_or_default();
 
-                if element_style.float != Float::None {
+                if element_style.display == Display::Flex {
+                    self.layout_flex_node(node_id, available_space, &element_style, &child_ids)
+                } else if element_style.display == Display::Grid {
+                    self.layout_grid_node(node_id, available_space, &element_style, &child_ids)
+                } else if element_style.float != Float::None {
                     // Handle as block-level element, regardless of display value
                     self.layout_inline_node(node_id, available_space, &element_style, &child_ids)
                 } else {
                     match element_style.display {
                         Display::Block => self.layout_block_node(node_id, available_space, &element_style, &child_ids),
                         Display::Inline | Display::InlineBlock => self.layout_inline_node(node_id, available_space, &element_style, &child_ids),
-                        Display::Flex => unreachable!(),
+                        Display::Flex | Display::Grid => unreachable!(),
                         Display::None => unreachable!(),
                     }
                 }
             }
             Some(NodeContent::Text(text)) => {
-                let parent_style = if let Some(parent_id) = self.parent(node_id) {
-                    // Try to get the computed style of the parent
-                    self.get_computed_style(parent_id).unwrap_or_else(|| {
-                        // Fallback if parent exists but has no computed style
-                        ComputedStyle::default()
-                    })
-                } else {
-                    // Fallback if no parent exists
-                    ComputedStyle::default()
-                };
-
-                // Extract font properties with fallbacks
-                let font_family = parent_style.font_family;
-                let font_size = parent_style.font_size;
+                // Text nodes carry the style they inherit from their element
+                let text_style = self.computed_styles.get(key).cloned().unwrap_or_default();
 
-                let (measured_width, measured_height, text_info) = Self::measure_text(&text.content, font_family, font_size, available_space.width);
+                let (measured_width, measured_height, text_info) = Self::measure_text(&text.content, &text_style, available_space.width);
                 self.text_info.insert(key, text_info);
                 let text_rect = Rect {
                     x: available_space.x,
@@ -120,20 +197,125 @@ impl Dom {
         }
     }
 
+    /// Lays out a node with its border box forced to the given width and/or height, as flex
+    /// layout does once it has decided an item's size. The computed style is left untouched.
+    pub(crate) fn layout_sized_node(&mut self, node_id: NodeId, available_space: Rect, width: Option<f32>, height: Option<f32>) -> Rect {
+        let key: slotmap::DefaultKey = node_id.into();
+        let Some(original) = self.computed_styles.get(key).cloned() else {
+            return self.layout_node(node_id, available_space);
+        };
+
+        let padding = original.padding.resolve(available_space.width);
+        let border = original.border.widths();
+        // The sizes are border-box sizes; convert them back for content-box elements
+        let specified = |border_box: f32, edges: f32| {
+            let value = if original.box_sizing == BoxSizing::BorderBox { border_box } else { (border_box - edges).max(0.0) };
+            Size::Length(Length::Px(value))
+        };
+
+        let mut sized = original.clone();
+        if let Some(width) = width {
+            sized.width = Some(specified(width, padding.horizontal() + border.horizontal()));
+        }
+        if let Some(height) = height {
+            sized.height = Some(specified(height, padding.vertical() + border.vertical()));
+        }
+
+        self.computed_styles.insert(key, sized);
+        let rect = self.layout_node(node_id, available_space);
+        self.computed_styles.insert(key, original);
+        rect
+    }
+
+    /// The border-box width a node takes when nothing wraps, used to size flex items.
+    pub(crate) fn max_content_width(&mut self, node_id: NodeId) -> f32 {
+        let key: slotmap::DefaultKey = node_id.into();
+        let Some(style) = self.computed_styles.get(key).cloned() else {
+            return 0.0;
+        };
+        if style.display == Display::None {
+            return 0.0;
+        }
+
+        match self.content.get(key).cloned() {
+            Some(NodeContent::Text(text)) => self.cached_text_width(node_id, false, || Self::measure_text(&text.content, &style, f32::INFINITY).0),
+            Some(NodeContent::Element(_)) => {
+                // Percentages have nothing to resolve against here, so they count as zero
+                let edges = style.padding.resolve(0.0).horizontal() + style.border.widths().horizontal();
+                if let Some(Size::Length(width)) = style.width {
+                    if !width.has_percent() {
+                        return if style.box_sizing == BoxSizing::BorderBox { width.resolve(0.0) } else { width.resolve(0.0) + edges };
+                    }
+                }
+
+                let is_flex_row = style.display == Display::Flex && matches!(style.flex_direction, FlexDirection::Row | FlexDirection::RowReverse);
+                let child_ids: Vec<NodeId> = self.children.get(key).into_iter().flatten().copied().filter(|&child_id| !self.is_out_of_flow(child_id)).collect();
+                let mut widest_line = 0.0f32;
+                let mut current_line = 0.0f32;
+                for (index, child_id) in child_ids.into_iter().enumerate() {
+                    let child_style = self.computed_styles.get(child_id.into()).cloned().unwrap_or_default();
+                    let width = self.max_content_width(child_id) + child_style.margin.resolve(0.0).horizontal();
+                    if is_flex_row {
+                        let gap = if index > 0 { style.column_gap.resolve(0.0) } else { 0.0 };
+                        current_line += gap + width;
+                    } else if matches!(style.display, Display::Flex | Display::Grid) || child_style.display.is_block_level() {
+                        widest_line = widest_line.max(current_line).max(width);
+                        current_line = 0.0;
+                    } else {
+                        current_line += width;
+                    }
+                }
+                widest_line.max(current_line) + edges
+            }
+            None => 0.0,
+        }
+    }
+
+    /// The narrowest border-box width a node can take without overflowing, breaking text wherever it may wrap.
+    pub(crate) fn min_content_width(&mut self, node_id: NodeId) -> f32 {
+        let key: slotmap::DefaultKey = node_id.into();
+        let Some(style) = self.computed_styles.get(key).cloned() else {
+            return 0.0;
+        };
+        if style.display == Display::None {
+            return 0.0;
+        }
+
+        match self.content.get(key).cloned() {
+            Some(NodeContent::Text(text)) => self.cached_text_width(node_id, true, || Self::min_text_width(&text.content, &style)),
+            Some(NodeContent::Element(_)) => {
+                let edges = style.padding.resolve(0.0).horizontal() + style.border.widths().horizontal();
+                if let Some(Size::Length(width)) = style.width {
+                    if !width.has_percent() {
+                        return if style.box_sizing == BoxSizing::BorderBox { width.resolve(0.0) } else { width.resolve(0.0) + edges };
+                    }
+                }
+
+                let child_ids: Vec<NodeId> = self.children.get(key).into_iter().flatten().copied().filter(|&child_id| !self.is_out_of_flow(child_id)).collect();
+                let mut widest = 0.0f32;
+                for child_id in child_ids {
+                    let margins = self.computed_styles.get(child_id.into()).map_or(0.0, |child_style| child_style.margin.resolve(0.0).horizontal());
+                    widest = widest.max(self.min_content_width(child_id) + margins);
+                }
+                widest + edges
+            }
+            None => 0.0,
+        }
+    }
+
     fn layout_block_node(&mut self, node_id: NodeId, available_space: Rect, element_style: &ComputedStyle, child_ids: &[NodeId]) -> Rect {
         let key: slotmap::DefaultKey = node_id.into();
 
-        let border_left = element_style.border.left.map(|b| b.width).unwrap_or(0.0);
-        let border_right = element_style.border.right.map(|b| b.width).unwrap_or(0.0);
-        let border_top = element_style.border.top.map(|b| b.width).unwrap_or(0.0);
-        let border_bottom = element_style.border.bottom.map(|b| b.width).unwrap_or(0.0);
-        let horizontal_borders = border_left + border_right;
-        let vertical_borders = border_top + border_bottom;
-        let horizontal_padding = element_style.padding.horizontal_padding();
-        let vertical_padding = element_style.padding.vertical_padding();
+        let border = element_style.border.widths();
+        let padding = element_style.padding.resolve(available_space.width);
+        let margin = element_style.margin.resolve(available_space.width);
+        let horizontal_borders = border.horizontal();
+        let vertical_borders = border.vertical();
+        let horizontal_padding = padding.horizontal();
+        let vertical_padding = padding.vertical();
 
         // --- BOX SIZING: WIDTH CALCULATION ---
-        let border_box_width = resolve_size_or_default(&element_style.width, available_space.width, available_space.width - (element_style.margin.get_left() + element_style.margin.get_right()));
+        let border_box_width = resolve_size_or_default(&element_style.width, available_space.width, available_space.width - margin.horizontal());
 
         let content_width = if element_style.box_sizing == BoxSizing::BorderBox {
             border_box_width - horizontal_borders - horizontal_padding
@@ -143,8 +325,8 @@ impl Dom {
         };
         // --- END BOX SIZING ---
 
-        let content_x = available_space.x + element_style.margin.get_left() + border_left + element_style.padding.get_left();
-        let content_y = available_space.y + element_style.margin.get_top() + border_top + element_style.padding.get_top();
+        let content_x = available_space.x + margin.left + border.left + padding.left;
+        let content_y = available_space.y + margin.top + border.top + padding.top;
 
         let content_box = Rect {
             x: content_x,
@@ -152,7 +334,7 @@ impl Dom {
             width: content_width,
             height: f32::INFINITY,
         };
-        let (_used_width, used_height) = self.layout_inline_children(child_ids, content_box);
+        let (_used_width, used_height) = self.layout_inline_children(child_ids, content_box, element_style);
 
         let content_height = used_height;
 
@@ -163,9 +345,9 @@ impl Dom {
         }
         // --- END BOX SIZING ---
 
-        let final_height_with_margin = border_box_height + element_style.margin.get_top() + element_style.margin.get_bottom();
-        let border_box_x = available_space.x + element_style.margin.get_left();
-        let border_box_y = available_space.y + element_style.margin.get_top();
+        let final_height_with_margin = border_box_height + margin.vertical();
+        let border_box_x = available_space.x + margin.left;
+        let border_box_y = available_space.y + margin.top;
 
         let final_border_box_width = if element_style.box_sizing == BoxSizing::ContentBox && element_style.width.is_some() {
             content_width + horizontal_padding + horizontal_borders
@@ -191,17 +373,146 @@ impl Dom {
         }
     }
 
+    /// Lays out a node with `display: grid`: places its items on the grid, sizes the columns
+    /// and then the rows from the items in them, and lays each item out in its area.
+    fn layout_grid_node(&mut self, node_id: NodeId, available_space: Rect, element_style: &ComputedStyle, child_ids: &[NodeId]) -> Rect {
+        let key: slotmap::DefaultKey = node_id.into();
+
+        let border = element_style.border.widths();
+        let padding = element_style.padding.resolve(available_space.width);
+        let margin = element_style.margin.resolve(available_space.width);
+        let horizontal_edges = border.horizontal() + padding.horizontal();
+        let vertical_edges = border.vertical() + padding.vertical();
+        let is_border_box = element_style.box_sizing == BoxSizing::BorderBox;
+
+        let content_width = match resolve_size(&element_style.width, available_space.width) {
+            Some(width) if is_border_box => width - horizontal_edges,
+            Some(width) => width,
+            None => available_space.width - margin.horizontal() - horizontal_edges,
+        }
+        .max(0.0);
+        let content_height = resolve_size(&element_style.height, available_space.height).filter(|height| height.is_finite()).map(|height| if is_border_box { height - vertical_edges } else { height }.max(0.0));
+
+        let content_x = available_space.x + margin.left + border.left + padding.left;
+        let content_y = available_space.y + margin.top + border.top + padding.top;
+        let column_gap = element_style.column_gap.resolve(content_width);
+        let row_gap = element_style.row_gap.resolve(content_height.unwrap_or(0.0));
+
+        // --- Explicit grid, extended to cover the named areas ---
+        let areas = &element_style.grid_template_areas;
+        let mut columns = element_style.grid_template_columns.expand(Some(content_width), column_gap);
+        let mut rows = element_style.grid_template_rows.expand(content_height, row_gap);
+        columns.resize(columns.len().max(areas.columns), element_style.grid_auto_columns);
+        rows.resize(rows.len().max(areas.rows), element_style.grid_auto_rows);
+
+        // --- Placement ---
+        let mut items: Vec<(NodeId, ComputedStyle)> = Vec::new();
+        for &child_id in child_ids {
+            let child_style = self.computed_styles.get(child_id.into()).cloned().unwrap_or_default();
+            if child_style.display == Display::None {
+                self.layout_node(child_id, Rect { x: content_x, y: content_y, width: 0.0, height: 0.0 });
+                continue;
+            }
+            if child_style.position.is_out_of_flow() {
+                self.static_positions.insert(child_id.into(), Rect { x: content_x, y: content_y, width: content_width, height: f32::INFINITY });
+                continue;
+            }
+            items.push((child_id, child_style));
+        }
+        items.sort_by_key(|(_, style)| style.order);
+
+        let spans: Vec<_> = items.iter().map(|(_, style)| (style.grid_row.resolve(rows.len(), areas, true), style.grid_column.resolve(columns.len(), areas, false))).collect();
+        let positions = place_items(&spans, element_style.grid_auto_flow, rows.len(), columns.len());
+        // Items placed outside the explicit grid add implicit tracks
+        let column_count = positions.iter().zip(&spans).map(|(&(_, column), (_, column_span))| column + column_span.span).fold(columns.len(), usize::max);
+        let row_count = positions.iter().zip(&spans).map(|(&(row, _), (row_span, _))| row + row_span.span).fold(rows.len(), usize::max);
+        columns.resize(column_count, element_style.grid_auto_columns);
+        rows.resize(row_count, element_style.grid_auto_rows);
+
+        // --- Track sizing: columns first, then rows from the heights items take at those widths ---
+        let mut column_contributions = Vec::new();
+        for ((child_id, child_style), (&(_, column), (_, column_span))) in items.iter().zip(positions.iter().zip(&spans)) {
+            let margins = child_style.margin.resolve(content_width).horizontal();
+            column_contributions.push(TrackContribution {
+                start: column,
+                span: column_span.span,
+                min_content: self.min_content_width(*child_id) + margins,
+                max_content: self.max_content_width(*child_id) + margins,
+            });
+        }
+        let column_sizes = size_tracks(&columns, &column_contributions, Some(content_width), column_gap);
+        let column_offsets = track_offsets(&column_sizes, column_gap);
+        let area_width = |start: usize, span: usize| column_offsets[start + span - 1] + column_sizes[start + span - 1] - column_offsets[start];
+
+        let mut row_contributions = Vec::new();
+        for ((child_id, child_style), (&(row, column), (row_span, column_span))) in items.iter().zip(positions.iter().zip(&spans)) {
+            let width = area_width(column, column_span.span);
+            let item_space = Rect { x: content_x, y: content_y, width, height: f32::INFINITY };
+            self.layout_sized_node(*child_id, item_space, grid_item_width(child_style, width), None);
+            let height = self.layout.get((*child_id).into()).map_or(0.0, |layout| layout.actual_height) + child_style.margin.resolve(width).vertical();
+            row_contributions.push(TrackContribution { start: row, span: row_span.span, min_content: height, max_content: height });
+        }
+        let row_sizes = size_tracks(&rows, &row_contributions, content_height, row_gap);
+        let row_offsets = track_offsets(&row_sizes, row_gap);
+
+        // --- Final placement ---
+        for ((child_id, child_style), (&(row, column), (row_span, column_span))) in items.iter().zip(positions.iter().zip(&spans)) {
+            let width = area_width(column, column_span.span);
+            let last_row = row + row_span.span - 1;
+            let height = row_offsets[last_row] + row_sizes[last_row] - row_offsets[row];
+            let item_margin = child_style.margin.resolve(width);
+            let forced_width = grid_item_width(child_style, width);
+
+            let alignment = if child_style.align_self == AlignSelf::Auto { element_style.align_items } else { child_style.align_self.into() };
+            let auto_height = matches!(child_style.height, None | Some(Size::Auto));
+            let mut item_space = Rect { x: content_x + column_offsets[column], y: content_y + row_offsets[row], width, height };
+            if alignment == AlignItems::Stretch && auto_height {
+                self.layout_sized_node(*child_id, item_space, forced_width, Some((height - item_margin.vertical()).max(0.0)));
+                continue;
+            }
+
+            // The item keeps its own height and is aligned within its area
+            let item_height = self.layout.get((*child_id).into()).map_or(0.0, |layout| layout.actual_height) + item_margin.vertical();
+            item_space.y += match alignment {
+                AlignItems::FlexEnd => height - item_height,
+                AlignItems::Center => (height - item_height) / 2.0,
+                AlignItems::FlexStart | AlignItems::Stretch | AlignItems::Baseline => 0.0,
+            };
+            self.layout_sized_node(*child_id, item_space, forced_width, None);
+        }
+
+        let final_content_height = content_height.unwrap_or_else(|| row_sizes.iter().sum::<f32>() + row_gap * row_sizes.len().saturating_sub(1) as f32);
+        let final_width = content_width + horizontal_edges;
+        let final_height = final_content_height + vertical_edges;
+
+        self.layout.insert(
+            key,
+            LayoutData {
+                computed_x: available_space.x + margin.left,
+                computed_y: available_space.y + margin.top,
+                actual_width: final_width,
+                actual_height: final_height,
+            },
+        );
+
+        Rect {
+            x: available_space.x,
+            y: available_space.y,
+            width: final_width + margin.horizontal(),
+            height: final_height + margin.vertical(),
+        }
+    }
+
     fn layout_inline_node(&mut self, node_id: NodeId, available_space: Rect, element_style: &ComputedStyle, child_ids: &[NodeId]) -> Rect {
         let key: slotmap::DefaultKey = node_id.into();
 
-        let border_left = element_style.border.left.map(|b| b.width).unwrap_or(0.0);
-        let border_right = element_style.border.right.map(|b| b.width).unwrap_or(0.0);
-        let border_top = element_style.border.top.map(|b| b.width).unwrap_or(0.0);
-        let border_bottom = element_style.border.bottom.map(|b| b.width).unwrap_or(0.0);
+        let border = element_style.border.widths();
+        let padding = element_style.padding.resolve(available_space.width);
+        let margin = element_style.margin.resolve(available_space.width);
 
-        let content_x = available_space.x + element_style.margin.get_left() + border_left + element_style.padding.get_left();
-        let content_y = available_space.y + element_style.margin.get_top() + border_top + element_style.padding.get_top();
-        let content_width = available_space.width - (element_style.margin.get_left() + element_style.margin.get_right() + border_left + border_right + element_style.padding.get_left() + element_style.padding.get_right());
+        let content_x = available_space.x + margin.left + border.left + padding.left;
+        let content_y = available_space.y + margin.top + border.top + padding.top;
+        let content_width = available_space.width - (margin.horizontal() + border.horizontal() + padding.horizontal());
 
         let content_box = Rect {
             x: content_x,
@@ -209,12 +520,19 @@ impl Dom {
             width: content_width,
             height: f32::INFINITY,
         };
-        let (used_content_width, used_content_height) = self.layout_inline_children(child_ids, content_box);
+        let (mut used_content_width, mut used_content_height) = self.layout_inline_children(child_ids, content_box, element_style);
+        // Lines aligned other than to the left were placed across the available width; place them
+        // again within the width the box shrinks to
+        let shrinks = element_style.display == Display::Inline || resolve_size(&element_style.width, content_box.width).is_none();
+        if shrinks && used_content_width < content_box.width && !matches!(element_style.text_align, TextAlign::Left | TextAlign::Start) {
+            let shrunk_box = Rect { width: used_content_width, ..content_box };
+            (used_content_width, used_content_height) = self.layout_inline_children(child_ids, shrunk_box, element_style);
+        }
 
-        let horizontal_borders = border_left + border_right;
-        let vertical_borders = border_top + border_bottom;
-        let horizontal_padding = element_style.padding.get_left() + element_style.padding.get_right();
-        let vertical_padding = element_style.padding.get_top() + element_style.padding.get_bottom();
+        let horizontal_borders = border.horizontal();
+        let vertical_borders = border.vertical();
+        let horizontal_padding = padding.horizontal();
+        let vertical_padding = padding.vertical();
 
         let border_box_width = if element_style.display == Display::Inline {
             used_content_width + horizontal_padding + horizontal_borders
@@ -227,8 +545,8 @@ impl Dom {
             border_box_height = if element_style.box_sizing == BoxSizing::BorderBox { h } else { h + vertical_padding + vertical_borders };
         }
 
-        let border_box_x = available_space.x + element_style.margin.get_left();
-        let border_box_y = available_space.y + element_style.margin.get_top();
+        let border_box_x = available_space.x + margin.left;
+        let border_box_y = available_space.y + margin.top;
 
         self.layout.insert(
             key,
@@ -243,25 +561,40 @@ impl Dom {
         Rect {
             x: available_space.x,
             y: available_space.y,
-            width: border_box_width + element_style.margin.get_left() + element_style.margin.get_right(),
-            height: border_box_height + element_style.margin.get_top() + element_style.margin.get_bottom(),
+            width: border_box_width + margin.horizontal(),
+            height: border_box_height + margin.vertical(),
         }
     }
 
-    /// Lays out children, respecting floats, block, and inline elements.
-    fn layout_inline_children(&mut self, child_ids: &[NodeId], content_box: Rect) -> (f32, f32) {
+    /// Lays out children, respecting floats, block, and inline elements. Consecutive inline-level
+    /// children share lines, laid out together as one inline formatting context.
+    fn layout_inline_children(&mut self, child_ids: &[NodeId], content_box: Rect, container_style: &ComputedStyle) -> (f32, f32) {
         let mut left_floats: Vec<Rect> = Vec::new();
         let mut right_floats: Vec<Rect> = Vec::new();
-        let mut cursor_x = content_box.x;
         let mut cursor_y = content_box.y;
-        let mut max_height_in_line = 0.0f32;
         let mut max_width_so_far = 0.0f32;
+        let mut inline_run: Vec<NodeId> = Vec::new();
+        // Only the first line of the block is indented, which is gone once anything is in flow
+        let mut text_indent = container_style.text_indent.resolve(content_box.width);
 
         for &child_id in child_ids {
             let child_key = child_id.into();
 
             let (float_type, display_type) = { if let Some(style) = self.computed_styles.get(child_key) { (style.float, style.display) } else { (Float::None, Display::Inline) } };
 
+            // Out-of-flow children keep their place in the run, where their static position is
+            if self.is_out_of_flow(child_id) || (float_type == Float::None && !display_type.is_block_level()) {
+                inline_run.push(child_id);
+                continue;
+            }
+            if !inline_run.is_empty() {
+                let (run_width, run_bottom) = self.layout_inline_run(&inline_run, content_box, container_style, text_indent, cursor_y, (&left_floats, &right_floats));
+                max_width_so_far = max_width_so_far.max(run_width);
+                cursor_y = run_bottom;
+                text_indent = 0.0;
+                inline_run.clear();
+            }
+
             if float_type == Float::Left || float_type == Float::Right {
                 // --- NEW FLOAT LOGIC: Find a vertical spot where the float can fit ---
                 let ideal_space = Rect {
@@ -310,18 +643,11 @@ impl Dom {
                 } else {
                     right_floats.push(final_rect);
                 }
-            } else if display_type == Display::Block {
+            } else {
                 // --- HANDLE BLOCK ELEMENT ---
-                if cursor_x > content_box.x {
-                    cursor_y += max_height_in_line;
-                }
-
                 let floats_bottom = get_floats_bottom(&left_floats, &right_floats);
                 cursor_y = cursor_y.max(floats_bottom);
 
-                max_height_in_line = 0.0;
-                cursor_x = content_box.x;
-
                 let block_space = Rect {
                     x: content_box.x,
                     y: cursor_y,
@@ -331,43 +657,17 @@ impl Dom {
                 let child_rect = self.layout_node(child_id, block_space);
 
                 cursor_y += child_rect.height;
-            } else {
-                // --- HANDLE INLINE / INLINE-BLOCK ELEMENT ---
-                let (line_start, line_end) = get_line_bounds(cursor_y, &left_floats, &right_floats, &content_box);
-                cursor_x = cursor_x.max(line_start);
-
-                let remaining_width = line_end - cursor_x;
-                let child_space = Rect {
-                    x: cursor_x,
-                    y: cursor_y,
-                    width: remaining_width,
-                    height: f32::INFINITY,
-                };
-                let mut child_rect = self.layout_node(child_id, child_space);
-
-                if child_rect.width > remaining_width && cursor_x > line_start {
-                    cursor_y += max_height_in_line;
-                    max_height_in_line = 0.0;
-
-                    let (new_line_start, new_line_end) = get_line_bounds(cursor_y, &left_floats, &right_floats, &content_box);
-                    cursor_x = new_line_start;
-
-                    let new_child_space = Rect {
-                        x: cursor_x,
-                        y: cursor_y,
-                        width: new_line_end - new_line_start,
-                        height: f32::INFINITY,
-                    };
-                    child_rect = self.layout_node(child_id, new_child_space);
-                }
-
-                cursor_x += child_rect.width;
-                max_height_in_line = max_height_in_line.max(child_rect.height);
-                max_width_so_far = max_width_so_far.max(cursor_x - content_box.x);
+                text_indent = 0.0;
             }
         }
 
-        let in_flow_bottom = cursor_y + max_height_in_line;
+        if !inline_run.is_empty() {
+            let (run_width, run_bottom) = self.layout_inline_run(&inline_run, content_box, container_style, text_indent, cursor_y, (&left_floats, &right_floats));
+            max_width_so_far = max_width_so_far.max(run_width);
+            cursor_y = run_bottom;
+        }
+
+        let in_flow_bottom = cursor_y;
         let floats_bottom = get_floats_bottom(&left_floats, &right_floats);
 
         let total_content_height = in_flow_bottom.max(floats_bottom) - content_box.y;
@@ -376,7 +676,7 @@ impl Dom {
 }
 
 /// Helper to get the available horizontal space for a line, considering floats.
-fn get_line_bounds(y: f32, left_floats: &[Rect], right_floats: &[Rect], content_box: &Rect) -> (f32, f32) {
+pub(crate) fn get_line_bounds(y: f32, left_floats: &[Rect], right_floats: &[Rect], content_box: &Rect) -> (f32, f32) {
     let mut left_bound = content_box.x;
     let mut right_bound = content_box.x + content_box.width;
 
@@ -415,14 +715,21 @@ fn find_next_clear_y(current_y: f32, left_floats: &[Rect], right_floats: &[Rect]
     if next_y.is_infinite() { current_y } else { next_y + 0.01 } // Add a tiny epsilon to clear the edge
 }
 
-fn resolve_size(size: &Option<Size>, base: f32) -> Option<f32> {
+pub(crate) fn resolve_size(size: &Option<Size>, base: f32) -> Option<f32> {
     match size {
-        Some(Size::Points(p)) => Some(*p),
-        Some(Size::Percent(p)) => Some((p / 100.0) * base),
+        Some(Size::Length(length)) => Some(length.resolve(base)),
         Some(Size::Auto) | None => None,
     }
 }
 
+/// Grid items with an automatic width stretch across their area; others keep their own width.
+fn grid_item_width(style: &ComputedStyle, area_width: f32) -> Option<f32> {
+    match style.width {
+        None | Some(Size::Auto) => Some((area_width - style.margin.resolve(area_width).horizontal()).max(0.0)),
+        Some(Size::Length(_)) => None,
+    }
+}
+
 fn resolve_size_or_default(size: &Option<Size>, base: f32, default: f32) -> f32 {
     resolve_size(size, base).unwrap_or(default)
 }
@@ -0,0 +1,225 @@
+// /src/dom/length.rs
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// A CSS length as specified. Relative units are turned into pixels while computing styles,
+/// so computed styles only ever hold `Px`, `Percent`, or a `Calc` mixing the two.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Length {
+    Px(f32),
+    /// Relative to the element's font size, or the parent's for `font-size` itself
+    Em(f32),
+    /// Relative to the root element's font size
+    Rem(f32),
+    Vw(f32),
+    Vh(f32),
+    Vmin(f32),
+    Vmax(f32),
+    /// Resolved during layout against the containing block
+    Percent(f32),
+    Calc(CalcLength),
+}
+
+/// A `calc()` expression reduced to a sum of one term per unit.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct CalcLength {
+    pub px: f32,
+    pub em: f32,
+    pub rem: f32,
+    pub vw: f32,
+    pub vh: f32,
+    pub vmin: f32,
+    pub vmax: f32,
+    pub percent: f32,
+}
+
+/// What relative units are measured against.
+#[derive(Debug, Clone, Copy)]
+pub struct LengthContext {
+    pub font_size: f32,
+    pub root_font_size: f32,
+    pub viewport_width: f32,
+    pub viewport_height: f32,
+}
+
+impl Length {
+    pub const ZERO: Length = Length::Px(0.0);
+
+    pub fn to_calc(self) -> CalcLength {
+        let mut calc = CalcLength::default();
+        match self {
+            Length::Px(value) => calc.px = value,
+            Length::Em(value) => calc.em = value,
+            Length::Rem(value) => calc.rem = value,
+            Length::Vw(value) => calc.vw = value,
+            Length::Vh(value) => calc.vh = value,
+            Length::Vmin(value) => calc.vmin = value,
+            Length::Vmax(value) => calc.vmax = value,
+            Length::Percent(value) => calc.percent = value,
+            Length::Calc(value) => calc = value,
+        }
+        calc
+    }
+
+    pub fn has_percent(self) -> bool {
+        self.to_calc().percent != 0.0
+    }
+
+    /// Converts every unit except percentages into pixels.
+    pub fn absolutize(self, context: &LengthContext) -> Length {
+        let calc = self.to_calc();
+        let viewport_min = context.viewport_width.min(context.viewport_height);
+        let viewport_max = context.viewport_width.max(context.viewport_height);
+        let px = calc.px + calc.em * context.font_size + calc.rem * context.root_font_size + (calc.vw * context.viewport_width + calc.vh * context.viewport_height + calc.vmin * viewport_min + calc.vmax * viewport_max) / 100.0;
+        CalcLength { px, percent: calc.percent, ..CalcLength::default() }.simplify()
+    }
+
+    /// The used value in pixels, with percentages taken of `percent_base`.
+    /// Only meaningful on computed lengths; relative units that were never absolutized count as zero.
+    pub fn resolve(self, percent_base: f32) -> f32 {
+        let calc = self.to_calc();
+        // Skip the percentage term when there is none, so an infinite base doesn't turn pixels into NaN
+        if calc.percent == 0.0 { calc.px } else { calc.px + calc.percent * percent_base / 100.0 }
+    }
+}
+
+impl From<f32> for Length {
+    fn from(px: f32) -> Self {
+        Length::Px(px)
+    }
+}
+
+impl Default for Length {
+    fn default() -> Self {
+        Length::ZERO
+    }
+}
+
+impl CalcLength {
+    pub fn add(self, other: CalcLength) -> CalcLength {
+        CalcLength {
+            px: self.px + other.px,
+            em: self.em + other.em,
+            rem: self.rem + other.rem,
+            vw: self.vw + other.vw,
+            vh: self.vh + other.vh,
+            vmin: self.vmin + other.vmin,
+            vmax: self.vmax + other.vmax,
+            percent: self.percent + other.percent,
+        }
+    }
+
+    pub fn scale(self, factor: f32) -> CalcLength {
+        CalcLength {
+            px: self.px * factor,
+            em: self.em * factor,
+            rem: self.rem * factor,
+            vw: self.vw * factor,
+            vh: self.vh * factor,
+            vmin: self.vmin * factor,
+            vmax: self.vmax * factor,
+            percent: self.percent * factor,
+        }
+    }
+
+    /// Collapses to a plain unit when at most one term is non-zero.
+    pub fn simplify(self) -> Length {
+        let terms = self.terms();
+        match terms.iter().filter(|(value, _)| *value != 0.0).collect::<Vec<_>>().as_slice() {
+            [] => Length::ZERO,
+            [(value, unit)] => match *unit {
+                "px" => Length::Px(*value),
+                "em" => Length::Em(*value),
+                "rem" => Length::Rem(*value),
+                "vw" => Length::Vw(*value),
+                "vh" => Length::Vh(*value),
+                "vmin" => Length::Vmin(*value),
+                "vmax" => Length::Vmax(*value),
+                _ => Length::Percent(*value),
+            },
+            _ => Length::Calc(self),
+        }
+    }
+
+    fn terms(&self) -> [(f32, &'static str); 8] {
+        [(self.px, "px"), (self.em, "em"), (self.rem, "rem"), (self.vw, "vw"), (self.vh, "vh"), (self.vmin, "vmin"), (self.vmax, "vmax"), (self.percent, "%")]
+    }
+}
+
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Length::Px(value) => write!(f, "{}px", value),
+            Length::Em(value) => write!(f, "{}em", value),
+            Length::Rem(value) => write!(f, "{}rem", value),
+            Length::Vw(value) => write!(f, "{}vw", value),
+            Length::Vh(value) => write!(f, "{}vh", value),
+            Length::Vmin(value) => write!(f, "{}vmin", value),
+            Length::Vmax(value) => write!(f, "{}vmax", value),
+            Length::Percent(value) => write!(f, "{}%", value),
+            Length::Calc(calc) => {
+                write!(f, "calc(")?;
+                let mut first = true;
+                for (value, unit) in calc.terms().into_iter().filter(|(value, _)| *value != 0.0) {
+                    if first {
+                        write!(f, "{}{}", value, unit)?;
+                    } else {
+                        write!(f, " {} {}{}", if value < 0.0 { '-' } else { '+' }, value.abs(), unit)?;
+                    }
+                    first = false;
+                }
+                if first {
+                    write!(f, "0px")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::dom::Dom;
+    use crate::dom::styleengine::Size;
+
+    const CONTEXT: LengthContext = LengthContext { font_size: 10.0, root_font_size: 16.0, viewport_width: 800.0, viewport_height: 600.0 };
+
+    #[test]
+    fn absolutizes_relative_units_against_the_context() {
+        assert_eq!(Length::Em(1.5).absolutize(&CONTEXT), Length::Px(15.0));
+        assert_eq!(Length::Rem(2.0).absolutize(&CONTEXT), Length::Px(32.0));
+        assert_eq!(Length::Vw(10.0).absolutize(&CONTEXT), Length::Px(80.0));
+        assert_eq!(Length::Vh(10.0).absolutize(&CONTEXT), Length::Px(60.0));
+        assert_eq!(Length::Vmin(10.0).absolutize(&CONTEXT), Length::Px(60.0));
+        assert_eq!(Length::Vmax(10.0).absolutize(&CONTEXT), Length::Px(80.0));
+        assert_eq!(Length::Percent(50.0).absolutize(&CONTEXT), Length::Percent(50.0));
+    }
+
+    #[test]
+    fn keeps_percentages_apart_until_layout() {
+        let calc = CalcLength { em: 1.0, vw: 1.0, percent: 50.0, ..CalcLength::default() };
+        let computed = Length::Calc(calc).absolutize(&CONTEXT);
+        assert_eq!(computed, Length::Calc(CalcLength { px: 18.0, percent: 50.0, ..CalcLength::default() }));
+        assert_eq!(computed.resolve(200.0), 118.0);
+        assert_eq!(Length::Px(5.0).resolve(f32::INFINITY), 5.0);
+        // Terms that cancel out leave a plain unit
+        assert_eq!(calc.add(CalcLength { px: 3.0, ..calc.scale(-1.0) }).simplify(), Length::Px(3.0));
+        assert_eq!(Length::Calc(CalcLength { px: 10.0, percent: -5.0, ..CalcLength::default() }).to_string(), "calc(10px - 5%)");
+    }
+
+    #[test]
+    fn em_follows_the_font_size_and_rem_the_root() {
+        let html = r#"<div style="font-size: 10px"><p style="font-size: 2em; width: 2em; height: 1.5rem"></p></div>"#;
+        let mut dom = Dom::from_html(html).unwrap();
+        dom.compute_styles(800.0, 600.0);
+        let p = dom.descendants(dom.root).nth(1).unwrap();
+        let style = &dom.computed_styles[p.into()];
+        assert_eq!(style.font_size, 20.0);
+        assert_eq!(style.width, Some(Size::Length(Length::Px(40.0))));
+        let root_font_size = dom.computed_styles[dom.root.into()].font_size;
+        assert_eq!(style.height, Some(Size::Length(Length::Px(1.5 * root_font_size))));
+    }
+}
@@ -13,6 +13,22 @@ pub mod textlayout;
 pub mod domdefaults;
 pub mod domapi;
 pub mod fragment;
+pub mod cssparser;
+pub mod selector;
+pub mod length;
+pub mod flex;
+pub mod grid;
+pub mod positioning;
+pub mod scroll;
+pub mod invalidation;
+pub mod traversal;
+pub mod observer;
+pub mod signal;
+pub mod component;
+pub mod vdom;
+pub mod csswriter;
+pub mod html;
+pub mod inline;
 
 // Re-export commonly used types
 pub use dom::Dom;
\ No newline at end of file
@@ -0,0 +1,205 @@
+// /src/dom/observer.rs
+
+use std::fmt;
+
+use crate::dom::dom::{Dom, NodeId};
+use crate::dom::styleengine::Style;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(usize);
+
+/// Which changes an observer is told about. Without `subtree` only changes to the observed node
+/// itself (or its own child list) are reported.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ObserveOptions {
+    pub child_list: bool,
+    pub attributes: bool,
+    pub style: bool,
+    pub text: bool,
+    pub subtree: bool,
+    /// Keep the value an attribute, inline style or text had before each change.
+    pub old_values: bool,
+}
+
+impl ObserveOptions {
+    /// Every kind of change anywhere in the subtree, with old values.
+    pub fn all() -> Self {
+        Self {
+            child_list: true,
+            attributes: true,
+            style: true,
+            text: true,
+            subtree: true,
+            old_values: true,
+        }
+    }
+
+    fn accepts(&self, change: &MutationKind) -> bool {
+        match change {
+            MutationKind::ChildList { .. } => self.child_list,
+            MutationKind::Attribute { .. } => self.attributes,
+            MutationKind::Style { .. } => self.style,
+            MutationKind::Text { .. } => self.text,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum MutationKind {
+    /// Children were inserted into or taken out of the target, between `previous_sibling` and `next_sibling`.
+    ChildList {
+        added: Vec<NodeId>,
+        removed: Vec<NodeId>,
+        previous_sibling: Option<NodeId>,
+        next_sibling: Option<NodeId>,
+    },
+    /// `class` and `id` changes are reported as attributes too.
+    Attribute { name: String, old_value: Option<String> },
+    /// The target's inline style changed.
+    Style { old_style: Option<Box<Style>> },
+    /// A text node's content changed.
+    Text { old_value: Option<String> },
+}
+
+#[derive(Debug, Clone)]
+pub struct MutationRecord {
+    pub target: NodeId,
+    pub kind: MutationKind,
+}
+
+impl MutationRecord {
+    fn without_old_value(mut self) -> Self {
+        match &mut self.kind {
+            MutationKind::Attribute { old_value, .. } | MutationKind::Text { old_value } => *old_value = None,
+            MutationKind::Style { old_style } => *old_style = None,
+            MutationKind::ChildList { .. } => {}
+        }
+        self
+    }
+}
+
+type MutationCallback = Box<dyn FnMut(&[MutationRecord], &Dom)>;
+
+struct MutationObserver {
+    id: ObserverId,
+    target: NodeId,
+    options: ObserveOptions,
+    callback: MutationCallback,
+    pending: Vec<MutationRecord>,
+}
+
+/// The observers registered on a `Dom` and the records waiting for them.
+#[derive(Default)]
+pub struct MutationObservers {
+    observers: Vec<MutationObserver>,
+    next_id: usize,
+}
+
+impl fmt::Debug for MutationObservers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MutationObservers").field("observers", &self.observers.len()).finish()
+    }
+}
+
+impl Dom {
+    /// Registers a callback for changes to `target_id`. Records are collected as mutations happen
+    /// and handed over in batches by `deliver_mutations`, which `compute_styles` calls first thing
+    /// each frame. The callback can read the tree as it is after the whole batch. Removing the
+    /// target with `remove_node` disconnects the observer once its last records are delivered.
+    pub fn observe(&mut self, target_id: NodeId, options: ObserveOptions, callback: impl FnMut(&[MutationRecord], &Dom) + 'static) -> ObserverId {
+        let id = ObserverId(self.mutation_observers.next_id);
+        self.mutation_observers.next_id += 1;
+        self.mutation_observers.observers.push(MutationObserver {
+            id,
+            target: target_id,
+            options,
+            callback: Box::new(callback),
+            pending: Vec::new(),
+        });
+        id
+    }
+
+    /// Unregisters an observer, dropping any records it hasn't been given yet.
+    pub fn disconnect(&mut self, observer_id: ObserverId) {
+        self.mutation_observers.observers.retain(|observer| observer.id != observer_id);
+    }
+
+    /// Takes the records waiting for an observer without calling its callback.
+    pub fn take_records(&mut self, observer_id: ObserverId) -> Vec<MutationRecord> {
+        self.mutation_observers
+            .observers
+            .iter_mut()
+            .find(|observer| observer.id == observer_id)
+            .map(|observer| std::mem::take(&mut observer.pending))
+            .unwrap_or_default()
+    }
+
+    /// Calls every observer that has records waiting with all of them at once.
+    pub fn deliver_mutations(&mut self) {
+        let mut observers = std::mem::take(&mut self.mutation_observers.observers);
+        for observer in &mut observers {
+            if observer.pending.is_empty() {
+                continue;
+            }
+            let records = std::mem::take(&mut observer.pending);
+            (observer.callback)(&records, self);
+        }
+        self.mutation_observers.observers = observers;
+        self.drop_freed_observers();
+    }
+
+    /// Disconnects the observers whose target no longer exists and that have nothing left to deliver.
+    pub(crate) fn drop_freed_observers(&mut self) {
+        let nodes = &self.nodes;
+        self.mutation_observers.observers.retain(|observer| !observer.pending.is_empty() || nodes.contains_key(observer.target.into()));
+    }
+
+    /// Whether anything listens for mutations, so callers can skip capturing old values when not.
+    pub(crate) fn is_observed(&self) -> bool {
+        !self.mutation_observers.observers.is_empty()
+    }
+
+    /// Hands a change to every observer watching the target. Removals are queued before the node is
+    /// unlinked, while it is still inside the subtrees being observed.
+    pub(crate) fn queue_mutation(&mut self, target_id: NodeId, kind: MutationKind) {
+        if !self.is_observed() {
+            return;
+        }
+        let record = MutationRecord { target: target_id, kind };
+        let mut observers = std::mem::take(&mut self.mutation_observers.observers);
+        for observer in &mut observers {
+            let in_scope = observer.target == target_id || (observer.options.subtree && self.is_inclusive_ancestor(observer.target, target_id));
+            if !in_scope || !observer.options.accepts(&record.kind) {
+                continue;
+            }
+            observer.pending.push(if observer.options.old_values { record.clone() } else { record.clone().without_old_value() });
+        }
+        self.mutation_observers.observers = observers;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::dom::element::Element;
+
+    #[test]
+    fn removing_the_target_disconnects_after_delivery() {
+        let mut dom = Dom::new();
+        let root = dom.root;
+        let target = dom.append_new_element(root, Element::new()).unwrap();
+        let delivered = Rc::new(Cell::new(0));
+        let count = delivered.clone();
+        dom.observe(target, ObserveOptions::all(), move |records, _| count.set(count.get() + records.len()));
+
+        dom.set_attribute(target, "title", "a");
+        dom.remove_node(target);
+        assert!(dom.is_observed());
+        dom.deliver_mutations();
+        assert_eq!(delivered.get(), 1);
+        assert!(!dom.is_observed());
+    }
+}
@@ -0,0 +1,306 @@
+// /src/dom/positioning.rs
+
+use super::dom::{Dom, NodeId};
+use super::layoutengine::{EdgeSizes, Rect, resolve_size};
+use super::styleengine::{BoxSizing, ComputedStyle, Display, Position, Size};
+use super::traversal::DescendantCursor;
+
+impl Dom {
+    pub(crate) fn is_out_of_flow(&self, node_id: NodeId) -> bool {
+        self.computed_styles.get(node_id.into()).is_some_and(|style| style.position.is_out_of_flow())
+    }
+
+    /// Runs after normal flow: shifts relatively positioned boxes, and lays out absolutely
+    /// positioned and fixed boxes against their containing blocks. Nodes are visited in tree
+    /// order, so every ancestor is in its final place before its descendants use it.
+    pub(crate) fn layout_positioned_boxes(&mut self) {
+        for node_id in self.post_layout_boxes() {
+            let Some(style) = self.computed_styles.get(node_id.into()).cloned() else {
+                continue;
+            };
+            if style.position == Position::Static {
+                continue;
+            }
+
+            match style.position {
+                Position::Relative => {
+                    let (dx, dy) = self.relative_offset(node_id, &style);
+                    self.translate_subtree(node_id, dx, dy);
+                }
+                Position::Absolute | Position::Fixed => self.layout_absolute_box(node_id, &style),
+                // Sticky boxes depend on scroll offsets, so they are placed along with them
+                Position::Static | Position::Sticky => {}
+            }
+        }
+    }
+
+    /// Flags a node and its ancestors as holding a box that the passes after normal flow move.
+    pub(crate) fn mark_post_layout_subtree(&mut self, node_id: NodeId) {
+        let mut current = Some(node_id);
+        while let Some(id) = current {
+            // The ancestors of a flagged node are always flagged already
+            if self.post_layout_subtrees.insert(id.into(), true) == Some(true) {
+                break;
+            }
+            current = self.parent(id);
+        }
+    }
+
+    /// The displayed positioned boxes and scroll containers, in tree order. Only flagged subtrees
+    /// are walked, and none inside a box that isn't displayed.
+    pub(crate) fn post_layout_boxes(&self) -> Vec<NodeId> {
+        let mut boxes = Vec::new();
+        let mut cursor = DescendantCursor::new(self.root);
+        let mut node_id = self.root;
+        loop {
+            let key = node_id.into();
+            let style = self.computed_styles.get(key);
+            let displayed = !style.is_some_and(|style| style.display == Display::None);
+            if displayed && let Some(style) = style && (style.position != Position::Static || style.is_scroll_container()) {
+                boxes.push(node_id);
+            }
+            let next = if displayed && self.post_layout_subtrees.get(key) == Some(&true) { cursor.next(self) } else { cursor.skip_children(self) };
+            match next {
+                Some(next_id) => node_id = next_id,
+                None => return boxes,
+            }
+        }
+    }
+
+    /// The rectangle offsets and sizes of positioned boxes are resolved against: the padding box
+    /// of the nearest positioned ancestor, or the viewport for fixed boxes and when there is none.
+    pub fn containing_block(&self, node_id: NodeId) -> Rect {
+        let position = self.computed_styles.get(node_id.into()).map_or(Position::Static, |style| style.position);
+        match position {
+            Position::Fixed => self.viewport,
+            Position::Absolute => std::iter::successors(self.parent(node_id), |&ancestor_id| self.parent(ancestor_id))
+                .find(|&ancestor_id| self.computed_styles.get(ancestor_id.into()).is_some_and(|style| style.position != Position::Static))
+                .and_then(|ancestor_id| self.padding_box(ancestor_id))
+                .unwrap_or(self.viewport),
+            // Boxes in flow are contained by their parent's content box
+            _ => self.parent(node_id).and_then(|parent_id| self.content_box(parent_id)).unwrap_or(self.viewport),
+        }
+    }
+
+    /// The visible area that sticky boxes stay inside: the padding box of the nearest scroll container, or the viewport.
+    pub fn scrollport(&self, node_id: NodeId) -> Rect {
+        self.clipping_container(node_id).and_then(|container_id| self.padding_box(container_id)).unwrap_or(self.viewport)
+    }
+
+    pub fn padding_box(&self, node_id: NodeId) -> Option<Rect> {
+        let layout = self.layout.get(node_id.into())?;
+        let (border, _) = self.box_edges(node_id);
+        Some(Rect {
+            x: layout.computed_x + border.left,
+            y: layout.computed_y + border.top,
+            width: (layout.actual_width - border.horizontal()).max(0.0),
+            height: (layout.actual_height - border.vertical()).max(0.0),
+        })
+    }
+
+    pub fn content_box(&self, node_id: NodeId) -> Option<Rect> {
+        let padding_box = self.padding_box(node_id)?;
+        let (_, padding) = self.box_edges(node_id);
+        Some(Rect {
+            x: padding_box.x + padding.left,
+            y: padding_box.y + padding.top,
+            width: (padding_box.width - padding.horizontal()).max(0.0),
+            height: (padding_box.height - padding.vertical()).max(0.0),
+        })
+    }
+
+    /// Border and padding widths, with percentages taken of the parent's width.
+    fn box_edges(&self, node_id: NodeId) -> (EdgeSizes, EdgeSizes) {
+        let Some(style) = self.computed_styles.get(node_id.into()) else {
+            return (EdgeSizes::default(), EdgeSizes::default());
+        };
+        let base = self.parent(node_id).and_then(|parent_id| self.layout.get(parent_id.into())).map_or(self.viewport.width, |layout| layout.actual_width);
+        (style.border.widths(), style.padding.resolve(base))
+    }
+
+    /// `left` wins over `right` and `top` over `bottom` when both are set.
+    fn relative_offset(&self, node_id: NodeId, style: &ComputedStyle) -> (f32, f32) {
+        let containing_block = self.containing_block(node_id);
+        let offsets = resolve_offsets(style, containing_block);
+        let dx = offsets.left.or(offsets.right.map(|right| -right)).unwrap_or(0.0);
+        let dy = offsets.top.or(offsets.bottom.map(|bottom| -bottom)).unwrap_or(0.0);
+        (dx, dy)
+    }
+
+    /// How far a sticky box has to move to stay inset from its scrollport by its offsets,
+    /// without leaving its parent's content box.
+    pub(crate) fn sticky_offset(&self, node_id: NodeId, style: &ComputedStyle) -> (f32, f32) {
+        let Some(layout) = self.layout.get(node_id.into()).copied() else {
+            return (0.0, 0.0);
+        };
+        let scrollport = self.scrollport(node_id);
+        let offsets = resolve_offsets(style, scrollport);
+        // A scroll container's content box moves and grows with what it scrolls
+        let limits = match self.parent(node_id) {
+            Some(parent_id) if self.is_scroll_container(parent_id) => self.scrolled_content_box(parent_id),
+            _ => None,
+        }
+        .unwrap_or_else(|| self.containing_block(node_id));
+        let margin = style.margin.resolve(limits.width);
+
+        let along_axis = |start: f32, size: f32, inset_start: Option<f32>, inset_end: Option<f32>, view_start: f32, view_size: f32, limit_start: f32, limit_size: f32| {
+            let mut delta = 0.0f32;
+            if let Some(inset) = inset_end {
+                delta = delta.min(view_start + view_size - inset - (start + size));
+            }
+            // The start inset wins when both can't be satisfied
+            if let Some(inset) = inset_start {
+                delta = delta.max(view_start + inset - start);
+            }
+            delta.min(limit_start + limit_size - (start + size)).max(limit_start - start)
+        };
+
+        let dx = along_axis(
+            layout.computed_x - margin.left,
+            layout.actual_width + margin.horizontal(),
+            offsets.left,
+            offsets.right,
+            scrollport.x,
+            scrollport.width,
+            limits.x,
+            limits.width,
+        );
+        let dy = along_axis(
+            layout.computed_y - margin.top,
+            layout.actual_height + margin.vertical(),
+            offsets.top,
+            offsets.bottom,
+            scrollport.y,
+            scrollport.height,
+            limits.y,
+            limits.height,
+        );
+        (dx, dy)
+    }
+
+    /// Sizes an absolutely positioned or fixed box from its offsets, or shrink-to-fit when they
+    /// leave its width open, and lays out its subtree where the offsets put it.
+    fn layout_absolute_box(&mut self, node_id: NodeId, style: &ComputedStyle) {
+        let containing_block = self.containing_block(node_id);
+        let static_position = self.static_positions.get(node_id.into()).copied().unwrap_or(containing_block);
+        let offsets = resolve_offsets(style, containing_block);
+        let margin = style.margin.resolve(containing_block.width);
+        let padding = style.padding.resolve(containing_block.width);
+        let border = style.border.widths();
+        let to_border_box = |size: f32, edges: f32| if style.box_sizing == BoxSizing::BorderBox { size } else { size + edges };
+
+        let width = match (resolve_size(&style.width, containing_block.width), offsets.left, offsets.right) {
+            (Some(width), _, _) => to_border_box(width, padding.horizontal() + border.horizontal()),
+            (None, Some(left), Some(right)) => (containing_block.width - left - right - margin.horizontal()).max(0.0),
+            (None, left, right) => {
+                let available = containing_block.width - left.unwrap_or(0.0) - right.unwrap_or(0.0) - margin.horizontal();
+                let preferred = self.max_content_width(node_id);
+                preferred.min(available).max(self.min_content_width(node_id))
+            }
+        };
+        let height = match (resolve_size(&style.height, containing_block.height).filter(|height| height.is_finite()), offsets.top, offsets.bottom) {
+            (Some(height), _, _) => Some(to_border_box(height, padding.vertical() + border.vertical())),
+            (None, Some(top), Some(bottom)) => Some((containing_block.height - top - bottom - margin.vertical()).max(0.0)),
+            _ => None,
+        };
+
+        let x = match (offsets.left, offsets.right) {
+            (Some(left), _) => containing_block.x + left,
+            (None, Some(right)) => containing_block.x + containing_block.width - right - width - margin.horizontal(),
+            (None, None) => static_position.x,
+        };
+        let mut space = Rect {
+            x,
+            y: static_position.y,
+            width: width + margin.horizontal(),
+            height: f32::INFINITY,
+        };
+
+        match (offsets.top, offsets.bottom) {
+            (Some(top), _) => space.y = containing_block.y + top,
+            (None, Some(bottom)) => {
+                // Anchored to the bottom edge, so the height has to be known first
+                let border_box_height = match height {
+                    Some(height) => height,
+                    None => {
+                        self.layout_sized_node(node_id, space, Some(width), None);
+                        self.layout.get(node_id.into()).map_or(0.0, |layout| layout.actual_height)
+                    }
+                };
+                space.y = containing_block.y + containing_block.height - bottom - border_box_height - margin.vertical();
+            }
+            (None, None) => {}
+        }
+
+        self.layout_sized_node(node_id, space, Some(width), height);
+    }
+
+    /// Moves a laid-out box and everything inside it.
+    pub(crate) fn translate_subtree(&mut self, node_id: NodeId, dx: f32, dy: f32) {
+        if dx == 0.0 && dy == 0.0 {
+            return;
+        }
+        self.translate_node(node_id, dx, dy);
+        let mut cursor = DescendantCursor::new(node_id);
+        while let Some(id) = cursor.next(self) {
+            self.translate_node(id, dx, dy);
+        }
+    }
+
+    /// Moves one laid-out box, remembering the move so the next layout pass can undo it.
+    pub(crate) fn translate_node(&mut self, node_id: NodeId, dx: f32, dy: f32) {
+        let key = node_id.into();
+        if let Some(entry) = self.post_layout_offsets.entry(key) {
+            let offset = entry.or_default();
+            *offset = (offset.0 + dx, offset.1 + dy);
+        }
+        if let Some(layout) = self.layout.get_mut(key) {
+            layout.computed_x += dx;
+            layout.computed_y += dy;
+        }
+        if let Some(static_position) = self.static_positions.get_mut(key) {
+            static_position.x += dx;
+            static_position.y += dy;
+        }
+    }
+
+    /// Children in the order they are painted: boxes in normal flow first, then positioned ones.
+    pub fn children_in_paint_order(&self, node_id: NodeId) -> Vec<NodeId> {
+        let mut children = self.children.get(node_id.into()).cloned().unwrap_or_default();
+        children.sort_by_key(|&child_id| self.computed_styles.get(child_id.into()).is_some_and(|style| style.position != Position::Static));
+        children
+    }
+
+    /// Like `collect_nodes_depth_first`, but with each node's children in paint order.
+    pub fn collect_nodes_paint_order(&self, root_id: NodeId) -> Vec<NodeId> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![root_id];
+        while let Some(node_id) = stack.pop() {
+            nodes.push(node_id);
+            stack.extend(self.children_in_paint_order(node_id).into_iter().rev());
+        }
+        nodes
+    }
+}
+
+struct ResolvedOffsets {
+    top: Option<f32>,
+    right: Option<f32>,
+    bottom: Option<f32>,
+    left: Option<f32>,
+}
+
+/// Offsets in pixels, with `None` for `auto`. Horizontal percentages refer to the width of `reference`, vertical ones to its height.
+fn resolve_offsets(style: &ComputedStyle, reference: Rect) -> ResolvedOffsets {
+    let side = |value: Option<Size>, base: f32| match value {
+        Some(Size::Length(length)) => Some(length.resolve(base)),
+        Some(Size::Auto) | None => None,
+    };
+    let offsets = style.position_offsets;
+    ResolvedOffsets {
+        top: side(offsets.top, reference.height),
+        right: side(offsets.right, reference.width),
+        bottom: side(offsets.bottom, reference.height),
+        left: side(offsets.left, reference.width),
+    }
+}
@@ -0,0 +1,383 @@
+// /src/dom/scroll.rs
+
+use serde::Serialize;
+
+use super::dom::{Dom, NodeId};
+use super::layoutengine::Rect;
+use super::styleengine::{ComputedStyle, Display, Overflow, Position};
+
+/// Scrollbars are drawn over the content, inside the padding box, so they take no space from layout.
+pub const SCROLLBAR_THICKNESS: f32 = 8.0;
+const MIN_THUMB_LENGTH: f32 = 20.0;
+
+/// How far a scroll container is scrolled, and how much there was to scroll at the last layout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ScrollState {
+    pub offset_x: f32,
+    pub offset_y: f32,
+    /// Size of the content measured from the padding box's top-left corner, never less than the padding box.
+    pub scroll_width: f32,
+    pub scroll_height: f32,
+    /// Size of the padding box the content scrolls inside.
+    pub client_width: f32,
+    pub client_height: f32,
+}
+
+impl ScrollState {
+    pub fn max_offset_x(&self) -> f32 {
+        (self.scroll_width - self.client_width).max(0.0)
+    }
+
+    pub fn max_offset_y(&self) -> f32 {
+        (self.scroll_height - self.client_height).max(0.0)
+    }
+
+    fn clamp(&mut self) {
+        self.offset_x = self.offset_x.clamp(0.0, self.max_offset_x());
+        self.offset_y = self.offset_y.clamp(0.0, self.max_offset_y());
+    }
+}
+
+/// A scrollbar's track, and the thumb showing which part of the content is visible.
+#[derive(Debug, Clone, Copy)]
+pub struct Scrollbar {
+    pub track: Rect,
+    pub thumb: Rect,
+}
+
+/// How the scroll containers around a node treat its children, by how each child is positioned:
+/// children in flow like the node's own content, absolutely positioned ones like the content of
+/// the nearest positioned ancestor, and fixed ones not at all. Passed down a walk of the tree, it
+/// saves each node from looking up its clipping containers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Clipping<T> {
+    in_flow: T,
+    absolute: T,
+}
+
+impl<T: Copy + Default> Clipping<T> {
+    /// How a child with `style` is treated.
+    pub fn of(&self, style: &ComputedStyle) -> T {
+        match style.position {
+            Position::Fixed => T::default(),
+            Position::Absolute => self.absolute,
+            _ => self.in_flow,
+        }
+    }
+
+    /// How the children of a node with `style` are treated, given how the node is itself and
+    /// what it does to its content if it is a scroll container.
+    pub fn inside(&self, own: T, style: &ComputedStyle, content: impl FnOnce(T) -> T) -> Self {
+        let content = if style.is_scroll_container() { content(own) } else { own };
+        let absolute = if style.position != Position::Static { content } else { self.absolute };
+        Self { in_flow: content, absolute }
+    }
+}
+
+/// Whether a box moves when a scroll container scrolls, and whether that container is the nearest one clipping it.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum Scrolled {
+    #[default]
+    No,
+    Directly,
+    InsideNestedContainer,
+}
+
+impl Dom {
+    pub fn is_scroll_container(&self, node_id: NodeId) -> bool {
+        self.computed_styles.get(node_id.into()).is_some_and(ComputedStyle::is_scroll_container)
+    }
+
+    pub fn scroll_offset(&self, node_id: NodeId) -> (f32, f32) {
+        self.scroll_states.get(node_id.into()).map_or((0.0, 0.0), |state| (state.offset_x, state.offset_y))
+    }
+
+    /// Scrolls a scroll container, clamped to the content size found by the last layout.
+    /// The content moves on the next layout.
+    pub fn scroll_to(&mut self, node_id: NodeId, x: f32, y: f32) {
+        if !self.is_scroll_container(node_id) {
+            return;
+        }
+        if let Some(entry) = self.scroll_states.entry(node_id.into()) {
+            let state = entry.or_default();
+            state.offset_x = x;
+            state.offset_y = y;
+            state.clamp();
+        }
+    }
+
+    /// Returns whether the offset changed, i.e. false when already scrolled to the end in that direction.
+    pub fn scroll_by(&mut self, node_id: NodeId, dx: f32, dy: f32) -> bool {
+        let (x, y) = self.scroll_offset(node_id);
+        self.scroll_to(node_id, x + dx, y + dy);
+        self.scroll_offset(node_id) != (x, y)
+    }
+
+    /// Scrolls the innermost scroll container under the point that can still move by the given
+    /// delta, passing it outwards when a container is already at its end. Only `scroll` and
+    /// `auto` axes respond. Returns the container that scrolled.
+    pub fn scroll_at_position(&mut self, x: f32, y: f32, dx: f32, dy: f32) -> Option<NodeId> {
+        let target = self.find_node_at_position(x, y)?;
+        let mut candidate = if self.is_scroll_container(target) { Some(target) } else { self.clipping_container(target) };
+        while let Some(container_id) = candidate {
+            let (overflow_x, overflow_y) = self.computed_styles.get(container_id.into()).map(|style| (style.overflow_x, style.overflow_y))?;
+            let dx = if overflow_x.is_user_scrollable() { dx } else { 0.0 };
+            let dy = if overflow_y.is_user_scrollable() { dy } else { 0.0 };
+            if self.scroll_by(container_id, dx, dy) {
+                return Some(container_id);
+            }
+            candidate = self.clipping_container(container_id);
+        }
+        None
+    }
+
+    /// The nearest ancestor scroll container that clips a box. Fixed boxes escape every scroll
+    /// container, and absolutely positioned boxes escape those outside their containing block.
+    pub fn clipping_container(&self, node_id: NodeId) -> Option<NodeId> {
+        let mut escapes_static_ancestors = false;
+        let mut current = node_id;
+        loop {
+            match self.computed_styles.get(current.into())?.position {
+                Position::Fixed => return None,
+                Position::Absolute => escapes_static_ancestors = true,
+                _ => {}
+            }
+            let parent_id = self.parent(current)?;
+            let parent_style = self.computed_styles.get(parent_id.into())?;
+            let is_positioned = parent_style.position != Position::Static;
+            if parent_style.is_scroll_container() && (is_positioned || !escapes_static_ancestors) {
+                return Some(parent_id);
+            }
+            if is_positioned {
+                escapes_static_ancestors = false;
+            }
+            current = parent_id;
+        }
+    }
+
+    /// The area a box stays visible in after every scroll container around it has clipped it,
+    /// or `None` when nothing clips it.
+    pub fn clip_rect(&self, node_id: NodeId) -> Option<Rect> {
+        let mut clip: Option<Rect> = None;
+        let mut container = self.clipping_container(node_id);
+        while let Some(container_id) = container {
+            if let Some(padding_box) = self.padding_box(container_id) {
+                clip = Some(clip.map_or(padding_box, |clip| clip.intersection(&padding_box)));
+            }
+            container = self.clipping_container(container_id);
+        }
+        clip
+    }
+
+    /// A scroll container's content box stretched over everything it scrolls, at its current offset.
+    pub(crate) fn scrolled_content_box(&self, container_id: NodeId) -> Option<Rect> {
+        let (padding_box, content_box, state) = (self.padding_box(container_id)?, self.content_box(container_id)?, self.scroll_states.get(container_id.into())?);
+        Some(Rect {
+            x: content_box.x - state.offset_x,
+            y: content_box.y - state.offset_y,
+            width: (state.scroll_width - (padding_box.width - content_box.width)).max(0.0),
+            height: (state.scroll_height - (padding_box.height - content_box.height)).max(0.0),
+        })
+    }
+
+    /// A box's clip, given how its parent clips its children, and how the box clips its own.
+    /// Walking down from the root with `Clipping::default()` gives each box its `clip_rect`.
+    pub fn clip_inside(&self, node_id: NodeId, clipping: &Clipping<Option<Rect>>) -> (Option<Rect>, Clipping<Option<Rect>>) {
+        let Some(style) = self.computed_styles.get(node_id.into()) else {
+            return (None, Clipping::default());
+        };
+        let clip = clipping.of(style);
+        let inner = clipping.inside(clip, style, |clip| match (clip, self.padding_box(node_id)) {
+            (Some(clip), Some(padding_box)) => Some(clip.intersection(&padding_box)),
+            (clip, padding_box) => padding_box.or(clip),
+        });
+        (clip, inner)
+    }
+
+    /// The horizontal and vertical scrollbars of a scroll container, where shown: always for
+    /// `overflow: scroll`, and only when there is something to scroll for `auto`.
+    pub fn scrollbars(&self, node_id: NodeId) -> (Option<Scrollbar>, Option<Scrollbar>) {
+        let key = node_id.into();
+        let (Some(style), Some(state), Some(padding_box)) = (self.computed_styles.get(key), self.scroll_states.get(key), self.padding_box(node_id)) else {
+            return (None, None);
+        };
+        let show_x = style.overflow_x == Overflow::Scroll || (style.overflow_x == Overflow::Auto && state.max_offset_x() > 0.0);
+        let show_y = style.overflow_y == Overflow::Scroll || (style.overflow_y == Overflow::Auto && state.max_offset_y() > 0.0);
+        // Where both are shown, they leave the corner between them empty
+        let corner = |shown: bool| if shown { SCROLLBAR_THICKNESS } else { 0.0 };
+
+        let horizontal = show_x.then(|| {
+            let track = Rect {
+                x: padding_box.x,
+                y: padding_box.y + padding_box.height - SCROLLBAR_THICKNESS,
+                width: (padding_box.width - corner(show_y)).max(0.0),
+                height: SCROLLBAR_THICKNESS,
+            };
+            let (position, length) = thumb_span(track.width, state.client_width, state.scroll_width, state.offset_x, state.max_offset_x());
+            Scrollbar {
+                track,
+                thumb: Rect { x: track.x + position, width: length, ..track },
+            }
+        });
+        let vertical = show_y.then(|| {
+            let track = Rect {
+                x: padding_box.x + padding_box.width - SCROLLBAR_THICKNESS,
+                y: padding_box.y,
+                width: SCROLLBAR_THICKNESS,
+                height: (padding_box.height - corner(show_x)).max(0.0),
+            };
+            let (position, length) = thumb_span(track.height, state.client_height, state.scroll_height, state.offset_y, state.max_offset_y());
+            Scrollbar {
+                track,
+                thumb: Rect { y: track.y + position, height: length, ..track },
+            }
+        });
+        (horizontal, vertical)
+    }
+
+    /// Runs after positioned layout: measures each scroll container's content, moves what it
+    /// clips by its scroll offset, and places sticky boxes against their scrolled scrollports.
+    /// Nodes are visited in tree order, so outer containers have scrolled before inner ones measure.
+    pub(crate) fn apply_scroll_offsets(&mut self) {
+        for node_id in self.post_layout_boxes() {
+            let Some(style) = self.computed_styles.get(node_id.into()) else {
+                continue;
+            };
+            if !(style.position == Position::Sticky || style.is_scroll_container()) {
+                continue;
+            }
+
+            if style.position == Position::Sticky {
+                let style = style.clone();
+                let (dx, dy) = self.sticky_offset(node_id, &style);
+                self.translate_subtree(node_id, dx, dy);
+            }
+            if self.is_scroll_container(node_id) {
+                self.scroll_content(node_id);
+            }
+        }
+    }
+
+    fn scroll_content(&mut self, container_id: NodeId) {
+        let (Some(padding_box), Some(content_box), Some(style)) = (self.padding_box(container_id), self.content_box(container_id), self.computed_styles.get(container_id.into())) else {
+            return;
+        };
+        let clipping = Clipping::default().inside(Scrolled::No, style, |_| Scrolled::Directly);
+
+        // The content's far edges, plus the container's end padding
+        let padding_right = padding_box.x + padding_box.width - (content_box.x + content_box.width);
+        let padding_bottom = padding_box.y + padding_box.height - (content_box.y + content_box.height);
+        let mut far_edges = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        self.measure_scrolled(container_id, &clipping, &mut far_edges);
+        let right = (padding_box.x + padding_box.width).max(far_edges.0 + padding_right);
+        let bottom = (padding_box.y + padding_box.height).max(far_edges.1 + padding_bottom);
+
+        let Some(entry) = self.scroll_states.entry(container_id.into()) else {
+            return;
+        };
+        let state = entry.or_default();
+        state.client_width = padding_box.width;
+        state.client_height = padding_box.height;
+        state.scroll_width = right - padding_box.x;
+        state.scroll_height = bottom - padding_box.y;
+        state.clamp();
+        let (dx, dy) = (-state.offset_x, -state.offset_y);
+
+        if dx != 0.0 || dy != 0.0 {
+            self.translate_scrolled(container_id, &clipping, dx, dy);
+        }
+    }
+
+    /// Stretches `far_edges` over the displayed boxes below `node_id` that a scroll container clips
+    /// directly. Content inside nested scroll containers only counts as far as those containers reach.
+    fn measure_scrolled(&self, node_id: NodeId, clipping: &Clipping<Scrolled>, far_edges: &mut (f32, f32)) {
+        for child_id in self.child_nodes(node_id) {
+            let Some(style) = self.computed_styles.get(child_id.into()) else {
+                continue;
+            };
+            // Nothing inside a box that doesn't scroll does either
+            let scrolled = clipping.of(style);
+            if scrolled == Scrolled::No || style.display == Display::None {
+                continue;
+            }
+            if scrolled == Scrolled::Directly && let Some(layout) = self.layout.get(child_id.into()) {
+                far_edges.0 = far_edges.0.max(layout.computed_x + layout.actual_width);
+                far_edges.1 = far_edges.1.max(layout.computed_y + layout.actual_height);
+            }
+            self.measure_scrolled(child_id, &clipping.inside(scrolled, style, |_| Scrolled::InsideNestedContainer), far_edges);
+        }
+    }
+
+    /// Moves the displayed boxes below `node_id` that scroll with a scroll container.
+    fn translate_scrolled(&mut self, node_id: NodeId, clipping: &Clipping<Scrolled>, dx: f32, dy: f32) {
+        let mut index = 0;
+        while let Some(child_id) = self.child_at(node_id, index) {
+            index += 1;
+            let Some(style) = self.computed_styles.get(child_id.into()) else {
+                continue;
+            };
+            let scrolled = clipping.of(style);
+            if scrolled == Scrolled::No || style.display == Display::None {
+                continue;
+            }
+            let inner = clipping.inside(scrolled, style, |_| Scrolled::InsideNestedContainer);
+            self.translate_node(child_id, dx, dy);
+            self.translate_scrolled(child_id, &inner, dx, dy);
+        }
+    }
+}
+
+/// Where a scrollbar thumb starts along its track and how long it is.
+fn thumb_span(track_length: f32, client_length: f32, scroll_length: f32, offset: f32, max_offset: f32) -> (f32, f32) {
+    let length = (track_length * client_length / scroll_length.max(1.0)).clamp(MIN_THUMB_LENGTH.min(track_length), track_length);
+    let position = if max_offset > 0.0 { (track_length - length) * offset / max_offset } else { 0.0 };
+    (position, length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTML: &str = r#"<style>
+        #s { overflow: scroll; height: 50px } #t { height: 200px }
+        #p { position: relative; overflow: hidden; height: 30px } #a { position: absolute } #f { position: fixed; height: 10px }
+    </style><div id="s"><div id="t"></div><div id="p"><div id="a"></div><div id="q"><div id="b" style="position: absolute"></div></div></div><div><div id="f"></div></div></div>"#;
+
+    fn laid_out() -> Dom {
+        let mut dom = Dom::from_html(HTML).unwrap();
+        dom.compute_styles(800.0, 600.0);
+        dom.compute_layout(800.0, 600.0);
+        dom
+    }
+
+    fn check_clips(dom: &Dom, node_id: NodeId, clipping: &Clipping<Option<Rect>>) {
+        let (clip, inner) = dom.clip_inside(node_id, clipping);
+        assert_eq!(clip, dom.clip_rect(node_id));
+        for child_id in dom.child_nodes(node_id) {
+            check_clips(dom, child_id, &inner);
+        }
+    }
+
+    #[test]
+    fn clips_passed_down_match_each_nodes_own() {
+        let dom = laid_out();
+        check_clips(&dom, dom.root, &Clipping::default());
+        let [p, b, f] = ["p", "b", "f"].map(|id| dom.get_element_by_id(id).unwrap());
+        assert_eq!(dom.clip_rect(b), dom.padding_box(p).map(|padding_box| padding_box.intersection(&dom.clip_rect(p).unwrap())));
+        assert_eq!(dom.clip_rect(f), None);
+    }
+
+    #[test]
+    fn scrolling_moves_what_the_container_clips() {
+        let mut dom = laid_out();
+        let [s, t, p, b, f] = ["s", "t", "p", "b", "f"].map(|id| dom.get_element_by_id(id).unwrap());
+        let y = |dom: &Dom, id: NodeId| dom.layout[id.into()].computed_y;
+        let before = [t, p, b, f].map(|id| y(&dom, id));
+        assert_eq!(dom.scroll_states[s.into()].scroll_height, 230.0);
+
+        assert!(dom.scroll_by(s, 0.0, 40.0));
+        dom.compute_styles(800.0, 600.0);
+        dom.compute_layout(800.0, 600.0);
+        assert_eq!([t, p, b, f].map(|id| y(&dom, id)), [before[0] - 40.0, before[1] - 40.0, before[2] - 40.0, before[3]]);
+    }
+}
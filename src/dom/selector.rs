@@ -0,0 +1,382 @@
+// /src/dom/selector.rs
+
+use crate::dom::{
+    cssparser::CssParseError,
+    dom::NodeId,
+    element::Element,
+    traversal::NodeKind,
+    Dom,
+};
+
+/// (id selectors, class and attribute selectors, type selectors), compared lexicographically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Specificity(pub u32, pub u32, pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combinator {
+    /// `a b`
+    Descendant,
+    /// `a > b`
+    Child,
+    /// `a + b`
+    NextSibling,
+    /// `a ~ b`
+    SubsequentSibling,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeOperator {
+    /// `[name]`
+    Exists,
+    /// `[name=value]`
+    Equals(String),
+    /// `[name~=value]`, one of a whitespace separated list
+    Includes(String),
+    /// `[name|=value]`, exactly `value` or starting with `value-`
+    DashMatch(String),
+    /// `[name^=value]`
+    Prefix(String),
+    /// `[name$=value]`
+    Suffix(String),
+    /// `[name*=value]`
+    Substring(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeSelector {
+    pub name: String,
+    pub operator: AttributeOperator,
+}
+
+/// A sequence of simple selectors with no combinator between them, e.g. `div.tab#active[data-dirty]`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CompoundSelector {
+    pub tag: Option<String>,
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub attributes: Vec<AttributeSelector>,
+}
+
+/// A complex selector stored right to left: `subject` is the element being matched and
+/// `ancestors` walks outwards, so `a > b c` is `c` followed by `(Descendant, b)` and `(Child, a)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    pub subject: CompoundSelector,
+    pub ancestors: Vec<(Combinator, CompoundSelector)>,
+}
+
+impl CompoundSelector {
+    pub fn matches(&self, element: &Element) -> bool {
+        if let Some(tag) = &self.tag {
+            if !element.name.as_deref().is_some_and(|name| name.eq_ignore_ascii_case(tag)) {
+                return false;
+            }
+        }
+        if let Some(id) = &self.id {
+            if element.id.as_deref() != Some(id.as_str()) {
+                return false;
+            }
+        }
+        if !self.classes.iter().all(|class| element.class_list.contains(class)) {
+            return false;
+        }
+        self.attributes.iter().all(|attribute| attribute.matches(element))
+    }
+
+    fn specificity(&self) -> Specificity {
+        Specificity(self.id.is_some() as u32, (self.classes.len() + self.attributes.len()) as u32, self.tag.is_some() as u32)
+    }
+}
+
+impl AttributeSelector {
+    pub fn matches(&self, element: &Element) -> bool {
+        let Some(value) = element.get_attribute(&self.name) else {
+            return false;
+        };
+        match &self.operator {
+            AttributeOperator::Exists => true,
+            AttributeOperator::Equals(expected) => value == expected.as_str(),
+            AttributeOperator::Includes(expected) => value.split_whitespace().any(|word| word == expected),
+            AttributeOperator::DashMatch(expected) => value == expected.as_str() || value.strip_prefix(expected.as_str()).is_some_and(|rest| rest.starts_with('-')),
+            AttributeOperator::Prefix(expected) => !expected.is_empty() && value.starts_with(expected.as_str()),
+            AttributeOperator::Suffix(expected) => !expected.is_empty() && value.ends_with(expected.as_str()),
+            AttributeOperator::Substring(expected) => !expected.is_empty() && value.contains(expected.as_str()),
+        }
+    }
+}
+
+impl Selector {
+    pub fn specificity(&self) -> Specificity {
+        let mut total = self.subject.specificity();
+        for (_, compound) in &self.ancestors {
+            let Specificity(a, b, c) = compound.specificity();
+            total = Specificity(total.0 + a, total.1 + b, total.2 + c);
+        }
+        total
+    }
+
+    /// Whether matching depends on earlier siblings, so changing one element can restyle the ones after it.
+    pub fn has_sibling_combinator(&self) -> bool {
+        self.ancestors.iter().any(|(combinator, _)| matches!(combinator, Combinator::NextSibling | Combinator::SubsequentSibling))
+    }
+
+    pub fn matches(&self, dom: &Dom, node_id: NodeId) -> bool {
+        match dom.get_element(node_id) {
+            Some(element) if self.subject.matches(element) => self.matches_from(dom, node_id, 0),
+            _ => false,
+        }
+    }
+
+    /// Checks `ancestors[index..]` given that `node_id` matched the compound before `index`.
+    fn matches_from(&self, dom: &Dom, node_id: NodeId, index: usize) -> bool {
+        let Some((combinator, compound)) = self.ancestors.get(index) else {
+            return true;
+        };
+        let matches_here = |candidate: NodeId| dom.get_element(candidate).is_some_and(|element| compound.matches(element)) && self.matches_from(dom, candidate, index + 1);
+
+        match combinator {
+            Combinator::Child => dom.parent(node_id).is_some_and(matches_here),
+            Combinator::Descendant => {
+                let mut current = dom.parent(node_id);
+                while let Some(ancestor) = current {
+                    if matches_here(ancestor) {
+                        return true;
+                    }
+                    current = dom.parent(ancestor);
+                }
+                false
+            }
+            Combinator::NextSibling => previous_element_siblings(dom, node_id).next().is_some_and(matches_here),
+            Combinator::SubsequentSibling => previous_element_siblings(dom, node_id).any(matches_here),
+        }
+    }
+}
+
+/// Element siblings before `node_id`, nearest first.
+fn previous_element_siblings(dom: &Dom, node_id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+    dom.previous_siblings(node_id).filter(move |&sibling| dom.node_kind(sibling) == Some(NodeKind::Element))
+}
+
+/// Parses a comma separated selector list such as `.editor > .line, #gutter span`.
+/// Error positions are relative to the start of `input`.
+pub fn parse_selector_list(input: &str) -> Result<Vec<Selector>, CssParseError> {
+    let mut parser = SelectorParser { input, pos: 0 };
+    let mut selectors = Vec::new();
+
+    loop {
+        parser.skip_whitespace();
+        selectors.push(parser.parse_selector()?);
+        match parser.peek() {
+            Some(',') => {
+                parser.bump();
+            }
+            None => break,
+            Some(c) => return Err(parser.error(format!("unexpected `{}` in selector", c))),
+        }
+    }
+
+    Ok(selectors)
+}
+
+struct SelectorParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl SelectorParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn error(&self, message: impl Into<String>) -> CssParseError {
+        let consumed = &self.input[..self.pos];
+        let line = consumed.matches('\n').count() + 1;
+        let column = consumed.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+        CssParseError { message: message.into(), line, column }
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+        self.pos > start
+    }
+
+    fn parse_selector(&mut self) -> Result<Selector, CssParseError> {
+        let mut compounds = vec![self.parse_compound()?];
+        let mut combinators = Vec::new();
+
+        loop {
+            let had_whitespace = self.skip_whitespace();
+            let combinator = match self.peek() {
+                None | Some(',') => break,
+                Some('>') => Combinator::Child,
+                Some('+') => Combinator::NextSibling,
+                Some('~') => Combinator::SubsequentSibling,
+                Some(_) if had_whitespace => Combinator::Descendant,
+                Some(c) => return Err(self.error(format!("unexpected `{}` in selector", c))),
+            };
+            if combinator != Combinator::Descendant {
+                self.bump();
+                self.skip_whitespace();
+            }
+            combinators.push(combinator);
+            compounds.push(self.parse_compound()?);
+        }
+
+        let subject = compounds.pop().unwrap_or_default();
+        let ancestors = combinators.into_iter().rev().zip(compounds.into_iter().rev()).collect();
+        Ok(Selector { subject, ancestors })
+    }
+
+    fn parse_compound(&mut self) -> Result<CompoundSelector, CssParseError> {
+        let start = self.pos;
+        let mut compound = CompoundSelector::default();
+
+        if self.peek() == Some('*') {
+            self.bump();
+        } else if self.peek().is_some_and(is_name_char) {
+            compound.tag = Some(self.parse_name()?);
+        }
+
+        loop {
+            match self.peek() {
+                Some('#') => {
+                    self.bump();
+                    let id = self.parse_name()?;
+                    if compound.id.is_some() {
+                        return Err(self.error("a compound selector can only have one id"));
+                    }
+                    compound.id = Some(id);
+                }
+                Some('.') => {
+                    self.bump();
+                    compound.classes.push(self.parse_name()?);
+                }
+                Some('[') => {
+                    self.bump();
+                    compound.attributes.push(self.parse_attribute()?);
+                }
+                Some(':') => return Err(self.error("pseudo-classes and pseudo-elements are not supported")),
+                _ => break,
+            }
+        }
+
+        if self.pos == start {
+            return Err(match self.peek() {
+                Some(c) => self.error(format!("expected a selector, found `{}`", c)),
+                None => self.error("expected a selector"),
+            });
+        }
+        Ok(compound)
+    }
+
+    fn parse_name(&mut self) -> Result<String, CssParseError> {
+        let start = self.pos;
+        while self.peek().is_some_and(is_name_char) {
+            self.bump();
+        }
+        if self.pos == start {
+            return Err(self.error("expected a name"));
+        }
+        Ok(self.input[start..self.pos].to_string())
+    }
+
+    fn parse_attribute(&mut self) -> Result<AttributeSelector, CssParseError> {
+        self.skip_whitespace();
+        let name = self.parse_name()?;
+        self.skip_whitespace();
+
+        let operator: fn(String) -> AttributeOperator = match self.bump() {
+            Some(']') => return Ok(AttributeSelector { name, operator: AttributeOperator::Exists }),
+            Some('=') => AttributeOperator::Equals,
+            Some(c @ ('~' | '|' | '^' | '$' | '*')) if self.peek() == Some('=') => {
+                self.bump();
+                match c {
+                    '~' => AttributeOperator::Includes,
+                    '|' => AttributeOperator::DashMatch,
+                    '^' => AttributeOperator::Prefix,
+                    '$' => AttributeOperator::Suffix,
+                    _ => AttributeOperator::Substring,
+                }
+            }
+            _ => return Err(self.error("expected `]` or an attribute operator")),
+        };
+
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.bump();
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c != quote) {
+                    self.bump();
+                }
+                if self.peek().is_none() {
+                    return Err(self.error("unterminated string in attribute selector"));
+                }
+                let value = self.input[start..self.pos].to_string();
+                self.bump();
+                value
+            }
+            _ => self.parse_name()?,
+        };
+
+        self.skip_whitespace();
+        if self.bump() != Some(']') {
+            return Err(self.error("expected `]`"));
+        }
+        Ok(AttributeSelector { name, operator: operator(value) })
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_compounds_and_combinators_right_to_left() {
+        let selectors = parse_selector_list("ul > li.item#first[data-x^='a b'] + span, *").unwrap();
+        assert_eq!(selectors.len(), 2);
+        let selector = &selectors[0];
+        assert_eq!(selector.subject.tag.as_deref(), Some("span"));
+        let (combinator, li) = &selector.ancestors[0];
+        assert_eq!(*combinator, Combinator::NextSibling);
+        assert_eq!((li.tag.as_deref(), li.id.as_deref(), li.classes.as_slice()), (Some("li"), Some("first"), &["item".to_string()][..]));
+        assert_eq!(li.attributes, [AttributeSelector { name: "data-x".to_string(), operator: AttributeOperator::Prefix("a b".to_string()) }]);
+        assert_eq!(selector.ancestors[1].0, Combinator::Child);
+        assert_eq!(selector.specificity(), Specificity(1, 2, 3));
+        assert_eq!(selectors[1].subject, CompoundSelector::default());
+    }
+
+    #[test]
+    fn rejects_invalid_selectors_with_positions() {
+        let error = parse_selector_list("div,\n  a:hover").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 4));
+        assert!(parse_selector_list("#a#b").is_err());
+        assert!(parse_selector_list("a >").is_err());
+        assert!(parse_selector_list("[x=\"y]").is_err());
+    }
+
+    #[test]
+    fn matches_against_the_tree() {
+        let dom = Dom::from_html(r#"<ul><li class="item">a</li><li id="b">b</li></ul><p lang="en-GB"></p>"#).unwrap();
+        let matching = |selector: &str| dom.query_selector_all(dom.root, selector).unwrap().len();
+        assert_eq!(matching("ul li"), 2);
+        assert_eq!(matching(".item + li#b"), 1);
+        assert_eq!(matching(".item ~ li"), 1);
+        assert_eq!(matching("li + .item"), 0);
+        assert_eq!(matching("ul ~ p[lang|=en]"), 1);
+        assert_eq!(matching("body li"), 0);
+    }
+}
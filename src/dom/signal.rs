@@ -0,0 +1,158 @@
+// /src/dom/signal.rs
+
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+use crate::dom::component::ComponentId;
+
+type Subscribers = RefCell<BTreeSet<ComponentId>>;
+
+/// Bookkeeping shared by every signal of a `ComponentRuntime`: which component is rendering,
+/// so reads can subscribe it, which signals each component is subscribed to, and which
+/// components have to render again.
+#[derive(Debug, Default)]
+pub(crate) struct ReactiveState {
+    rendering: RefCell<Vec<ComponentId>>,
+    dependencies: RefCell<HashMap<ComponentId, Vec<Weak<Subscribers>>>>,
+    pub(crate) stale: RefCell<BTreeSet<ComponentId>>,
+}
+
+impl ReactiveState {
+    /// Runs `render` with reads subscribing `component_id`. Only the signals read this time stay
+    /// subscribed, so a component that stops reading a signal isn't rendered again by it.
+    pub(crate) fn track<R>(&self, component_id: ComponentId, render: impl FnOnce() -> R) -> R {
+        self.unsubscribe(component_id);
+        self.rendering.borrow_mut().push(component_id);
+        let result = render();
+        self.rendering.borrow_mut().pop();
+        result
+    }
+
+    /// Removes a component from the subscribers of every signal it read.
+    pub(crate) fn unsubscribe(&self, component_id: ComponentId) {
+        let dependencies = self.dependencies.borrow_mut().remove(&component_id).unwrap_or_default();
+        for subscribers in dependencies.iter().filter_map(Weak::upgrade) {
+            subscribers.borrow_mut().remove(&component_id);
+        }
+    }
+}
+
+/// A value components can read while rendering. Setting it makes every component that read it
+/// render again on the next `ComponentRuntime::update`, and nothing else. Clones share the value.
+pub struct Signal<T> {
+    value: Rc<RefCell<T>>,
+    subscribers: Rc<Subscribers>,
+    state: Rc<ReactiveState>,
+}
+
+impl<T> Clone for Signal<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            subscribers: self.subscribers.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+/// Signals are equal when they share a value, so props holding one compare equal across renders.
+impl<T> PartialEq for Signal<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.value, &other.value)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Signal<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Signal").field(&self.value.borrow()).finish()
+    }
+}
+
+impl<T> Signal<T> {
+    pub(crate) fn new(state: Rc<ReactiveState>, value: T) -> Self {
+        Self {
+            value: Rc::new(RefCell::new(value)),
+            subscribers: Rc::new(RefCell::new(BTreeSet::new())),
+            state,
+        }
+    }
+
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        self.with(T::clone)
+    }
+
+    /// Reads the value without cloning it.
+    pub fn with<R>(&self, read: impl FnOnce(&T) -> R) -> R {
+        // Each signal is listed once among the component's dependencies, however often it is read
+        if let Some(&component_id) = self.state.rendering.borrow().last()
+            && self.subscribers.borrow_mut().insert(component_id)
+        {
+            self.state.dependencies.borrow_mut().entry(component_id).or_default().push(Rc::downgrade(&self.subscribers));
+        }
+        read(&self.value.borrow())
+    }
+
+    pub fn set(&self, value: T) {
+        self.update(|current| *current = value);
+    }
+
+    pub fn update(&self, update: impl FnOnce(&mut T)) {
+        update(&mut self.value.borrow_mut());
+        self.state.stale.borrow_mut().extend(self.subscribers.borrow().iter().copied());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use crate::dom::component::{Component, ComponentRuntime, Scope, View};
+    use crate::dom::dom::Dom;
+
+    use super::*;
+
+    #[derive(PartialEq)]
+    struct Counter {
+        show: Signal<bool>,
+        count: Signal<i32>,
+        renders: Rc<Cell<usize>>,
+    }
+
+    impl Component for Counter {
+        type State = ();
+
+        fn init(&self, _scope: &Scope) {}
+
+        fn render(&self, _state: &()) -> View {
+            self.renders.set(self.renders.get() + 1);
+            if self.show.get() { View::text(self.count.get().to_string()) } else { View::text("hidden") }
+        }
+    }
+
+    #[test]
+    fn signals_no_longer_read_dont_render_again() {
+        let mut dom = Dom::new();
+        let mut runtime = ComponentRuntime::new();
+        let counter = Counter { show: runtime.signal(true), count: runtime.signal(0), renders: Rc::default() };
+        let (show, count, renders) = (counter.show.clone(), counter.count.clone(), counter.renders.clone());
+        let root = dom.root;
+        let component_id = runtime.mount(&mut dom, root, counter).unwrap();
+        assert_eq!(renders.get(), 1);
+
+        show.set(false);
+        assert!(runtime.update(&mut dom));
+        assert_eq!(renders.get(), 2);
+        assert!(count.subscribers.borrow().is_empty());
+        count.set(1);
+        assert!(!runtime.update(&mut dom));
+        assert_eq!(renders.get(), 2);
+
+        runtime.unmount(&mut dom, component_id);
+        assert!(show.subscribers.borrow().is_empty());
+    }
+}
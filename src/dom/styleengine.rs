@@ -1,12 +1,18 @@
 // /src/dom/styleengine.rs
 
-use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize, Serializer};
 use serde_json;
 use skia_safe::Color4f;
 
-use crate::dom::dom::{Dom, NodeContent};
+use crate::dom::dom::{Dom, NodeContent, NodeId};
+use crate::dom::grid::{GridAutoFlow, GridPlacement, GridTemplateAreas, TrackList, TrackSize};
+use crate::dom::layoutengine::EdgeSizes;
+use crate::dom::length::{Length, LengthContext};
+use crate::dom::selector::Selector;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Color {
     pub r: f32,
     pub g: f32,
@@ -14,23 +20,23 @@ pub struct Color {
     pub a: f32,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Display {
     Block,
     Inline,
     InlineBlock,
     Flex,
+    Grid,
     None,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Size {
-    Points(f32),
-    Percent(f32),
+    Length(Length),
     Auto,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum FlexDirection {
     Row,
     RowReverse,
@@ -38,7 +44,7 @@ pub enum FlexDirection {
     ColumnReverse,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum JustifyContent {
     FlexStart,
     FlexEnd,
@@ -48,7 +54,7 @@ pub enum JustifyContent {
     SpaceEvenly,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum AlignItems {
     FlexStart,
     FlexEnd,
@@ -57,7 +63,7 @@ pub enum AlignItems {
     Baseline,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum AlignContent {
     FlexStart,
     FlexEnd,
@@ -65,9 +71,10 @@ pub enum AlignContent {
     Stretch,
     SpaceBetween,
     SpaceAround,
+    SpaceEvenly,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum AlignSelf {
     Auto,
     FlexStart,
@@ -77,24 +84,24 @@ pub enum AlignSelf {
     Baseline,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum FlexWrap {
     NoWrap,
     Wrap,
     WrapReverse,
 }
 
-#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
 pub struct BoxModelValues {
-     pub top: Option<f32>,
-    pub right: Option<f32>,
-    pub bottom: Option<f32>,
-    pub left: Option<f32>,
+    pub top: Option<Length>,
+    pub right: Option<Length>,
+    pub bottom: Option<Length>,
+    pub left: Option<Length>,
 }
 
 
 // An enum to represent different border styles.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
 pub struct BorderStyle {
     pub top: Option<BorderSide>,
     pub right: Option<BorderSide>,
@@ -102,44 +109,172 @@ pub struct BorderStyle {
     pub left: Option<BorderSide>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum BoxSizing {
     ContentBox,
     BorderBox,
-    Inherit,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Float {
     Left,
     Right,
     None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum WhiteSpace {
+    Normal,
+    NoWrap,
+    Pre,
+    PreWrap,
+    PreLine,
+}
+
+/// What happens to content that does not fit inside a box's padding box, per axis.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Overflow {
+    Visible,
+    /// Clipped, and scrollable only programmatically.
+    Hidden,
+    /// Clipped, with a scrollbar shown whether or not there is anything to scroll.
+    Scroll,
+    /// Clipped, with a scrollbar shown only when the content overflows.
+    Auto,
+}
+
+/// Where an inline box sits in its line.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum VerticalAlign {
+    Baseline,
+    Sub,
+    Super,
+    /// The top of the box at the top of the parent's text
+    TextTop,
+    TextBottom,
+    /// The middle of the box at the parent's baseline raised by half an x-height
+    Middle,
+    /// The top of the box at the top of the line
+    Top,
+    Bottom,
+    /// The baseline raised by a length, or a percentage of the line height
+    Length(Length),
+}
+
+/// The height of each line of text.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LineHeight {
+    /// Nine eighths of the font size
+    Normal,
+    /// A multiple of the font size, inherited as the multiple rather than the height
+    Number(f32),
+    Length(Length),
+}
+
+/// How lines are lined up inside their block. `Start` and `End` follow the text's direction.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TextAlign {
+    Left,
+    Right,
+    Center,
+    /// Both edges, by stretching the spaces of every line but the last
+    Justify,
+    Start,
+    End,
+}
+
+/// `inherit`, `initial` and `unset`, accepted by every property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CssWideKeyword {
     Inherit,
+    Initial,
+    /// Behaves as `inherit` for inherited properties and `initial` otherwise.
+    Unset,
 }
 
-#[derive(Debug, Clone, Serialize, Default)]
-pub struct PositionOffsets {
-    pub top: Option<f32>,
-    pub right: Option<f32>,
-    pub bottom: Option<f32>,
-    pub left: Option<f32>,
+/// Identifies a property of `Style`/`ComputedStyle`, at the granularity the structs store them. The
+/// sides of the box properties and offsets and the two lines of a grid placement are separate, so
+/// a keyword on one of them leaves the others alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PropertyId {
+    Display,
+    Width,
+    Height,
+    BackgroundColor,
+    Color,
+    MarginTop,
+    MarginRight,
+    MarginBottom,
+    MarginLeft,
+    PaddingTop,
+    PaddingRight,
+    PaddingBottom,
+    PaddingLeft,
+    BorderTop,
+    BorderRight,
+    BorderBottom,
+    BorderLeft,
+    BoxSizing,
+    Position,
+    Top,
+    Right,
+    Bottom,
+    Left,
+    Float,
+    FontSize,
+    FontFamily,
+    FontWeight,
+    FontStyle,
+    FontStretch,
+    WhiteSpace,
+    FlexDirection,
+    JustifyContent,
+    AlignItems,
+    AlignContent,
+    FlexWrap,
+    AlignSelf,
+    FlexGrow,
+    FlexShrink,
+    FlexBasis,
+    Order,
+    RowGap,
+    ColumnGap,
+    GridTemplateColumns,
+    GridTemplateRows,
+    GridTemplateAreas,
+    GridAutoColumns,
+    GridAutoRows,
+    GridAutoFlow,
+    GridColumnStart,
+    GridColumnEnd,
+    GridRowStart,
+    GridRowEnd,
+    OverflowX,
+    OverflowY,
+    VerticalAlign,
+    LineHeight,
+    TextAlign,
+    TextIndent,
+    LetterSpacing,
+    WordSpacing,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Default)]
-pub struct ComputedPositionOffsets {
-    pub top: f32,
-    pub right: f32,
-    pub bottom: f32,
-    pub left: f32,
+/// `top`/`right`/`bottom`/`left`. In `Style`, `None` leaves a side unspecified; once computed, `None` and `Size::Auto` both mean `auto`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub struct PositionOffsets {
+    pub top: Option<Size>,
+    pub right: Option<Size>,
+    pub bottom: Option<Size>,
+    pub left: Option<Size>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct BorderSide {
-    pub width: f32,
+    pub width: Length,
     pub color: Color,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Style {
     pub display: Option<Display>,
     pub width: Option<Size>,  // Changed from f32
@@ -151,9 +286,10 @@ pub struct Style {
     pub border: Option<BorderStyle>,
     pub box_sizing: Option<BoxSizing>,
     pub position: Option<Position>,
-    pub position_offsets: Option<ComputedPositionOffsets>,
+    pub position_offsets: Option<PositionOffsets>,
     pub float: Option<Float>,
     pub font: Option<Font>,
+    pub white_space: Option<WhiteSpace>,
 
     // NEW Flex container properties
     pub flex_direction: Option<FlexDirection>,
@@ -166,17 +302,69 @@ pub struct Style {
     pub align_self: Option<AlignSelf>,
     pub flex_grow: Option<f32>,
     pub flex_shrink: Option<f32>,
-    // flex_basis can be added later for more complexity
+    pub flex_basis: Option<Size>,
+    pub order: Option<i32>,
+
+    // Gaps between flex lines (`row_gap`) and between items in a line (`column_gap`) for rows
+    pub row_gap: Option<Length>,
+    pub column_gap: Option<Length>,
+
+    // Grid container properties
+    pub grid_template_columns: Option<TrackList>,
+    pub grid_template_rows: Option<TrackList>,
+    pub grid_template_areas: Option<GridTemplateAreas>,
+    pub grid_auto_columns: Option<TrackSize>,
+    pub grid_auto_rows: Option<TrackSize>,
+    pub grid_auto_flow: Option<GridAutoFlow>,
+
+    // Grid item properties
+    pub grid_column: Option<GridPlacement>,
+    pub grid_row: Option<GridPlacement>,
+
+    pub overflow_x: Option<Overflow>,
+    pub overflow_y: Option<Overflow>,
+    pub vertical_align: Option<VerticalAlign>,
+    pub line_height: Option<LineHeight>,
+    pub text_align: Option<TextAlign>,
+    pub text_indent: Option<Length>,
+    pub letter_spacing: Option<Length>,
+    pub word_spacing: Option<Length>,
+
+    // Properties set to `inherit`/`initial`/`unset`; these win over a value for the same property
+    #[serde(default)]
+    pub keywords: BTreeMap<PropertyId, CssWideKeyword>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Font {
     pub color: Option<Color>,
-    pub font_size: Option<f32>,
-    pub font_family: Option<String>,
+    pub font_size: Option<Length>,
+    /// Families in order of preference; later ones supply the glyphs earlier ones lack.
+    pub font_family: Option<Vec<String>>,
+    pub font_weight: Option<FontWeight>,
+    pub font_style: Option<FontSlant>,
+    /// Width as a percentage of the normal width, from 50 to 200
+    pub font_stretch: Option<f32>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// `font-weight` as specified.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FontWeight {
+    /// A weight from 1 to 1000, 400 being `normal` and 700 `bold`
+    Absolute(u16),
+    Bolder,
+    Lighter,
+}
+
+/// `font-style`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FontSlant {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ComputedStyle {
     pub display: Display,
     pub width: Option<Size>,
@@ -193,7 +381,11 @@ pub struct ComputedStyle {
     
     pub color: Color,
     pub font_size: f32,
-    pub font_family: String,
+    pub font_family: Vec<String>,
+    pub font_weight: u16,
+    pub font_style: FontSlant,
+    pub font_stretch: f32,
+    pub white_space: WhiteSpace,
     // NEW Flex container properties
     pub flex_direction: FlexDirection,
     pub justify_content: JustifyContent,
@@ -205,16 +397,40 @@ pub struct ComputedStyle {
     pub align_self: AlignSelf,
     pub flex_grow: f32, // Default: 0.0
     pub flex_shrink: f32, // Default: 1.0
-                        // flex_basis can be added later for more complexity
+    pub flex_basis: Size, // Default: Auto, which uses the width or height
+    pub order: i32,
+
+    pub row_gap: Length,
+    pub column_gap: Length,
+
+    pub grid_template_columns: TrackList,
+    pub grid_template_rows: TrackList,
+    pub grid_template_areas: GridTemplateAreas,
+    pub grid_auto_columns: TrackSize,
+    pub grid_auto_rows: TrackSize,
+    pub grid_auto_flow: GridAutoFlow,
+    pub grid_column: GridPlacement,
+    pub grid_row: GridPlacement,
+
+    pub overflow_x: Overflow,
+    pub overflow_y: Overflow,
+    pub vertical_align: VerticalAlign,
+    pub line_height: LineHeight,
+    pub text_align: TextAlign,
+    /// Resolved against the width of the block at layout time
+    pub text_indent: Length,
+    pub letter_spacing: Length,
+    pub word_spacing: Length,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Position {
     Static,
     Relative,
     Absolute,
     Fixed,
-    // Sticky can be added later
+    /// Relative, but shifted to stay inside the nearest scroll container while its parent is visible
+    Sticky,
 }
 
 impl From<Color> for Color4f {
@@ -223,9 +439,235 @@ impl From<Color> for Color4f {
     }
 }
 
+impl PropertyId {
+    pub const ALL: [PropertyId; 60] = [
+        PropertyId::Display,
+        PropertyId::Width,
+        PropertyId::Height,
+        PropertyId::BackgroundColor,
+        PropertyId::Color,
+        PropertyId::MarginTop,
+        PropertyId::MarginRight,
+        PropertyId::MarginBottom,
+        PropertyId::MarginLeft,
+        PropertyId::PaddingTop,
+        PropertyId::PaddingRight,
+        PropertyId::PaddingBottom,
+        PropertyId::PaddingLeft,
+        PropertyId::BorderTop,
+        PropertyId::BorderRight,
+        PropertyId::BorderBottom,
+        PropertyId::BorderLeft,
+        PropertyId::BoxSizing,
+        PropertyId::Position,
+        PropertyId::Top,
+        PropertyId::Right,
+        PropertyId::Bottom,
+        PropertyId::Left,
+        PropertyId::Float,
+        PropertyId::FontSize,
+        PropertyId::FontFamily,
+        PropertyId::FontWeight,
+        PropertyId::FontStyle,
+        PropertyId::FontStretch,
+        PropertyId::WhiteSpace,
+        PropertyId::FlexDirection,
+        PropertyId::JustifyContent,
+        PropertyId::AlignItems,
+        PropertyId::AlignContent,
+        PropertyId::FlexWrap,
+        PropertyId::AlignSelf,
+        PropertyId::FlexGrow,
+        PropertyId::FlexShrink,
+        PropertyId::FlexBasis,
+        PropertyId::Order,
+        PropertyId::RowGap,
+        PropertyId::ColumnGap,
+        PropertyId::GridTemplateColumns,
+        PropertyId::GridTemplateRows,
+        PropertyId::GridTemplateAreas,
+        PropertyId::GridAutoColumns,
+        PropertyId::GridAutoRows,
+        PropertyId::GridAutoFlow,
+        PropertyId::GridColumnStart,
+        PropertyId::GridColumnEnd,
+        PropertyId::GridRowStart,
+        PropertyId::GridRowEnd,
+        PropertyId::OverflowX,
+        PropertyId::OverflowY,
+        PropertyId::VerticalAlign,
+        PropertyId::LineHeight,
+        PropertyId::TextAlign,
+        PropertyId::TextIndent,
+        PropertyId::LetterSpacing,
+        PropertyId::WordSpacing,
+    ];
+
+    /// Inherited properties take the parent's computed value when not specified.
+    pub fn is_inherited(self) -> bool {
+        matches!(
+            self,
+            PropertyId::Color
+                | PropertyId::FontSize
+                | PropertyId::FontFamily
+                | PropertyId::FontWeight
+                | PropertyId::FontStyle
+                | PropertyId::FontStretch
+                | PropertyId::WhiteSpace
+                | PropertyId::LineHeight
+                | PropertyId::TextAlign
+                | PropertyId::TextIndent
+                | PropertyId::LetterSpacing
+                | PropertyId::WordSpacing
+        )
+    }
+}
+
+impl FontWeight {
+    pub const NORMAL: u16 = 400;
+    pub const BOLD: u16 = 700;
+
+    /// The computed weight, given the weight inherited from the parent.
+    pub fn resolve(self, inherited: u16) -> u16 {
+        match self {
+            FontWeight::Absolute(weight) => weight,
+            FontWeight::Bolder => match inherited {
+                ..350 => 400,
+                350..550 => 700,
+                550..900 => 900,
+                _ => inherited,
+            },
+            FontWeight::Lighter => match inherited {
+                ..100 => inherited,
+                100..550 => 100,
+                550..750 => 400,
+                _ => 700,
+            },
+        }
+    }
+}
+
+impl Display {
+    /// Whether the box starts on its own line in normal flow.
+    pub fn is_block_level(self) -> bool {
+        matches!(self, Display::Block | Display::Flex | Display::Grid)
+    }
+
+    /// The display a box takes when it becomes a flex or grid item.
+    pub fn blockified(self) -> Display {
+        match self {
+            Display::Inline | Display::InlineBlock => Display::Block,
+            other => other,
+        }
+    }
+}
+
+impl Position {
+    /// Absolutely positioned and fixed boxes are taken out of normal flow.
+    pub fn is_out_of_flow(self) -> bool {
+        matches!(self, Position::Absolute | Position::Fixed)
+    }
+}
+
+impl Overflow {
+    /// Whether the user can scroll along an axis with this value, e.g. with the mouse wheel.
+    pub fn is_user_scrollable(self) -> bool {
+        matches!(self, Overflow::Scroll | Overflow::Auto)
+    }
+}
+
+impl ComputedStyle {
+    /// Whether a box with `other` would be laid out differently; colors only change painting.
+    pub fn differs_in_layout(&self, other: &ComputedStyle) -> bool {
+        let mut repainted = other.clone();
+        repainted.bg_color = self.bg_color;
+        repainted.color = self.color;
+        *self != repainted
+    }
+
+    /// Boxes that clip their content on either axis are scroll containers.
+    pub fn is_scroll_container(&self) -> bool {
+        self.overflow_x != Overflow::Visible || self.overflow_y != Overflow::Visible
+    }
+
+    /// Overwrites one property with the value it has in `source`.
+    pub fn copy_property(&mut self, source: &ComputedStyle, property: PropertyId) {
+        match property {
+            PropertyId::Display => self.display = source.display,
+            PropertyId::Width => self.width = source.width,
+            PropertyId::Height => self.height = source.height,
+            PropertyId::BackgroundColor => self.bg_color = source.bg_color,
+            PropertyId::Color => self.color = source.color,
+            PropertyId::MarginTop => self.margin.top = source.margin.top,
+            PropertyId::MarginRight => self.margin.right = source.margin.right,
+            PropertyId::MarginBottom => self.margin.bottom = source.margin.bottom,
+            PropertyId::MarginLeft => self.margin.left = source.margin.left,
+            PropertyId::PaddingTop => self.padding.top = source.padding.top,
+            PropertyId::PaddingRight => self.padding.right = source.padding.right,
+            PropertyId::PaddingBottom => self.padding.bottom = source.padding.bottom,
+            PropertyId::PaddingLeft => self.padding.left = source.padding.left,
+            PropertyId::BorderTop => self.border.top = source.border.top,
+            PropertyId::BorderRight => self.border.right = source.border.right,
+            PropertyId::BorderBottom => self.border.bottom = source.border.bottom,
+            PropertyId::BorderLeft => self.border.left = source.border.left,
+            PropertyId::BoxSizing => self.box_sizing = source.box_sizing,
+            PropertyId::Position => self.position = source.position,
+            PropertyId::Top => self.position_offsets.top = source.position_offsets.top,
+            PropertyId::Right => self.position_offsets.right = source.position_offsets.right,
+            PropertyId::Bottom => self.position_offsets.bottom = source.position_offsets.bottom,
+            PropertyId::Left => self.position_offsets.left = source.position_offsets.left,
+            PropertyId::Float => self.float = source.float,
+            PropertyId::FontSize => self.font_size = source.font_size,
+            PropertyId::FontFamily => self.font_family = source.font_family.clone(),
+            PropertyId::FontWeight => self.font_weight = source.font_weight,
+            PropertyId::FontStyle => self.font_style = source.font_style,
+            PropertyId::FontStretch => self.font_stretch = source.font_stretch,
+            PropertyId::WhiteSpace => self.white_space = source.white_space,
+            PropertyId::FlexDirection => self.flex_direction = source.flex_direction,
+            PropertyId::JustifyContent => self.justify_content = source.justify_content,
+            PropertyId::AlignItems => self.align_items = source.align_items,
+            PropertyId::AlignContent => self.align_content = source.align_content,
+            PropertyId::FlexWrap => self.flex_wrap = source.flex_wrap,
+            PropertyId::AlignSelf => self.align_self = source.align_self,
+            PropertyId::FlexGrow => self.flex_grow = source.flex_grow,
+            PropertyId::FlexShrink => self.flex_shrink = source.flex_shrink,
+            PropertyId::FlexBasis => self.flex_basis = source.flex_basis,
+            PropertyId::Order => self.order = source.order,
+            PropertyId::RowGap => self.row_gap = source.row_gap,
+            PropertyId::ColumnGap => self.column_gap = source.column_gap,
+            PropertyId::GridTemplateColumns => self.grid_template_columns = source.grid_template_columns.clone(),
+            PropertyId::GridTemplateRows => self.grid_template_rows = source.grid_template_rows.clone(),
+            PropertyId::GridTemplateAreas => self.grid_template_areas = source.grid_template_areas.clone(),
+            PropertyId::GridAutoColumns => self.grid_auto_columns = source.grid_auto_columns,
+            PropertyId::GridAutoRows => self.grid_auto_rows = source.grid_auto_rows,
+            PropertyId::GridAutoFlow => self.grid_auto_flow = source.grid_auto_flow,
+            PropertyId::GridColumnStart => self.grid_column.start = source.grid_column.start.clone(),
+            PropertyId::GridColumnEnd => self.grid_column.end = source.grid_column.end.clone(),
+            PropertyId::GridRowStart => self.grid_row.start = source.grid_row.start.clone(),
+            PropertyId::GridRowEnd => self.grid_row.end = source.grid_row.end.clone(),
+            PropertyId::OverflowX => self.overflow_x = source.overflow_x,
+            PropertyId::OverflowY => self.overflow_y = source.overflow_y,
+            PropertyId::VerticalAlign => self.vertical_align = source.vertical_align,
+            PropertyId::LineHeight => self.line_height = source.line_height,
+            PropertyId::TextAlign => self.text_align = source.text_align,
+            PropertyId::TextIndent => self.text_indent = source.text_indent,
+            PropertyId::LetterSpacing => self.letter_spacing = source.letter_spacing,
+            PropertyId::WordSpacing => self.word_spacing = source.word_spacing,
+        }
+    }
+}
+
 impl BorderStyle {
-    pub fn uniform(width: f32, color: Color) -> Self {
-        let side = BorderSide { width, color };
+    /// Overwrites the sides `other` specifies.
+    fn merge(&mut self, other: &BorderStyle) {
+        self.top = other.top.or(self.top);
+        self.right = other.right.or(self.right);
+        self.bottom = other.bottom.or(self.bottom);
+        self.left = other.left.or(self.left);
+    }
+
+    pub fn uniform(width: impl Into<Length>, color: Color) -> Self {
+        let side = BorderSide { width: width.into(), color };
         Self {
             top: Some(side),
             right: Some(side),
@@ -234,33 +676,182 @@ impl BorderStyle {
         }
     }
 
-    pub fn all(width: f32, color: Color) -> Self {
+    pub fn all(width: impl Into<Length>, color: Color) -> Self {
         Self::uniform(width, color)
     }
 
-    pub fn top(mut self, width: f32, color: Color) -> Self {
-        self.top = Some(BorderSide { width, color });
+    pub fn top(mut self, width: impl Into<Length>, color: Color) -> Self {
+        self.top = Some(BorderSide { width: width.into(), color });
         self
     }
 
-    pub fn right(mut self, width: f32, color: Color) -> Self {
-        self.right = Some(BorderSide { width, color });
+    pub fn right(mut self, width: impl Into<Length>, color: Color) -> Self {
+        self.right = Some(BorderSide { width: width.into(), color });
         self
     }
 
-    pub fn bottom(mut self, width: f32, color: Color) -> Self {
-        self.bottom = Some(BorderSide { width, color });
+    pub fn bottom(mut self, width: impl Into<Length>, color: Color) -> Self {
+        self.bottom = Some(BorderSide { width: width.into(), color });
         self
     }
 
-    pub fn left(mut self, width: f32, color: Color) -> Self {
-        self.left = Some(BorderSide { width, color });
+    pub fn left(mut self, width: impl Into<Length>, color: Color) -> Self {
+        self.left = Some(BorderSide { width: width.into(), color });
         self
     }
+
+    /// Used border widths in pixels; border widths never take percentages.
+    pub fn widths(&self) -> EdgeSizes {
+        let width = |side: Option<BorderSide>| side.map_or(0.0, |side| side.width.resolve(0.0));
+        EdgeSizes {
+            top: width(self.top),
+            right: width(self.right),
+            bottom: width(self.bottom),
+            left: width(self.left),
+        }
+    }
+
+    fn absolutize(self, context: &LengthContext) -> Self {
+        let side = |side: Option<BorderSide>| side.map(|side| BorderSide { width: side.width.absolutize(context), ..side });
+        Self {
+            top: side(self.top),
+            right: side(self.right),
+            bottom: side(self.bottom),
+            left: side(self.left),
+        }
+    }
+}
+
+impl BoxModelValues {
+    /// Overwrites the sides `other` specifies.
+    fn merge(&mut self, other: &BoxModelValues) {
+        self.top = other.top.or(self.top);
+        self.right = other.right.or(self.right);
+        self.bottom = other.bottom.or(self.bottom);
+        self.left = other.left.or(self.left);
+    }
+
+    fn absolutize(self, context: &LengthContext) -> Self {
+        let side = |value: Option<Length>| value.map(|value| value.absolutize(context));
+        Self {
+            top: side(self.top),
+            right: side(self.right),
+            bottom: side(self.bottom),
+            left: side(self.left),
+        }
+    }
+}
+
+impl PositionOffsets {
+    /// Overwrites the sides `other` specifies.
+    fn merge(&mut self, other: &PositionOffsets) {
+        self.top = other.top.or(self.top);
+        self.right = other.right.or(self.right);
+        self.bottom = other.bottom.or(self.bottom);
+        self.left = other.left.or(self.left);
+    }
+
+    fn absolutize(self, context: &LengthContext) -> Self {
+        let side = |value: Option<Size>| value.map(|value| value.absolutize(context));
+        Self {
+            top: side(self.top),
+            right: side(self.right),
+            bottom: side(self.bottom),
+            left: side(self.left),
+        }
+    }
+}
+
+impl Size {
+    fn absolutize(self, context: &LengthContext) -> Self {
+        match self {
+            Size::Length(length) => Size::Length(length.absolutize(context)),
+            Size::Auto => Size::Auto,
+        }
+    }
 }
 
 impl Style {
+    /// Whether a value (not a keyword) is specified for `property`.
+    pub fn has_value(&self, property: PropertyId) -> bool {
+        let font = self.font.as_ref();
+        match property {
+            PropertyId::Display => self.display.is_some(),
+            PropertyId::Width => self.width.is_some(),
+            PropertyId::Height => self.height.is_some(),
+            PropertyId::BackgroundColor => self.bg_color.is_some(),
+            PropertyId::Color => self.color.is_some() || font.is_some_and(|f| f.color.is_some()),
+            PropertyId::MarginTop => self.margin.is_some_and(|values| values.top.is_some()),
+            PropertyId::MarginRight => self.margin.is_some_and(|values| values.right.is_some()),
+            PropertyId::MarginBottom => self.margin.is_some_and(|values| values.bottom.is_some()),
+            PropertyId::MarginLeft => self.margin.is_some_and(|values| values.left.is_some()),
+            PropertyId::PaddingTop => self.padding.is_some_and(|values| values.top.is_some()),
+            PropertyId::PaddingRight => self.padding.is_some_and(|values| values.right.is_some()),
+            PropertyId::PaddingBottom => self.padding.is_some_and(|values| values.bottom.is_some()),
+            PropertyId::PaddingLeft => self.padding.is_some_and(|values| values.left.is_some()),
+            PropertyId::BorderTop => self.border.is_some_and(|values| values.top.is_some()),
+            PropertyId::BorderRight => self.border.is_some_and(|values| values.right.is_some()),
+            PropertyId::BorderBottom => self.border.is_some_and(|values| values.bottom.is_some()),
+            PropertyId::BorderLeft => self.border.is_some_and(|values| values.left.is_some()),
+            PropertyId::BoxSizing => self.box_sizing.is_some(),
+            PropertyId::Position => self.position.is_some(),
+            PropertyId::Top => self.position_offsets.is_some_and(|offsets| offsets.top.is_some()),
+            PropertyId::Right => self.position_offsets.is_some_and(|offsets| offsets.right.is_some()),
+            PropertyId::Bottom => self.position_offsets.is_some_and(|offsets| offsets.bottom.is_some()),
+            PropertyId::Left => self.position_offsets.is_some_and(|offsets| offsets.left.is_some()),
+            PropertyId::Float => self.float.is_some(),
+            PropertyId::FontSize => font.is_some_and(|f| f.font_size.is_some()),
+            PropertyId::FontFamily => font.is_some_and(|f| f.font_family.is_some()),
+            PropertyId::FontWeight => font.is_some_and(|f| f.font_weight.is_some()),
+            PropertyId::FontStyle => font.is_some_and(|f| f.font_style.is_some()),
+            PropertyId::FontStretch => font.is_some_and(|f| f.font_stretch.is_some()),
+            PropertyId::WhiteSpace => self.white_space.is_some(),
+            PropertyId::FlexDirection => self.flex_direction.is_some(),
+            PropertyId::JustifyContent => self.justify_content.is_some(),
+            PropertyId::AlignItems => self.align_items.is_some(),
+            PropertyId::AlignContent => self.align_content.is_some(),
+            PropertyId::FlexWrap => self.flex_wrap.is_some(),
+            PropertyId::AlignSelf => self.align_self.is_some(),
+            PropertyId::FlexGrow => self.flex_grow.is_some(),
+            PropertyId::FlexShrink => self.flex_shrink.is_some(),
+            PropertyId::FlexBasis => self.flex_basis.is_some(),
+            PropertyId::Order => self.order.is_some(),
+            PropertyId::RowGap => self.row_gap.is_some(),
+            PropertyId::ColumnGap => self.column_gap.is_some(),
+            PropertyId::GridTemplateColumns => self.grid_template_columns.is_some(),
+            PropertyId::GridTemplateRows => self.grid_template_rows.is_some(),
+            PropertyId::GridTemplateAreas => self.grid_template_areas.is_some(),
+            PropertyId::GridAutoColumns => self.grid_auto_columns.is_some(),
+            PropertyId::GridAutoRows => self.grid_auto_rows.is_some(),
+            PropertyId::GridAutoFlow => self.grid_auto_flow.is_some(),
+            PropertyId::GridColumnStart => self.grid_column.as_ref().is_some_and(|placement| placement.start.is_some()),
+            PropertyId::GridColumnEnd => self.grid_column.as_ref().is_some_and(|placement| placement.end.is_some()),
+            PropertyId::GridRowStart => self.grid_row.as_ref().is_some_and(|placement| placement.start.is_some()),
+            PropertyId::GridRowEnd => self.grid_row.as_ref().is_some_and(|placement| placement.end.is_some()),
+            PropertyId::OverflowX => self.overflow_x.is_some(),
+            PropertyId::OverflowY => self.overflow_y.is_some(),
+            PropertyId::VerticalAlign => self.vertical_align.is_some(),
+            PropertyId::LineHeight => self.line_height.is_some(),
+            PropertyId::TextAlign => self.text_align.is_some(),
+            PropertyId::TextIndent => self.text_indent.is_some(),
+            PropertyId::LetterSpacing => self.letter_spacing.is_some(),
+            PropertyId::WordSpacing => self.word_spacing.is_some(),
+        }
+    }
+
+    pub fn set_keyword(&mut self, property: PropertyId, keyword: CssWideKeyword) {
+        self.keywords.insert(property, keyword);
+    }
+
     pub fn apply(&mut self, other: &Style) {
+        // A value from `other` replaces a keyword set earlier for the same property
+        for property in PropertyId::ALL {
+            if other.has_value(property) {
+                self.keywords.remove(&property);
+            }
+        }
+        self.keywords.extend(other.keywords.iter().map(|(&property, &keyword)| (property, keyword)));
+
         if other.display.is_some() {
             self.display = other.display;
         }
@@ -276,14 +867,15 @@ impl Style {
         if other.color.is_some() {
             self.color = other.color;
         }
-        if other.margin.is_some() {
-            self.margin = other.margin;
+        // Box sides, offsets and grid lines are set one at a time, so those from different rules combine
+        if let Some(margin) = &other.margin {
+            self.margin.get_or_insert_with(BoxModelValues::default).merge(margin);
         }
-        if other.padding.is_some() {
-            self.padding = other.padding;
+        if let Some(padding) = &other.padding {
+            self.padding.get_or_insert_with(BoxModelValues::default).merge(padding);
         }
-        if other.border.is_some() {
-            self.border = other.border;
+        if let Some(border) = &other.border {
+            self.border.get_or_insert_with(BorderStyle::default).merge(border);
         }
         if other.box_sizing.is_some() {
             self.box_sizing = other.box_sizing;
@@ -294,96 +886,474 @@ impl Style {
         if other.position.is_some() {
             self.position = other.position;
         }
-        if other.position_offsets.is_some() {
-            self.position_offsets = other.position_offsets;
+        if let Some(offsets) = &other.position_offsets {
+            self.position_offsets.get_or_insert_with(PositionOffsets::default).merge(offsets);
+        }
+        if let Some(font) = &other.font {
+            let current = self.font.get_or_insert_with(Font::default);
+            if font.color.is_some() {
+                current.color = font.color;
+            }
+            if font.font_size.is_some() {
+                current.font_size = font.font_size;
+            }
+            if font.font_family.is_some() {
+                current.font_family = font.font_family.clone();
+            }
+            if font.font_weight.is_some() {
+                current.font_weight = font.font_weight;
+            }
+            if font.font_style.is_some() {
+                current.font_style = font.font_style;
+            }
+            if font.font_stretch.is_some() {
+                current.font_stretch = font.font_stretch;
+            }
+        }
+        if other.flex_direction.is_some() {
+            self.flex_direction = other.flex_direction;
+        }
+        if other.justify_content.is_some() {
+            self.justify_content = other.justify_content;
+        }
+        if other.align_items.is_some() {
+            self.align_items = other.align_items;
+        }
+        if other.align_content.is_some() {
+            self.align_content = other.align_content;
+        }
+        if other.flex_wrap.is_some() {
+            self.flex_wrap = other.flex_wrap;
+        }
+        if other.align_self.is_some() {
+            self.align_self = other.align_self;
+        }
+        if other.flex_grow.is_some() {
+            self.flex_grow = other.flex_grow;
+        }
+        if other.flex_shrink.is_some() {
+            self.flex_shrink = other.flex_shrink;
+        }
+        if other.white_space.is_some() {
+            self.white_space = other.white_space;
+        }
+        if other.flex_basis.is_some() {
+            self.flex_basis = other.flex_basis;
+        }
+        if other.order.is_some() {
+            self.order = other.order;
+        }
+        if other.row_gap.is_some() {
+            self.row_gap = other.row_gap;
+        }
+        if other.column_gap.is_some() {
+            self.column_gap = other.column_gap;
+        }
+        if other.grid_template_columns.is_some() {
+            self.grid_template_columns = other.grid_template_columns.clone();
+        }
+        if other.grid_template_rows.is_some() {
+            self.grid_template_rows = other.grid_template_rows.clone();
+        }
+        if other.grid_template_areas.is_some() {
+            self.grid_template_areas = other.grid_template_areas.clone();
+        }
+        if other.grid_auto_columns.is_some() {
+            self.grid_auto_columns = other.grid_auto_columns;
+        }
+        if other.grid_auto_rows.is_some() {
+            self.grid_auto_rows = other.grid_auto_rows;
+        }
+        if other.grid_auto_flow.is_some() {
+            self.grid_auto_flow = other.grid_auto_flow;
+        }
+        if let Some(column) = &other.grid_column {
+            self.grid_column.get_or_insert_with(GridPlacement::default).merge(column);
+        }
+        if let Some(row) = &other.grid_row {
+            self.grid_row.get_or_insert_with(GridPlacement::default).merge(row);
+        }
+        if other.overflow_x.is_some() {
+            self.overflow_x = other.overflow_x;
+        }
+        if other.overflow_y.is_some() {
+            self.overflow_y = other.overflow_y;
+        }
+        if other.vertical_align.is_some() {
+            self.vertical_align = other.vertical_align;
+        }
+        if other.line_height.is_some() {
+            self.line_height = other.line_height;
+        }
+        if other.text_align.is_some() {
+            self.text_align = other.text_align;
+        }
+        if other.text_indent.is_some() {
+            self.text_indent = other.text_indent;
+        }
+        if other.letter_spacing.is_some() {
+            self.letter_spacing = other.letter_spacing;
+        }
+        if other.word_spacing.is_some() {
+            self.word_spacing = other.word_spacing;
         }
     }
 }
 
 impl Dom {
+    /// Recomputes the styles of dirty nodes and everything below them, and of the siblings after
+    /// them when a stylesheet uses sibling combinators. Other nodes keep their computed styles.
+    /// Nodes whose style changed in a way that affects layout are marked for layout.
     pub fn compute_styles(&mut self, viewport_width: f32, viewport_height: f32) {
-        let all_nodes = self.collect_nodes_depth_first(self.root);
+        // Whatever changed since the last frame is one batch for mutation observers
+        self.deliver_mutations();
+
+        // Viewport units resolve against the viewport, so resizing restyles everything
+        if self.styled_viewport != Some((viewport_width, viewport_height)) {
+            self.styled_viewport = Some((viewport_width, viewport_height));
+            self.dirty.borrow_mut().insert(self.root.into(), true);
+        }
+        let sibling_sensitive = self.stylesheets.iter().flat_map(|sheet| &sheet.rules).flat_map(|rule| &rule.selectors).any(Selector::has_sibling_combinator);
+        let initial_font_size = ComputedStyle::default().font_size;
 
-        for node_id in all_nodes {
+        let mut stack = vec![(self.root, false)];
+        while let Some((node_id, ancestor_restyled)) = stack.pop() {
             let key: slotmap::DefaultKey = node_id.into();
+            let restyle = ancestor_restyled || self.is_dirty(node_id) || !self.computed_styles.contains_key(key);
 
-            match self.content.get(key) {
-                Some(NodeContent::Element(_)) => {
-                    let user_style = self.styles.get(key).unwrap();
-                    // Get parent style using our new parent method
-                    let parent_style = self.parent(node_id).and_then(|parent_id| {
-                        let parent_key: slotmap::DefaultKey = parent_id.into();
-                        self.computed_styles.get(parent_key)
-                    });
-
-                    let computed_style = Self::resolve_style(&user_style, parent_style);
-                    self.computed_styles.insert(key, computed_style);
+            // Descendants of a restyled node restyle too, and with sibling combinators so do the siblings after a dirty one
+            if let Some(children) = self.children.get(key) {
+                let mut after_dirty_sibling = false;
+                let mut queued = Vec::with_capacity(children.len());
+                for &child_id in children {
+                    queued.push((child_id, restyle || after_dirty_sibling));
+                    after_dirty_sibling |= sibling_sensitive && self.is_dirty(child_id);
                 }
-                Some(NodeContent::Text(_)) => {
+                stack.extend(queued.into_iter().rev());
+            }
+            if !restyle {
+                continue;
+            }
+
+            let style = match self.content.get(key) {
+                Some(NodeContent::Element(_)) => self.cascaded_style(node_id),
+                // Text inherits from its element as an inline box, unless it was given a font of its own.
+                Some(NodeContent::Text(_)) => Style {
+                    display: Some(Display::Inline),
+                    font: self.styles.get(key).and_then(|style| style.font.clone()),
+                    ..Style::default()
+                },
+                None => continue,
+            };
+
+            let parent_style = self.parent(node_id).and_then(|parent_id| self.computed_styles.get(parent_id.into()));
+            // `rem` is the root element's font size, and the initial one on the root itself
+            let root_font_size = if node_id == self.root { initial_font_size } else { self.computed_styles.get(self.root.into()).map_or(initial_font_size, |root| root.font_size) };
+            let context = LengthContext {
+                font_size: parent_style.map_or(initial_font_size, |parent| parent.font_size),
+                root_font_size,
+                viewport_width,
+                viewport_height,
+            };
+
+            let computed_style = Self::resolve_style(&style, parent_style, &context);
+            let relayout = self.computed_styles.get(key).is_none_or(|previous| previous.differs_in_layout(&computed_style));
+            let moved_after_flow = computed_style.position != Position::Static || computed_style.is_scroll_container();
+            self.computed_styles.insert(key, computed_style);
+            if relayout {
+                self.mark_layout_dirty(node_id);
+            }
+            // Restyled descendants come later and flag this node again if they need it
+            self.post_layout_subtrees.remove(key);
+            if moved_after_flow {
+                self.mark_post_layout_subtree(node_id);
+            }
+        }
+        self.dirty.borrow_mut().clear();
+    }
+
+    /// Merges every matching stylesheet rule, ordered by specificity then source order,
+    /// with the node's own style applied last.
+    fn cascaded_style(&self, node_id: NodeId) -> Style {
+        let mut matched = Vec::new();
+        for (sheet_index, sheet) in self.stylesheets.iter().enumerate() {
+            for (rule_index, rule) in sheet.rules.iter().enumerate() {
+                // A rule with a selector list applies with its most specific matching selector.
+                if let Some(specificity) = rule.selectors.iter().filter(|selector| selector.matches(self, node_id)).map(|selector| selector.specificity()).max() {
+                    matched.push((specificity, sheet_index, rule_index, &rule.style));
                 }
-                None => {}
             }
         }
+        matched.sort_by_key(|&(specificity, sheet_index, rule_index, _)| (specificity, sheet_index, rule_index));
+
+        let mut style = Style::default();
+        for (_, _, _, rule_style) in matched {
+            style.apply(rule_style);
+        }
+        if let Some(inline_style) = self.styles.get(node_id.into()) {
+            style.apply(inline_style);
+        }
+        style
     }
 
-    fn resolve_style(style: &Style, parent_style: Option<&ComputedStyle>) -> ComputedStyle {
-        // Start with dark theme defaults
-        let mut computed = ComputedStyle::default();
+    /// `context` measures against the parent's font size; lengths on the element itself are
+    /// converted to pixels against its own computed font size once that is known.
+    fn resolve_style(style: &Style, parent_style: Option<&ComputedStyle>, context: &LengthContext) -> ComputedStyle {
+        let initial = ComputedStyle::default();
+        let mut computed = initial.clone();
+
+        // Inherited properties start from the parent, everything else from its initial value
+        if let Some(parent) = parent_style {
+            for property in PropertyId::ALL.into_iter().filter(|property| property.is_inherited()) {
+                computed.copy_property(parent, property);
+            }
+        }
 
         if let Some(display) = style.display {
             computed.display = display;
-        } else if let Some(parent) = parent_style {
-            computed.display = parent.display;
         }
-
-        if let Some(box_sizing) = style.box_sizing {
-            computed.box_sizing = match box_sizing {
-                BoxSizing::Inherit => {
-                    if let Some(parent) = parent_style {
-                        parent.box_sizing
-                    } else {
-                        BoxSizing::ContentBox
-                    }
-                }
-                _ => box_sizing,
-            };
+        if style.width.is_some() {
+            computed.width = style.width;
         }
-
-        if let Some(float) = style.float {
-            computed.float = match float {
-                Float::Inherit => {
-                    if let Some(parent) = parent_style {
-                        parent.float
-                    } else {
-                        Float::None
-                    }
-                }
-                _ => float,
-            };
+        if style.height.is_some() {
+            computed.height = style.height;
         }
-
-        // Apply element-specific styling
         if let Some(bg_color) = style.bg_color {
             computed.bg_color = bg_color;
         }
-
         if let Some(margin) = style.margin {
             computed.margin = margin;
         }
-
         if let Some(padding) = style.padding {
             computed.padding = padding;
         }
-
         if let Some(border) = style.border {
             computed.border = border;
         }
-
+        if let Some(box_sizing) = style.box_sizing {
+            computed.box_sizing = box_sizing;
+        }
         if let Some(position) = style.position {
             computed.position = position;
         }
+        if let Some(offsets) = &style.position_offsets {
+            computed.position_offsets.merge(offsets);
+        }
+        if let Some(float) = style.float {
+            computed.float = float;
+        }
+        if let Some(white_space) = style.white_space {
+            computed.white_space = white_space;
+        }
+
+        let font = style.font.as_ref();
+        if let Some(color) = font.and_then(|font| font.color).or(style.color) {
+            computed.color = color;
+        }
+        if let Some(font_size) = font.and_then(|font| font.font_size) {
+            // `em` and percentages in `font-size` are relative to the parent's font size
+            computed.font_size = font_size.absolutize(context).resolve(context.font_size);
+        }
+        if let Some(font_family) = font.and_then(|font| font.font_family.clone()) {
+            computed.font_family = font_family;
+        }
+        if let Some(font_weight) = font.and_then(|font| font.font_weight) {
+            // `bolder` and `lighter` step from the inherited weight
+            computed.font_weight = font_weight.resolve(computed.font_weight);
+        }
+        if let Some(font_style) = font.and_then(|font| font.font_style) {
+            computed.font_style = font_style;
+        }
+        if let Some(font_stretch) = font.and_then(|font| font.font_stretch) {
+            computed.font_stretch = font_stretch;
+        }
+
+        if let Some(flex_direction) = style.flex_direction {
+            computed.flex_direction = flex_direction;
+        }
+        if let Some(justify_content) = style.justify_content {
+            computed.justify_content = justify_content;
+        }
+        if let Some(align_items) = style.align_items {
+            computed.align_items = align_items;
+        }
+        if let Some(align_content) = style.align_content {
+            computed.align_content = align_content;
+        }
+        if let Some(flex_wrap) = style.flex_wrap {
+            computed.flex_wrap = flex_wrap;
+        }
+        if let Some(align_self) = style.align_self {
+            computed.align_self = align_self;
+        }
+        if let Some(flex_grow) = style.flex_grow {
+            computed.flex_grow = flex_grow;
+        }
+        if let Some(flex_shrink) = style.flex_shrink {
+            computed.flex_shrink = flex_shrink;
+        }
+        if let Some(flex_basis) = style.flex_basis {
+            computed.flex_basis = flex_basis;
+        }
+        if let Some(order) = style.order {
+            computed.order = order;
+        }
+        if let Some(row_gap) = style.row_gap {
+            computed.row_gap = row_gap;
+        }
+        if let Some(column_gap) = style.column_gap {
+            computed.column_gap = column_gap;
+        }
+        if let Some(columns) = &style.grid_template_columns {
+            computed.grid_template_columns = columns.clone();
+        }
+        if let Some(rows) = &style.grid_template_rows {
+            computed.grid_template_rows = rows.clone();
+        }
+        if let Some(areas) = &style.grid_template_areas {
+            computed.grid_template_areas = areas.clone();
+        }
+        if let Some(auto_columns) = style.grid_auto_columns {
+            computed.grid_auto_columns = auto_columns;
+        }
+        if let Some(auto_rows) = style.grid_auto_rows {
+            computed.grid_auto_rows = auto_rows;
+        }
+        if let Some(auto_flow) = style.grid_auto_flow {
+            computed.grid_auto_flow = auto_flow;
+        }
+        if let Some(column) = &style.grid_column {
+            computed.grid_column = column.clone();
+        }
+        if let Some(row) = &style.grid_row {
+            computed.grid_row = row.clone();
+        }
+        if let Some(overflow_x) = style.overflow_x {
+            computed.overflow_x = overflow_x;
+        }
+        if let Some(overflow_y) = style.overflow_y {
+            computed.overflow_y = overflow_y;
+        }
+        if let Some(vertical_align) = style.vertical_align {
+            computed.vertical_align = vertical_align;
+        }
+        if let Some(line_height) = style.line_height {
+            computed.line_height = line_height;
+        }
+        if let Some(text_align) = style.text_align {
+            computed.text_align = text_align;
+        }
+        if let Some(text_indent) = style.text_indent {
+            computed.text_indent = text_indent;
+        }
+        if let Some(letter_spacing) = style.letter_spacing {
+            computed.letter_spacing = letter_spacing;
+        }
+        if let Some(word_spacing) = style.word_spacing {
+            computed.word_spacing = word_spacing;
+        }
+
+        // `inherit`, `initial` and `unset` override any value given for the same property
+        for (&property, &keyword) in &style.keywords {
+            let inherit = match keyword {
+                CssWideKeyword::Inherit => true,
+                CssWideKeyword::Initial => false,
+                CssWideKeyword::Unset => property.is_inherited(),
+            };
+            match parent_style {
+                Some(parent) if inherit => computed.copy_property(parent, property),
+                // The root has nothing to inherit from, so `inherit` falls back to the initial value
+                _ => computed.copy_property(&initial, property),
+            }
+        }
+
+        let context = LengthContext { font_size: computed.font_size, ..*context };
+        computed.width = computed.width.map(|width| width.absolutize(&context));
+        computed.height = computed.height.map(|height| height.absolutize(&context));
+        computed.margin = computed.margin.absolutize(&context);
+        computed.padding = computed.padding.absolutize(&context);
+        computed.border = computed.border.absolutize(&context);
+        computed.position_offsets = computed.position_offsets.absolutize(&context);
+        computed.flex_basis = computed.flex_basis.absolutize(&context);
+        computed.row_gap = computed.row_gap.absolutize(&context);
+        computed.column_gap = computed.column_gap.absolutize(&context);
+        computed.grid_template_columns = computed.grid_template_columns.absolutize(&context);
+        computed.grid_template_rows = computed.grid_template_rows.absolutize(&context);
+        computed.grid_auto_columns = computed.grid_auto_columns.absolutize(&context);
+        computed.grid_auto_rows = computed.grid_auto_rows.absolutize(&context);
+        if let VerticalAlign::Length(length) = computed.vertical_align {
+            computed.vertical_align = VerticalAlign::Length(length.absolutize(&context));
+        }
+        if let LineHeight::Length(length) = computed.line_height {
+            // Percentages are of the element's own font size, and children inherit the height
+            computed.line_height = LineHeight::Length(Length::Px(length.absolutize(&context).resolve(computed.font_size)));
+        }
+        computed.text_indent = computed.text_indent.absolutize(&context);
+        computed.letter_spacing = computed.letter_spacing.absolutize(&context);
+        computed.word_spacing = computed.word_spacing.absolutize(&context);
+
+        // Flex and grid items and out-of-flow boxes are always block-level boxes and cannot float
+        if computed.position.is_out_of_flow() || parent_style.is_some_and(|parent| matches!(parent.display, Display::Flex | Display::Grid)) {
+            computed.display = computed.display.blockified();
+            computed.float = Float::None;
+        }
+
+        // A box can't clip one axis and let the other spill, so `visible` next to anything else acts as `auto`
+        if computed.overflow_x == Overflow::Visible && computed.overflow_y != Overflow::Visible {
+            computed.overflow_x = Overflow::Auto;
+        } else if computed.overflow_y == Overflow::Visible && computed.overflow_x != Overflow::Visible {
+            computed.overflow_y = Overflow::Auto;
+        }
 
         computed
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::grid::GridLine;
+
+    #[test]
+    fn longhands_from_a_more_specific_rule_keep_the_other_sides() {
+        let html = r#"<style>
+            div { margin: 10px; border: 1px solid red; grid-column: 1 / 3; top: 2px }
+            .a { margin-left: 5px; border-top: none; grid-column-end: 4; left: 7px }
+        </style><div class="a"></div>"#;
+        let mut dom = Dom::from_html(html).unwrap();
+        dom.compute_styles(800.0, 600.0);
+        let div = dom.children(dom.root).unwrap()[0];
+        let style = &dom.computed_styles[div.into()];
+
+        assert_eq!(style.margin, BoxModelValues::default().top(10.0).right(10.0).bottom(10.0).left(5.0));
+        let border = style.border.widths();
+        assert_eq!((border.top, border.right, border.bottom, border.left), (0.0, 1.0, 1.0, 1.0));
+        assert_eq!(style.grid_column, GridPlacement { start: Some(GridLine::Line(1)), end: Some(GridLine::Line(4)) });
+        assert_eq!((style.position_offsets.top, style.position_offsets.left), (Some(Size::Length(Length::Px(2.0))), Some(Size::Length(Length::Px(7.0)))));
+    }
+
+    #[test]
+    fn merged_sides_survive_writing_css() {
+        let mut style = Style::from_css("margin: 1px; border: 2px solid #ff0000; grid-row: 2 / 3").unwrap();
+        style.apply(&Style::from_css("margin-top: 4px; border-left: none; grid-row-start: span 2").unwrap());
+        assert_eq!(Style::from_css(&style.to_css()).unwrap(), style);
+        assert!(style.to_css().contains("border-left: none"));
+    }
+
+    #[test]
+    fn passes_after_normal_flow_follow_moved_positioned_boxes() {
+        let html = r#"<style>
+            #b { position: absolute } #d { display: none } #e { overflow: hidden }
+        </style><div id="a"><div id="b"></div></div><div id="c"></div><div id="d"><div id="e"></div></div>"#;
+        let mut dom = Dom::from_html(html).unwrap();
+        dom.compute_styles(800.0, 600.0);
+        let [b, c] = ["b", "c"].map(|id| dom.get_element_by_id(id).unwrap());
+        assert_eq!(dom.post_layout_boxes(), vec![b]);
+
+        dom.append_child(c, b).unwrap();
+        dom.compute_styles(800.0, 600.0);
+        assert_eq!(dom.post_layout_subtrees.get(c.into()), Some(&true));
+        assert_eq!(dom.post_layout_boxes(), vec![b]);
+    }
+}
@@ -1,6 +1,11 @@
 // /src/dom/text.rs
 
+use std::ops::Range;
+
+use crate::dom::domapi::DomError;
+use crate::dom::observer::MutationKind;
 use crate::dom::styleengine::Color;
+use crate::dom::{dom::NodeContent, dom::NodeId, Dom};
 
 #[derive(Debug, Clone)]
 pub struct Text {
@@ -13,4 +18,233 @@ impl Text {
             content: content.into(),
         }
     }
-}
\ No newline at end of file
+
+    /// The byte offset of a char offset; the length of the text is a valid offset too.
+    pub fn byte_offset(&self, char_offset: usize) -> Option<usize> {
+        self.content.char_indices().map(|(offset, _)| offset).chain(std::iter::once(self.content.len())).nth(char_offset)
+    }
+
+    fn byte_range(&self, char_range: Range<usize>) -> Result<Range<usize>, DomError> {
+        let len = self.content.chars().count();
+        let out_of_bounds = |offset| DomError::TextOffsetOutOfBounds { offset, len };
+        let start = self.byte_offset(char_range.start).ok_or(out_of_bounds(char_range.start))?;
+        let end = self.byte_offset(char_range.end).ok_or(out_of_bounds(char_range.end))?;
+        Ok(start..end)
+    }
+
+    fn check_byte_range(&self, range: &Range<usize>) -> Result<(), DomError> {
+        let len = self.content.len();
+        for offset in [range.start, range.end] {
+            if offset > len {
+                return Err(DomError::TextOffsetOutOfBounds { offset, len });
+            }
+            if !self.content.is_char_boundary(offset) {
+                return Err(DomError::NotACharBoundary(offset));
+            }
+        }
+        if range.start > range.end {
+            return Err(DomError::TextOffsetOutOfBounds { offset: range.start, len: range.end });
+        }
+        Ok(())
+    }
+}
+
+/// Editing text nodes. Offsets and ranges are in bytes, except in the `_chars` variants, which
+/// count chars. Each edit drops only the edited node's measured text, so the next layout
+/// measures that node again and reuses everything else.
+impl Dom {
+    /// Replaces a byte range of a text node's content and returns the text that was there.
+    pub fn replace_text(&mut self, node_id: NodeId, range: Range<usize>, replacement: &str) -> Result<String, DomError> {
+        let observed = self.is_observed();
+        let text = self.text_mut(node_id)?;
+        text.check_byte_range(&range)?;
+        let old_value = observed.then(|| text.content.clone());
+        let removed = text.content[range.clone()].to_string();
+        text.content.replace_range(range, replacement);
+
+        self.invalidate_text(node_id);
+        self.queue_mutation(node_id, MutationKind::Text { old_value });
+        Ok(removed)
+    }
+
+    pub fn replace_text_chars(&mut self, node_id: NodeId, range: Range<usize>, replacement: &str) -> Result<String, DomError> {
+        let range = self.text_ref(node_id)?.byte_range(range)?;
+        self.replace_text(node_id, range, replacement)
+    }
+
+    pub fn insert_text(&mut self, node_id: NodeId, offset: usize, text: &str) -> Result<(), DomError> {
+        self.replace_text(node_id, offset..offset, text).map(|_| ())
+    }
+
+    pub fn insert_text_chars(&mut self, node_id: NodeId, offset: usize, text: &str) -> Result<(), DomError> {
+        self.replace_text_chars(node_id, offset..offset, text).map(|_| ())
+    }
+
+    /// Removes a byte range of a text node's content and returns it.
+    pub fn delete_text(&mut self, node_id: NodeId, range: Range<usize>) -> Result<String, DomError> {
+        self.replace_text(node_id, range, "")
+    }
+
+    pub fn delete_text_chars(&mut self, node_id: NodeId, range: Range<usize>) -> Result<String, DomError> {
+        self.replace_text_chars(node_id, range, "")
+    }
+
+    /// Moves the text after `offset` into a new text node with the same inline style, placed
+    /// right after the original one when it has a parent. Returns the new node.
+    pub fn split_text(&mut self, node_id: NodeId, offset: usize) -> Result<NodeId, DomError> {
+        let len = self.text_ref(node_id)?.content.len();
+        let tail = self.delete_text(node_id, offset..len)?;
+        let tail_id = self.create_text(Text::new(tail));
+        if let Some(style) = self.styles.get(node_id.into()).cloned() {
+            self.styles.insert(tail_id.into(), style);
+        }
+        if let Some(parent_id) = self.parent(node_id) {
+            self.insert_after(parent_id, tail_id, node_id)?;
+        }
+        Ok(tail_id)
+    }
+
+    pub fn split_text_chars(&mut self, node_id: NodeId, offset: usize) -> Result<NodeId, DomError> {
+        let text = self.text_ref(node_id)?;
+        let len = text.content.chars().count();
+        let offset = text.byte_offset(offset).ok_or(DomError::TextOffsetOutOfBounds { offset, len })?;
+        self.split_text(node_id, offset)
+    }
+
+    /// Removes empty text nodes from a subtree and merges each run of adjacent text nodes into
+    /// its first node. Neighbours with different inline styles are kept apart.
+    pub fn normalize(&mut self, node_id: NodeId) {
+        let elements: Vec<NodeId> = std::iter::once(node_id).chain(self.descendants(node_id)).filter(|&id| self.is_element(id)).collect();
+        for element_id in elements {
+            let children = self.children(element_id).cloned().unwrap_or_default();
+            let mut run_start: Option<NodeId> = None;
+            for child_id in children {
+                let Some(content) = self.text_ref(child_id).ok().map(|text| text.content.clone()) else {
+                    run_start = None;
+                    continue;
+                };
+                if content.is_empty() {
+                    self.remove_node(child_id);
+                    continue;
+                }
+                match run_start {
+                    Some(start_id) if self.styles.get(start_id.into()) == self.styles.get(child_id.into()) => {
+                        let end = self.text_ref(start_id).map_or(0, |text| text.content.len());
+                        self.insert_text(start_id, end, &content).expect("appending to a text node's end can't fail");
+                        self.remove_node(child_id);
+                    }
+                    _ => run_start = Some(child_id),
+                }
+            }
+        }
+    }
+
+    /// The text of every text node in a subtree, in document order.
+    pub fn text_content(&self, node_id: NodeId) -> String {
+        std::iter::once(node_id).chain(self.descendants(node_id)).filter_map(|id| self.text_ref(id).ok()).map(|text| text.content.as_str()).collect()
+    }
+
+    fn text_ref(&self, node_id: NodeId) -> Result<&Text, DomError> {
+        match self.content.get(node_id.into()) {
+            Some(NodeContent::Text(text)) => Ok(text),
+            Some(NodeContent::Element(_)) => Err(DomError::NotAText(node_id)),
+            None => Err(DomError::NodeNotFound(node_id)),
+        }
+    }
+
+    fn text_mut(&mut self, node_id: NodeId) -> Result<&mut Text, DomError> {
+        match self.content.get_mut(node_id.into()) {
+            Some(NodeContent::Text(text)) => Ok(text),
+            Some(NodeContent::Element(_)) => Err(DomError::NotAText(node_id)),
+            None => Err(DomError::NodeNotFound(node_id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::element::Element;
+    use crate::dom::styleengine::Style;
+
+    /// "héllo wörld" in a text node under the root.
+    fn text_node(dom: &mut Dom) -> NodeId {
+        let node_id = dom.create_text(Text::new("héllo wörld"));
+        dom.append_child(dom.root, node_id).unwrap();
+        node_id
+    }
+
+    fn content(dom: &Dom, node_id: NodeId) -> &str {
+        &dom.text_ref(node_id).unwrap().content
+    }
+
+    #[test]
+    fn edits_count_bytes_or_chars() {
+        let mut dom = Dom::new();
+        let node_id = text_node(&mut dom);
+        // "é" is two bytes long, so the first three bytes are "hé"
+        assert_eq!(dom.replace_text(node_id, 0..3, "Ha"), Ok("hé".to_string()));
+        assert_eq!(dom.delete_text_chars(node_id, 7..8), Ok("ö".to_string()));
+        assert_eq!(content(&dom, node_id), "Hallo wrld");
+        dom.insert_text_chars(node_id, 7, "ö").unwrap();
+        dom.insert_text(node_id, 12, "!").unwrap();
+        assert_eq!(content(&dom, node_id), "Hallo wörld!");
+    }
+
+    #[test]
+    fn refuses_offsets_inside_characters_or_past_the_end() {
+        let mut dom = Dom::new();
+        let node_id = text_node(&mut dom);
+        assert_eq!(dom.replace_text(node_id, 2..4, "e"), Err(DomError::NotACharBoundary(2)));
+        assert_eq!(dom.insert_text(node_id, 14, "!"), Err(DomError::TextOffsetOutOfBounds { offset: 14, len: 13 }));
+        assert_eq!(dom.delete_text(node_id, Range { start: 5, end: 3 }), Err(DomError::TextOffsetOutOfBounds { offset: 5, len: 3 }));
+        assert_eq!(dom.delete_text_chars(node_id, 3..12), Err(DomError::TextOffsetOutOfBounds { offset: 12, len: 11 }));
+        assert_eq!(dom.split_text(node_id, 9), Err(DomError::NotACharBoundary(9)));
+        assert_eq!(dom.insert_text(dom.root, 0, "!"), Err(DomError::NotAText(dom.root)));
+        // Nothing changed on the way
+        assert_eq!(content(&dom, node_id), "héllo wörld");
+    }
+
+    #[test]
+    fn splits_multi_byte_text_after_the_original() {
+        let mut dom = Dom::new();
+        let node_id = text_node(&mut dom);
+        dom.set_style(node_id, Style { color: Some(Color::new(1.0, 0.0, 0.0, 1.0)), ..Style::default() });
+        let tail_id = dom.split_text_chars(node_id, 8).unwrap();
+        assert_eq!((content(&dom, node_id), content(&dom, tail_id)), ("héllo wö", "rld"));
+        assert_eq!(dom.next_sibling(node_id), Some(tail_id));
+        assert_eq!(dom.styles.get(tail_id.into()), dom.styles.get(node_id.into()));
+
+        // Detached text splits into another detached node
+        let detached = dom.create_text(Text::new("ab"));
+        let tail_id = dom.split_text(detached, 1).unwrap();
+        assert_eq!((content(&dom, detached), content(&dom, tail_id), dom.parent(tail_id)), ("a", "b", None));
+    }
+
+    #[test]
+    fn normalize_merges_runs_with_the_same_style() {
+        let mut dom = Dom::new();
+        let div = dom.create_element(Element::new());
+        dom.append_child(dom.root, div).unwrap();
+        let red = Style { color: Some(Color::new(1.0, 0.0, 0.0, 1.0)), ..Style::default() };
+        let mut texts = Vec::new();
+        for (content, style) in [("a", None), ("", None), ("b", None), ("c", Some(&red)), ("d", Some(&red)), ("", Some(&red))] {
+            let node_id = dom.create_text(Text::new(content));
+            if let Some(style) = style {
+                dom.set_style(node_id, style.clone());
+            }
+            dom.append_child(div, node_id).unwrap();
+            texts.push(node_id);
+        }
+        let span = dom.create_element(Element::new());
+        dom.append_child(div, span).unwrap();
+        let after = dom.create_text(Text::new("e"));
+        dom.append_child(div, after).unwrap();
+
+        dom.normalize(dom.root);
+        let children = dom.children(div).cloned().unwrap_or_default();
+        assert_eq!(children, [texts[0], texts[3], span, after]);
+        assert_eq!((content(&dom, texts[0]), content(&dom, texts[3])), ("ab", "cd"));
+        assert_eq!(dom.text_content(dom.root), "abcde");
+    }
+}
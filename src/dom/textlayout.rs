@@ -1,72 +1,230 @@
-use skia_safe::{Font, FontStyle};
+use skia_safe::textlayout::{Paragraph, ParagraphBuilder, ParagraphStyle, StrutStyle, TextAlign as SkTextAlign, TextStyle};
+use skia_safe::{Font, Point};
 
-use crate::dom::{fontmanager::get_thread_local_font_mgr, layoutengine::TextInfo, Dom};
+use crate::dom::{
+    Dom,
+    fontmanager::{font_style, get_thread_local_font_collection, match_typeface, resolve_families},
+    layoutengine::{GlyphRun, TextInfo},
+    styleengine::{ComputedStyle, LineHeight, TextAlign, WhiteSpace},
+};
 
-impl Dom{
-fn calculate_line_height(font: &Font, font_size: f32) -> f32 {
-        font_size * 9.0 / 8.0
+impl Dom {
+    /// The used `line-height` of text in this style.
+    pub(crate) fn calculate_line_height(style: &ComputedStyle) -> f32 {
+        match style.line_height {
+            LineHeight::Normal => style.font_size * 9.0 / 8.0,
+            LineHeight::Number(factor) => style.font_size * factor,
+            LineHeight::Length(length) => length.resolve(style.font_size),
+        }
     }
 
-    pub fn measure_text(content: &str, font_family: String, font_size: f32, max_width: f32) -> (f32, f32, TextInfo) {
-        let font_mgr = get_thread_local_font_mgr();
-        let typeface = font_mgr
-            .match_family_style(font_family, FontStyle::normal())
-            .unwrap_or_else(|| font_mgr.legacy_make_typeface(None, FontStyle::normal()).unwrap());
+    /// Lays a paragraph out in `width`. Text that may not wrap is first measured unbounded, so
+    /// its lines only break where the text does but are still aligned within the width.
+    pub(crate) fn layout_paragraph(paragraph: &mut Paragraph, width: f32, wrap: bool) {
+        if wrap {
+            paragraph.layout(width);
+            return;
+        }
+        paragraph.layout(f32::INFINITY);
+        if width.is_finite() {
+            paragraph.layout(paragraph.max_intrinsic_width().ceil().max(width));
+        }
+    }
 
-        let font = Font::new(typeface, font_size);
-        let line_height = Self::calculate_line_height(&font, font_size);
+    /// Shapes and breaks text into lines no wider than `max_width`, where the white-space mode
+    /// allows wrapping. Shaping applies kerning and ligatures, handles complex scripts and picks
+    /// fallback fonts for characters the family lacks, such as CJK or emoji.
+    pub fn measure_text(content: &str, style: &ComputedStyle, max_width: f32) -> (f32, f32, TextInfo) {
+        let text = Self::prepare_text(content, style.white_space);
+        let wrap = !matches!(style.white_space, WhiteSpace::NoWrap | WhiteSpace::Pre);
+        let mut paragraph = Self::build_paragraph(&text, style);
+        Self::layout_paragraph(&mut paragraph, max_width, wrap);
 
+        let line_metrics = paragraph.get_line_metrics();
         let mut text_info = TextInfo {
-            lines: Vec::new(),
-            line_heights: Vec::new(),
-            line_widths: Vec::new(),
+            lines: Vec::with_capacity(line_metrics.len()),
+            line_heights: Vec::with_capacity(line_metrics.len()),
+            line_widths: Vec::with_capacity(line_metrics.len()),
+            ascent: line_metrics.first().map_or(0.0, |line| line.baseline as f32),
+            runs: Vec::new(),
         };
+        for line in &line_metrics {
+            text_info.lines.push(text.get(line.start_index..line.end_excluding_whitespaces).unwrap_or_default().to_string());
+            text_info.line_heights.push(line.height as f32);
+            text_info.line_widths.push(line.width as f32);
+        }
 
-        let lines: Vec<&str> = content.split('\n').collect();
+        paragraph.visit(|line, info| {
+            // Called without a run at the end of each line
+            let Some(info) = info else {
+                return;
+            };
+            let origin = info.origin();
+            text_info.runs.push(GlyphRun {
+                font: info.font().clone(),
+                glyphs: info.glyphs().to_vec(),
+                positions: info.positions().iter().map(|position| Point::new(origin.x + position.x, origin.y + position.y)).collect(),
+                line,
+            });
+        });
 
-        for line in lines {
-            let (text_width, _) = font.measure_str(line, None);
+        let widest_line = text_info.line_widths.iter().fold(0.0, |max, &w| f32::max(max, w));
+        // Unwrapped text is allowed to overflow its container. Aligned lines are placed across
+        // the whole width, so the text takes all of it.
+        let aligned = !matches!(style.text_align, TextAlign::Left | TextAlign::Start) && max_width.is_finite();
+        let max_line_width = if aligned {
+            widest_line.max(max_width)
+        } else if wrap {
+            widest_line.min(max_width)
+        } else {
+            widest_line
+        };
+        let total_height = text_info.line_heights.iter().sum();
 
-            if text_width <= max_width {
-                text_info.lines.push(line.to_string());
-                text_info.line_heights.push(line_height);
-                text_info.line_widths.push(text_width);
-            } else {
-                let words: Vec<&str> = line.split_whitespace().collect();
-                let space_width = font.measure_str(" ", None).0;
-                let mut current_line = String::new();
-                let mut current_line_width = 0.0;
-
-                for word in words {
-                    let word_width = font.measure_str(word, None).0;
-                    if current_line.is_empty() {
-                        current_line = word.to_string();
-                        current_line_width = word_width;
-                    } else if current_line_width + space_width + word_width <= max_width {
-                        current_line.push(' ');
-                        current_line.push_str(word);
-                        current_line_width += space_width + word_width;
-                    } else {
-                        text_info.lines.push(current_line);
-                        text_info.line_heights.push(line_height);
-                        text_info.line_widths.push(current_line_width);
-
-                        current_line = word.to_string();
-                        current_line_width = word_width;
-                    }
-                }
+        (max_line_width, total_height, text_info)
+    }
+
+    /// The width of the widest piece of text that can't be broken, which is the whole of each
+    /// line when the white-space mode doesn't wrap.
+    pub fn min_text_width(content: &str, style: &ComputedStyle) -> f32 {
+        if matches!(style.white_space, WhiteSpace::NoWrap | WhiteSpace::Pre) {
+            return Self::measure_text(content, style, f32::INFINITY).0;
+        }
+        let mut paragraph = Self::build_paragraph(&Self::prepare_text(content, style.white_space), style);
+        paragraph.layout(f32::INFINITY);
+        paragraph.min_intrinsic_width()
+    }
+
+    /// Applies the white-space mode: collapses runs of spaces and drops newlines that aren't kept.
+    fn prepare_text(content: &str, white_space: WhiteSpace) -> String {
+        let collapse_spaces = matches!(white_space, WhiteSpace::Normal | WhiteSpace::NoWrap | WhiteSpace::PreLine);
+        let preserve_newlines = matches!(white_space, WhiteSpace::Pre | WhiteSpace::PreWrap | WhiteSpace::PreLine);
+
+        let source_lines: Vec<&str> = if preserve_newlines { content.split('\n').collect() } else { vec![content] };
+        let lines: Vec<String> = source_lines
+            .into_iter()
+            .map(|source_line| if collapse_spaces { source_line.split(Self::is_collapsible_space).filter(|word| !word.is_empty()).collect::<Vec<_>>().join(" ") } else { source_line.to_string() })
+            .collect();
+        lines.join("\n")
+    }
+
+    fn build_paragraph(text: &str, style: &ComputedStyle) -> Paragraph {
+        let text_style = Self::text_style(style);
+        let mut builder = ParagraphBuilder::new(&Self::paragraph_style(style), get_thread_local_font_collection());
+        builder.push_style(&text_style);
+        builder.add_text(text);
+        builder.build()
+    }
 
-                if !current_line.is_empty() {
-                    text_info.lines.push(current_line);
-                    text_info.line_heights.push(line_height);
-                    text_info.line_widths.push(current_line_width);
+    /// The font, size and line height text in this style is shaped with.
+    pub(crate) fn text_style(style: &ComputedStyle) -> TextStyle {
+        let font_size = style.font_size;
+        let mut text_style = TextStyle::new();
+        text_style.set_font_families(&resolve_families(&style.font_family, style.font_weight, style.font_style, style.font_stretch));
+        text_style.set_font_size(font_size);
+        text_style.set_font_style(font_style(style.font_weight, style.font_style, style.font_stretch));
+        text_style.set_letter_spacing(style.letter_spacing.resolve(0.0));
+        text_style.set_word_spacing(style.word_spacing.resolve(0.0));
+        // Every line gets the same height, with the leading split evenly above and below the text
+        if font_size > 0.0 {
+            text_style.set_height(Self::calculate_line_height(style) / font_size);
+            text_style.set_height_override(true);
+            text_style.set_half_leading(true);
+        }
+        text_style
+    }
+
+    /// A paragraph in this style. Its lines are never shorter than a line of its own text, even
+    /// when they only hold smaller text or inline boxes.
+    pub(crate) fn paragraph_style(style: &ComputedStyle) -> ParagraphStyle {
+        let mut strut_style = StrutStyle::new();
+        strut_style.set_font_families(&resolve_families(&style.font_family, style.font_weight, style.font_style, style.font_stretch));
+        strut_style.set_font_size(style.font_size);
+        strut_style.set_font_style(font_style(style.font_weight, style.font_style, style.font_stretch));
+        if style.font_size > 0.0 {
+            strut_style.set_height(Self::calculate_line_height(style) / style.font_size);
+            strut_style.set_height_override(true);
+            strut_style.set_half_leading(true);
+        }
+        strut_style.set_strut_enabled(true);
+
+        let mut paragraph_style = ParagraphStyle::new();
+        paragraph_style.set_text_style(&Self::text_style(style));
+        paragraph_style.set_text_align(match style.text_align {
+            TextAlign::Left => SkTextAlign::Left,
+            TextAlign::Right => SkTextAlign::Right,
+            TextAlign::Center => SkTextAlign::Center,
+            TextAlign::Justify => SkTextAlign::Justify,
+            TextAlign::Start => SkTextAlign::Start,
+            TextAlign::End => SkTextAlign::End,
+        });
+        paragraph_style.set_strut_style(strut_style);
+        paragraph_style.set_replace_tab_characters(true);
+        paragraph_style
+    }
+
+    /// How far the font of this style reaches above and below the baseline, which is the height
+    /// of an inline box's content area.
+    pub(crate) fn font_extents(style: &ComputedStyle) -> (f32, f32) {
+        let typeface = resolve_families(&style.font_family, style.font_weight, style.font_style, style.font_stretch)
+            .iter()
+            .find_map(|family| match_typeface(family, style.font_weight, style.font_style, style.font_stretch));
+        match typeface {
+            Some(typeface) => {
+                let (_, metrics) = Font::new(typeface, style.font_size).metrics();
+                (-metrics.ascent, metrics.descent)
+            }
+            None => (style.font_size * 0.8, style.font_size * 0.2),
+        }
+    }
+
+    /// Applies the white-space mode to the text of one node in a run of inline content.
+    /// Collapsible spaces are dropped after a space that ended the previous text, tracked by
+    /// `after_space`, and spaces that may not wrap become no-break spaces.
+    pub(crate) fn collapse_inline_text(content: &str, white_space: WhiteSpace, after_space: &mut bool) -> String {
+        let collapse_spaces = matches!(white_space, WhiteSpace::Normal | WhiteSpace::NoWrap | WhiteSpace::PreLine);
+        let preserve_newlines = matches!(white_space, WhiteSpace::Pre | WhiteSpace::PreWrap | WhiteSpace::PreLine);
+        let space = if matches!(white_space, WhiteSpace::NoWrap | WhiteSpace::Pre) { '\u{a0}' } else { ' ' };
+
+        let mut text = String::with_capacity(content.len());
+        for character in content.chars() {
+            if character == '\n' && preserve_newlines {
+                // Spaces around a kept newline collapse away
+                if collapse_spaces && text.ends_with(space) {
+                    text.pop();
+                }
+                text.push('\n');
+                *after_space = true;
+            } else if Self::is_collapsible_space(character) && collapse_spaces {
+                if !*after_space {
+                    text.push(space);
+                    *after_space = true;
                 }
+            } else {
+                text.push(if character == ' ' { space } else { character });
+                *after_space = false;
             }
         }
+        text
+    }
 
-        let max_line_width = text_info.line_widths.iter().fold(0.0, |max, &w| f32::max(max, w)).min(max_width);
-        let total_height = text_info.line_heights.iter().sum();
+    /// The white space CSS collapses: spaces, tabs and line breaks, but not no-break spaces.
+    pub(crate) fn is_collapsible_space(character: char) -> bool {
+        matches!(character, ' ' | '\t' | '\n' | '\r')
+    }
+}
 
-        (max_line_width, total_height, text_info)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_break_spaces_dont_collapse() {
+        let mut after_space = true;
+        assert_eq!(Dom::collapse_inline_text(" a \u{a0}\u{a0} b\t\n", WhiteSpace::Normal, &mut after_space), "a \u{a0}\u{a0} b ");
+        assert!(after_space);
+        assert_eq!(Dom::collapse_inline_text("\u{a0}", WhiteSpace::Normal, &mut after_space), "\u{a0}");
+        assert!(!after_space);
+        assert_eq!(Dom::prepare_text("  a\u{a0}\u{a0}b  ", WhiteSpace::Normal), "a\u{a0}\u{a0}b");
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,310 @@
+// /src/dom/traversal.rs
+
+use crate::dom::cssparser::CssParseError;
+use crate::dom::dom::{Dom, NodeContent, NodeId};
+use crate::dom::selector::{Selector, parse_selector_list};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Element,
+    Text,
+}
+
+impl NodeContent {
+    pub fn kind(&self) -> NodeKind {
+        match self {
+            NodeContent::Element(_) => NodeKind::Element,
+            NodeContent::Text(_) => NodeKind::Text,
+        }
+    }
+}
+
+/// The ancestors of a node, nearest first.
+pub struct Ancestors<'a> {
+    dom: &'a Dom,
+    current: Option<NodeId>,
+}
+
+impl Iterator for Ancestors<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        self.current = self.dom.parent(self.current?);
+        self.current
+    }
+}
+
+/// How many levels of a walk keep their position among their siblings inline.
+const KEPT_DEPTH: usize = 32;
+
+/// How deep a walk is below its root, and the position of each node on the way down among its
+/// parent's children. Positions are kept for the first `KEPT_DEPTH` levels so climbing back up is
+/// constant time; deeper ones are looked up in the parent's children.
+#[derive(Clone, Copy)]
+struct WalkPath {
+    depth: usize,
+    positions: [usize; KEPT_DEPTH],
+}
+
+impl WalkPath {
+    fn new() -> Self {
+        Self { depth: 0, positions: [0; KEPT_DEPTH] }
+    }
+
+    /// Records where the node the walk just moved to sits among its siblings.
+    fn set_position(&mut self, index: usize) {
+        if let Some(position) = self.positions.get_mut(self.depth - 1) {
+            *position = index;
+        }
+    }
+
+    /// The position among its siblings of `node_id`, the node the walk is at.
+    fn position(&self, dom: &Dom, node_id: NodeId, parent_id: NodeId) -> usize {
+        match self.positions.get(self.depth - 1) {
+            Some(&position) => position,
+            None => dom.child_nodes(parent_id).position(|id| id == node_id).expect("a node is among its parent's children"),
+        }
+    }
+}
+
+/// Where a walk over a node's descendants in tree order is. It borrows the tree only for each step,
+/// so the boxes and styles of nodes can change between steps, as long as the tree's shape doesn't.
+pub(crate) struct DescendantCursor {
+    /// The node returned last, starting at the root, or `None` once the walk is over.
+    current: Option<NodeId>,
+    path: WalkPath,
+}
+
+impl DescendantCursor {
+    pub(crate) fn new(node_id: NodeId) -> Self {
+        Self { current: Some(node_id), path: WalkPath::new() }
+    }
+
+    /// The next descendant in tree order.
+    pub(crate) fn next(&mut self, dom: &Dom) -> Option<NodeId> {
+        self.advance(dom, true)
+    }
+
+    /// The next descendant in tree order that isn't inside the node returned last.
+    pub(crate) fn skip_children(&mut self, dom: &Dom) -> Option<NodeId> {
+        self.advance(dom, false)
+    }
+
+    fn advance(&mut self, dom: &Dom, into_children: bool) -> Option<NodeId> {
+        let mut node_id = self.current?;
+        if into_children && let Some(child_id) = dom.first_child(node_id) {
+            self.path.depth += 1;
+            self.path.set_position(0);
+            self.current = Some(child_id);
+            return self.current;
+        }
+        // Climb until a node has a next sibling, stopping at the root
+        while self.path.depth > 0 {
+            let parent_id = dom.parent(node_id)?;
+            let index = self.path.position(dom, node_id, parent_id) + 1;
+            if let Some(sibling_id) = dom.child_at(parent_id, index) {
+                self.path.set_position(index);
+                self.current = Some(sibling_id);
+                return self.current;
+            }
+            self.path.depth -= 1;
+            node_id = parent_id;
+        }
+        self.current = None;
+        None
+    }
+}
+
+/// The descendants of a node in tree order, each one before its children. Walks from each node to
+/// its first child, next sibling or an ancestor's next sibling without allocating.
+pub struct Descendants<'a> {
+    dom: &'a Dom,
+    cursor: DescendantCursor,
+}
+
+impl Iterator for Descendants<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        self.cursor.next(self.dom)
+    }
+}
+
+/// The descendants of a node with each one after its children, as needed when a parent's
+/// result depends on its children's. Allocates nothing, like `Descendants`.
+pub struct DescendantsPostOrder<'a> {
+    dom: &'a Dom,
+    /// The node returned last, or the root before the walk starts; `None` once it's over.
+    current: Option<NodeId>,
+    started: bool,
+    path: WalkPath,
+}
+
+impl DescendantsPostOrder<'_> {
+    /// Goes down first children from `node_id` to a leaf and returns it, or ends the walk at the root.
+    fn deepest_first(&mut self, mut node_id: NodeId) -> Option<NodeId> {
+        while let Some(child_id) = self.dom.first_child(node_id) {
+            self.path.depth += 1;
+            self.path.set_position(0);
+            node_id = child_id;
+        }
+        self.current = (self.path.depth > 0).then_some(node_id);
+        self.current
+    }
+}
+
+impl Iterator for DescendantsPostOrder<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let node_id = self.current?;
+        if !self.started {
+            self.started = true;
+            return self.deepest_first(node_id);
+        }
+        let parent_id = self.dom.parent(node_id)?;
+        let index = self.path.position(self.dom, node_id, parent_id) + 1;
+        if let Some(sibling_id) = self.dom.child_at(parent_id, index) {
+            self.path.set_position(index);
+            return self.deepest_first(sibling_id);
+        }
+        // The root itself isn't one of its descendants
+        self.path.depth -= 1;
+        self.current = (self.path.depth > 0).then_some(parent_id);
+        self.current
+    }
+}
+
+impl Dom {
+    pub fn node_kind(&self, node_id: NodeId) -> Option<NodeKind> {
+        self.content.get(node_id.into()).map(NodeContent::kind)
+    }
+
+    pub fn child_at(&self, node_id: NodeId, index: usize) -> Option<NodeId> {
+        self.children.get(node_id.into())?.get(index).copied()
+    }
+
+    pub fn first_child(&self, node_id: NodeId) -> Option<NodeId> {
+        self.child_at(node_id, 0)
+    }
+
+    pub fn last_child(&self, node_id: NodeId) -> Option<NodeId> {
+        self.children.get(node_id.into())?.last().copied()
+    }
+
+    pub fn next_sibling(&self, node_id: NodeId) -> Option<NodeId> {
+        self.next_siblings(node_id).next()
+    }
+
+    pub fn previous_sibling(&self, node_id: NodeId) -> Option<NodeId> {
+        self.previous_siblings(node_id).next()
+    }
+
+    pub fn ancestors(&self, node_id: NodeId) -> Ancestors<'_> {
+        Ancestors { dom: self, current: Some(node_id) }
+    }
+
+    /// The children of a node, or nothing for a node that doesn't exist.
+    pub fn child_nodes(&self, node_id: NodeId) -> impl DoubleEndedIterator<Item = NodeId> + '_ {
+        self.children.get(node_id.into()).into_iter().flatten().copied()
+    }
+
+    pub fn children_of_kind(&self, node_id: NodeId, kind: NodeKind) -> impl DoubleEndedIterator<Item = NodeId> + '_ {
+        self.child_nodes(node_id).filter(move |&child_id| self.node_kind(child_id) == Some(kind))
+    }
+
+    /// The siblings after a node, nearest first.
+    pub fn next_siblings(&self, node_id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let (siblings, index) = self.siblings_around(node_id);
+        siblings.get(index + 1..).unwrap_or(&[]).iter().copied()
+    }
+
+    /// The siblings before a node, nearest first.
+    pub fn previous_siblings(&self, node_id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let (siblings, index) = self.siblings_around(node_id);
+        siblings[..index.min(siblings.len())].iter().rev().copied()
+    }
+
+    pub fn descendants(&self, node_id: NodeId) -> Descendants<'_> {
+        Descendants { dom: self, cursor: DescendantCursor::new(node_id) }
+    }
+
+    pub fn descendants_post_order(&self, node_id: NodeId) -> DescendantsPostOrder<'_> {
+        DescendantsPostOrder { dom: self, current: Some(node_id), started: false, path: WalkPath::new() }
+    }
+
+    /// The first element matching a selector list among the descendants of `scope_id`, in tree order.
+    /// Combinators can match ancestors outside the scope, as in the browser's `querySelector`.
+    pub fn query_selector(&self, scope_id: NodeId, selectors: &str) -> Result<Option<NodeId>, CssParseError> {
+        let selectors = parse_selector_list(selectors)?;
+        Ok(self.descendants(scope_id).find(|&node_id| self.matches_any(&selectors, node_id)))
+    }
+
+    /// Every element matching a selector list among the descendants of `scope_id`, in tree order.
+    pub fn query_selector_all(&self, scope_id: NodeId, selectors: &str) -> Result<Vec<NodeId>, CssParseError> {
+        let selectors = parse_selector_list(selectors)?;
+        Ok(self.descendants(scope_id).filter(|&node_id| self.matches_any(&selectors, node_id)).collect())
+    }
+
+    pub fn get_element_by_id(&self, id: &str) -> Option<NodeId> {
+        self.document_nodes().find(|&node_id| self.get_element(node_id).is_some_and(|element| element.id.as_deref() == Some(id)))
+    }
+
+    pub fn get_elements_by_class_name<'a>(&'a self, class: &'a str) -> impl Iterator<Item = NodeId> + 'a {
+        self.document_nodes().filter(move |&node_id| self.get_element(node_id).is_some_and(|element| element.class_list.contains(class)))
+    }
+
+    pub fn get_elements_by_name<'a>(&'a self, name: &'a str) -> impl Iterator<Item = NodeId> + 'a {
+        self.document_nodes().filter(move |&node_id| self.get_element(node_id).is_some_and(|element| element.name.as_deref() == Some(name)))
+    }
+
+    /// The root and all of its descendants, in tree order.
+    fn document_nodes(&self) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::once(self.root).chain(self.descendants(self.root))
+    }
+
+    fn matches_any(&self, selectors: &[Selector], node_id: NodeId) -> bool {
+        selectors.iter().any(|selector| selector.matches(self, node_id))
+    }
+
+    /// A node's parent's children and the node's index among them, or no siblings for a node without a parent.
+    fn siblings_around(&self, node_id: NodeId) -> (&[NodeId], usize) {
+        let siblings = self.parent(node_id).and_then(|parent_id| self.children.get(parent_id.into()));
+        match siblings {
+            Some(siblings) => (siblings, siblings.iter().position(|&id| id == node_id).unwrap_or(siblings.len())),
+            None => (&[], 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(dom: &Dom, nodes: impl Iterator<Item = NodeId>) -> Vec<String> {
+        nodes.map(|node_id| dom.get_element(node_id).and_then(|element| element.name.clone()).unwrap_or_default()).collect()
+    }
+
+    #[test]
+    fn walks_descendants_in_both_orders() {
+        let dom = Dom::from_html("<a><b><c></c><d></d></b><e></e></a><f></f>").unwrap();
+        let a = dom.first_child(dom.root).unwrap();
+        assert_eq!(names(&dom, dom.descendants(dom.root)), ["a", "b", "c", "d", "e", "f"]);
+        assert_eq!(names(&dom, dom.descendants(a)), ["b", "c", "d", "e"]);
+        assert_eq!(names(&dom, dom.descendants_post_order(dom.root)), ["c", "d", "b", "e", "a", "f"]);
+        assert_eq!(names(&dom, dom.descendants_post_order(a)), ["c", "d", "b", "e"]);
+        let f = dom.last_child(dom.root).unwrap();
+        assert_eq!(dom.descendants(f).count() + dom.descendants_post_order(f).count(), 0);
+    }
+
+    #[test]
+    fn walks_below_the_depth_kept_inline() {
+        // A chain deeper than the kept positions, with a sibling after every link
+        let depth = KEPT_DEPTH + 8;
+        let dom = Dom::from_html(&("<a>".repeat(depth) + &"<b></b></a>".repeat(depth))).unwrap();
+        let pre_order = names(&dom, dom.descendants(dom.root));
+        assert_eq!(pre_order, [vec!["a"; depth], vec!["b"; depth]].concat());
+        let post_order = names(&dom, dom.descendants_post_order(dom.root));
+        assert_eq!(post_order, ["b", "a"].repeat(depth));
+    }
+}
@@ -0,0 +1,391 @@
+// /src/dom/vdom.rs
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::dom::dom::{Dom, NodeId};
+use crate::dom::domapi::DomError;
+use crate::dom::element::Element;
+use crate::dom::styleengine::Style;
+
+/// A lightweight description of a subtree, diffed against the previous description to update
+/// the real nodes with as few operations as possible.
+#[derive(Debug, Clone)]
+pub enum VNode {
+    Element(Box<VElement>),
+    Text(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct VElement {
+    pub element: Element,
+    pub style: Style,
+    /// Identifies the element among its siblings, so it keeps its node when the list is reordered.
+    pub key: Option<String>,
+    pub children: Vec<VNode>,
+}
+
+impl VNode {
+    pub fn text(content: impl Into<String>) -> Self {
+        VNode::Text(content.into())
+    }
+
+    fn key(&self) -> Option<&str> {
+        match self {
+            VNode::Element(element) => element.key.as_deref(),
+            VNode::Text(_) => None,
+        }
+    }
+
+    /// Whether a node built for `self` can be updated in place to match `other`.
+    fn same_kind(&self, other: &VNode) -> bool {
+        match (self, other) {
+            (VNode::Element(a), VNode::Element(b)) => a.element.name == b.element.name,
+            (VNode::Text(_), VNode::Text(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl VElement {
+    pub fn new(element: Element) -> Self {
+        Self {
+            element,
+            style: Style::default(),
+            key: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    pub fn child(mut self, child: impl Into<VNode>) -> Self {
+        self.children.push(child.into());
+        self
+    }
+
+    pub fn children(mut self, children: impl IntoIterator<Item = VNode>) -> Self {
+        self.children.extend(children);
+        self
+    }
+}
+
+impl From<VElement> for VNode {
+    fn from(element: VElement) -> Self {
+        VNode::Element(Box::new(element))
+    }
+}
+
+/// One change to the real tree. Indices count the children as they are when the patch is
+/// applied, so patches have to be applied in the order `diff` returns them.
+#[derive(Debug, Clone)]
+pub enum Patch {
+    /// Builds the nodes for `node` and inserts them at `index` among `parent`'s children.
+    Insert {
+        parent: NodeId,
+        index: usize,
+        node: VNode,
+    },
+    Remove {
+        node: NodeId,
+    },
+    /// Moves a child to `index`, counted as if it had already been taken out of its place.
+    Move {
+        parent: NodeId,
+        node: NodeId,
+        index: usize,
+    },
+    SetStyle {
+        node: NodeId,
+        style: Box<Style>,
+    },
+    /// Replaces a text node's content.
+    SetInnerText {
+        node: NodeId,
+        text: String,
+    },
+    SetAttribute {
+        node: NodeId,
+        name: String,
+        value: String,
+    },
+    RemoveAttribute {
+        node: NodeId,
+        name: String,
+    },
+}
+
+/// The children of a container node, kept in sync with a list of `VNode`s. The tree assumes
+/// nothing else adds or removes the container's children.
+#[derive(Debug, Clone)]
+pub struct VTree {
+    pub container: NodeId,
+    children: Vec<VNode>,
+}
+
+impl VTree {
+    pub fn new(container: NodeId) -> Self {
+        Self { container, children: Vec::new() }
+    }
+
+    /// The description the container currently matches.
+    pub fn children(&self) -> &[VNode] {
+        &self.children
+    }
+
+    /// The patches that turn the container's children from the current description into `new_children`.
+    pub fn diff(&self, dom: &Dom, new_children: &[VNode]) -> Vec<Patch> {
+        let mut patches = Vec::new();
+        diff_children(dom, self.container, &self.children, new_children, &mut patches);
+        patches
+    }
+
+    /// Diffs against the current description and applies the patches. Returns how many it took.
+    pub fn update(&mut self, dom: &mut Dom, new_children: Vec<VNode>) -> Result<usize, DomError> {
+        let patches = self.diff(dom, &new_children);
+        let count = patches.len();
+        dom.apply_patches(patches)?;
+        self.children = new_children;
+        Ok(count)
+    }
+}
+
+impl Dom {
+    /// Creates detached nodes for a virtual subtree.
+    pub fn build_vnode(&mut self, vnode: &VNode) -> NodeId {
+        match vnode {
+            VNode::Text(content) => self.create_text_node(content.clone()),
+            VNode::Element(velement) => {
+                let node_id = self.create_element(velement.element.clone());
+                if velement.style != Style::default() {
+                    self.replace_style(node_id, velement.style.clone());
+                }
+                for child in &velement.children {
+                    let child_id = self.build_vnode(child);
+                    self.append_child(node_id, child_id).expect("a new element takes any new node");
+                }
+                node_id
+            }
+        }
+    }
+
+    pub fn apply_patch(&mut self, patch: Patch) -> Result<(), DomError> {
+        match patch {
+            Patch::Insert { parent, index, node } => {
+                let node_id = self.build_vnode(&node);
+                self.move_node(node_id, parent, index)?;
+            }
+            Patch::Remove { node } => self.remove_node(node),
+            Patch::Move { parent, node, index } => self.move_node(node, parent, index)?,
+            Patch::SetStyle { node, style } => self.replace_style(node, *style),
+            Patch::SetInnerText { node, text } => {
+                self.set_text_content(node, text);
+            }
+            Patch::SetAttribute { node, name, value } => self.set_attribute(node, &name, &value),
+            Patch::RemoveAttribute { node, name } => {
+                self.remove_attribute(node, &name);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn apply_patches(&mut self, patches: impl IntoIterator<Item = Patch>) -> Result<(), DomError> {
+        patches.into_iter().try_for_each(|patch| self.apply_patch(patch))
+    }
+}
+
+fn diff_node(dom: &Dom, node_id: NodeId, old: &VNode, new: &VNode, patches: &mut Vec<Patch>) {
+    match (old, new) {
+        (VNode::Text(old_text), VNode::Text(new_text)) if old_text != new_text => {
+            patches.push(Patch::SetInnerText { node: node_id, text: new_text.clone() });
+        }
+        (VNode::Element(old_element), VNode::Element(new_element)) => {
+            diff_attributes(node_id, &old_element.element, &new_element.element, patches);
+            if old_element.style != new_element.style {
+                patches.push(Patch::SetStyle {
+                    node: node_id,
+                    style: Box::new(new_element.style.clone()),
+                });
+            }
+            diff_children(dom, node_id, &old_element.children, &new_element.children, patches);
+        }
+        // Unchanged text; callers only pair nodes of the same kind
+        _ => {}
+    }
+}
+
+fn diff_attributes(node_id: NodeId, old: &Element, new: &Element, patches: &mut Vec<Patch>) {
+    let mut set = |name: &str, value: Option<String>| {
+        patches.push(match value {
+            Some(value) => Patch::SetAttribute { node: node_id, name: name.to_string(), value },
+            None => Patch::RemoveAttribute { node: node_id, name: name.to_string() },
+        });
+    };
+    if old.id != new.id {
+        set("id", new.id.clone());
+    }
+    if old.class_list != new.class_list {
+        set("class", (!new.class_list.is_empty()).then(|| new.class_list.to_string()));
+    }
+    for (name, value) in &new.attributes {
+        if old.attributes.get(name) != Some(value) {
+            set(name, Some(value.clone()));
+        }
+    }
+    for name in old.attributes.keys() {
+        if !new.attributes.contains_key(name) {
+            set(name, None);
+        }
+    }
+}
+
+/// Pairs old and new children by key, or in order for unkeyed ones, removes the old children left
+/// over, then moves and inserts so the children end up in the new order. Children that are
+/// already in order relative to each other (the longest such run) are never moved.
+fn diff_children(dom: &Dom, parent_id: NodeId, old: &[VNode], new: &[VNode], patches: &mut Vec<Patch>) {
+    let old_nodes = dom.children(parent_id).map_or(&[][..], Vec::as_slice);
+    let old = &old[..old.len().min(old_nodes.len())];
+
+    let mut keyed: HashMap<&str, usize> = HashMap::new();
+    let mut unkeyed = VecDeque::new();
+    for (index, vnode) in old.iter().enumerate() {
+        match vnode.key() {
+            Some(key) => {
+                keyed.insert(key, index);
+            }
+            None => unkeyed.push_back(index),
+        }
+    }
+
+    // The old child each new one reuses, if any
+    let mut sources = Vec::with_capacity(new.len());
+    let mut reused = vec![false; old.len()];
+    for vnode in new {
+        let candidate = match vnode.key() {
+            Some(key) => keyed.remove(key),
+            None => unkeyed.pop_front(),
+        };
+        let source = candidate.filter(|&index| old[index].same_kind(vnode));
+        if let Some(index) = source {
+            reused[index] = true;
+        }
+        sources.push(source);
+    }
+
+    for (index, &node_id) in old_nodes.iter().enumerate().take(old.len()) {
+        if !reused[index] {
+            patches.push(Patch::Remove { node: node_id });
+        }
+    }
+    for (vnode, source) in new.iter().zip(&sources) {
+        if let Some(index) = *source {
+            diff_node(dom, old_nodes[index], &old[index], vnode, patches);
+        }
+    }
+
+    // Which new children stay where they are
+    let reused_in_order: Vec<(usize, usize)> = sources.iter().enumerate().filter_map(|(new_index, source)| source.map(|old_index| (new_index, old_index))).collect();
+    let mut stays = vec![false; new.len()];
+    for position in longest_increasing_subsequence(&reused_in_order.iter().map(|&(_, old_index)| old_index).collect::<Vec<_>>()) {
+        stays[reused_in_order[position].0] = true;
+    }
+
+    // The children as they are while patching, by their index in `new`. Every child is put right
+    // after the one that precedes it in `new`, which is always placed by then.
+    let mut current: Vec<usize> = {
+        let mut by_old_index = reused_in_order.clone();
+        by_old_index.sort_by_key(|&(_, old_index)| old_index);
+        by_old_index.into_iter().map(|(new_index, _)| new_index).collect()
+    };
+    for (new_index, vnode) in new.iter().enumerate() {
+        if stays[new_index] {
+            continue;
+        }
+        current.retain(|&placed| placed != new_index);
+        let index = match new_index {
+            0 => 0,
+            _ => current.iter().position(|&placed| placed == new_index - 1).map_or(current.len(), |position| position + 1),
+        };
+        current.insert(index, new_index);
+        patches.push(match sources[new_index] {
+            Some(old_index) => Patch::Move {
+                parent: parent_id,
+                node: old_nodes[old_index],
+                index,
+            },
+            None => Patch::Insert { parent: parent_id, index, node: vnode.clone() },
+        });
+    }
+}
+
+/// Positions in `values` of one of its longest strictly increasing subsequences.
+fn longest_increasing_subsequence(values: &[usize]) -> Vec<usize> {
+    // `tails[k]` is the position of the smallest value ending an increasing run of length k + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous = vec![None; values.len()];
+    for (position, &value) in values.iter().enumerate() {
+        let length = tails.partition_point(|&tail| values[tail] < value);
+        if length > 0 {
+            previous[position] = Some(tails[length - 1]);
+        }
+        if length == tails.len() {
+            tails.push(position);
+        } else {
+            tails[length] = position;
+        }
+    }
+
+    let mut result = Vec::with_capacity(tails.len());
+    let mut position = tails.last().copied();
+    while let Some(current) = position {
+        result.push(current);
+        position = previous[current];
+    }
+    result.reverse();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_longest_increasing_subsequence() {
+        assert_eq!(longest_increasing_subsequence(&[]), Vec::<usize>::new());
+        assert_eq!(longest_increasing_subsequence(&[4]), [0]);
+        assert_eq!(longest_increasing_subsequence(&[0, 1, 2, 3]), [0, 1, 2, 3]);
+        assert_eq!(longest_increasing_subsequence(&[3, 2, 1, 0]).len(), 1);
+        // Equal values don't extend a strictly increasing run
+        assert_eq!(longest_increasing_subsequence(&[1, 1, 1]).len(), 1);
+
+        let values = [3, 0, 4, 1, 5, 2, 6];
+        let positions = longest_increasing_subsequence(&values);
+        assert_eq!(positions.len(), 4);
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1] && values[pair[0]] < values[pair[1]]));
+    }
+
+    #[test]
+    fn reordering_keyed_children_moves_only_what_changed_place() {
+        let mut dom = Dom::new();
+        let root = dom.root;
+        let mut tree = VTree::new(root);
+        let keyed = |keys: &[&str]| keys.iter().map(|&key| VElement::new(Element::new()).key(key).into()).collect::<Vec<VNode>>();
+
+        tree.update(&mut dom, keyed(&["a", "b", "c", "d"])).unwrap();
+        let nodes: Vec<NodeId> = dom.child_nodes(root).collect();
+
+        // Moving the last child to the front keeps the other three where they are
+        assert_eq!(tree.update(&mut dom, keyed(&["d", "a", "b", "c"])).unwrap(), 1);
+        assert_eq!(dom.child_nodes(root).collect::<Vec<_>>(), [nodes[3], nodes[0], nodes[1], nodes[2]]);
+
+        assert_eq!(tree.update(&mut dom, keyed(&["c", "b", "a", "d"])).unwrap(), 3);
+        assert_eq!(dom.child_nodes(root).collect::<Vec<_>>(), [nodes[2], nodes[1], nodes[0], nodes[3]]);
+    }
+}
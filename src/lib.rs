@@ -1,33 +1,581 @@
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, Ident};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    braced,
+    parse::{Parse, ParseStream},
+    parse_macro_input, token, Expr, ExprArray, ExprLit, ExprPath, ExprUnary, Ident, Lit, LitStr, Token, UnOp,
+};
 
+/// Builds a `Dom` subtree declaratively.
+///
+/// The first two arguments are the `&mut Dom` and the parent `NodeId`, followed by
+/// any number of nodes. Elements are written as `name { ... }` and may contain `id` and
+/// `class` attributes, style properties (`padding: [20.0, 20.0, 20.0, 20.0]`), a `border { top { width: 5.0, color: 0.0, 0.0, 1.0, 1.0 } }`
+/// block, string literals as text children, `{ expr }` as dynamic text and nested elements.
+/// Evaluates to a `Vec<NodeId>` of the created top-level nodes.
+///
+/// ```ignore
+/// rsx! {
+///     dom, dom.root,
+///     div {
+///         class: "panel",
+///         padding: [20.0, 20.0, 20.0, 20.0],
+///         bg_color: [0.1, 0.1, 0.1, 1.0],
+///         "inner text",
+///         div { display: inline_block, "nested text" }
+///     }
+/// };
+/// ```
 #[proc_macro]
 pub fn rsx(input: TokenStream) -> TokenStream {
-    // Parse the input as an identifier
-    let ident = parse_macro_input!(input as Ident);
-    let ident_str = ident.to_string();
-    
-    // Convert to PascalCase for the struct name
-    let struct_name = if ident_str == "div" {
-        "Div".to_string()
-    } else {
-        // Handle other tags as needed
-        let mut chars = ident_str.chars();
-        match chars.next() {
-            None => String::new(),
-            Some(f) => f.to_uppercase().collect::<String>() + chars.as_str(),
+    let input = parse_macro_input!(input as RsxInput);
+
+    match input.expand() {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(err) => TokenStream::from(err.to_compile_error()),
+    }
+}
+
+struct RsxInput {
+    dom: Expr,
+    parent: Expr,
+    nodes: Vec<RsxNode>,
+}
+
+enum RsxNode {
+    Text(LitStr),
+    Expr(Expr),
+    Element(RsxElement),
+}
+
+struct RsxElement {
+    name: Ident,
+    items: Vec<RsxItem>,
+}
+
+enum RsxItem {
+    Property(Ident, Expr),
+    Border(Vec<RsxBorderSide>),
+    Child(RsxNode),
+}
+
+struct RsxBorderSide {
+    side: Ident,
+    fields: Vec<(Ident, Vec<Expr>)>,
+}
+
+impl Parse for RsxInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let dom = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let parent = input.parse()?;
+        if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+        }
+
+        let mut nodes = Vec::new();
+        while !input.is_empty() {
+            nodes.push(input.parse()?);
+            if input.peek(Token![,]) {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(Self { dom, parent, nodes })
+    }
+}
+
+impl Parse for RsxNode {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(LitStr) {
+            Ok(RsxNode::Text(input.parse()?))
+        } else if input.peek(token::Brace) {
+            let content;
+            braced!(content in input);
+            Ok(RsxNode::Expr(content.parse()?))
+        } else if input.peek(Ident) && input.peek2(token::Brace) {
+            Ok(RsxNode::Element(input.parse()?))
+        } else {
+            Err(input.error("expected an element (`div { ... }`), a string literal or a `{ expr }` block"))
+        }
+    }
+}
+
+impl Parse for RsxElement {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+        let content;
+        braced!(content in input);
+
+        let mut items = Vec::new();
+        while !content.is_empty() {
+            if content.peek(Ident) && content.peek2(Token![:]) {
+                let property: Ident = content.parse()?;
+                content.parse::<Token![:]>()?;
+                items.push(RsxItem::Property(property, content.parse()?));
+            } else if content.peek(Ident) && content.peek2(token::Brace) && content.fork().parse::<Ident>()? == "border" {
+                content.parse::<Ident>()?;
+                let sides;
+                braced!(sides in content);
+                let mut parsed = Vec::new();
+                while !sides.is_empty() {
+                    parsed.push(sides.parse()?);
+                    if sides.peek(Token![,]) {
+                        sides.parse::<Token![,]>()?;
+                    }
+                }
+                items.push(RsxItem::Border(parsed));
+            } else {
+                items.push(RsxItem::Child(content.parse()?));
+            }
+
+            if content.peek(Token![,]) {
+                content.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(Self { name, items })
+    }
+}
+
+impl Parse for RsxBorderSide {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let side: Ident = input.parse()?;
+        let content;
+        braced!(content in input);
+
+        let mut fields = Vec::new();
+        while !content.is_empty() {
+            let field: Ident = content.parse()?;
+            content.parse::<Token![:]>()?;
+
+            // A field takes every comma separated value up to the next `name:`, so
+            // `color: 0.0, 0.0, 1.0, 1.0` works without brackets.
+            let mut values = vec![content.parse::<Expr>()?];
+            while content.peek(Token![,]) {
+                let fork = content.fork();
+                fork.parse::<Token![,]>()?;
+                if fork.is_empty() || (fork.peek(Ident) && fork.peek2(Token![:])) {
+                    break;
+                }
+                content.parse::<Token![,]>()?;
+                values.push(content.parse()?);
+            }
+            fields.push((field, values));
+
+            if content.peek(Token![,]) {
+                content.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(Self { side, fields })
+    }
+}
+
+impl RsxInput {
+    fn expand(&self) -> syn::Result<TokenStream2> {
+        let dom = &self.dom;
+        let parent = &self.parent;
+        let parent_ident = format_ident!("__rsx_parent");
+
+        let mut counter = 0;
+        let mut body = TokenStream2::new();
+        for node in &self.nodes {
+            let (stmts, id) = node.expand(&parent_ident, &mut counter)?;
+            body.extend(quote! {
+                #stmts
+                __rsx_roots.push(#id);
+            });
+        }
+
+        Ok(quote! {
+            {
+                let #parent_ident: crate::dom::dom::NodeId = #parent;
+                let __rsx_dom: &mut crate::dom::Dom = #dom;
+                let mut __rsx_roots: ::std::vec::Vec<crate::dom::dom::NodeId> = ::std::vec::Vec::new();
+                #body
+                __rsx_roots
+            }
+        })
+    }
+}
+
+impl RsxNode {
+    /// Emits the statements creating this node under `parent` and returns the identifier bound to its `NodeId`.
+    fn expand(&self, parent: &Ident, counter: &mut usize) -> syn::Result<(TokenStream2, Ident)> {
+        let id = format_ident!("__rsx_node_{}", *counter);
+        *counter += 1;
+
+        let stmts = match self {
+            RsxNode::Text(text) => quote! {
+                let #id = __rsx_dom.create_text(crate::dom::text::Text::new(#text));
+                __rsx_dom.append_child(#parent, #id).expect("rsx! nodes are appended to an element");
+            },
+            RsxNode::Expr(expr) => quote! {
+                let #id = __rsx_dom.create_text(crate::dom::text::Text::new(::std::string::ToString::to_string(&(#expr))));
+                __rsx_dom.append_child(#parent, #id).expect("rsx! nodes are appended to an element");
+            },
+            RsxNode::Element(element) => {
+                let name = element.name.to_string();
+                let mut element_setters = TokenStream2::new();
+                let mut style_stmts = TokenStream2::new();
+                let mut child_stmts = TokenStream2::new();
+
+                for item in &element.items {
+                    match item {
+                        RsxItem::Property(property, value) if property == "id" => element_setters.extend(quote! { .with_id(&::std::string::ToString::to_string(&(#value))) }),
+                        RsxItem::Property(property, value) if property == "class" => element_setters.extend(class_tokens(value)),
+                        RsxItem::Property(property, value) => style_stmts.extend(expand_property(property, value)?),
+                        RsxItem::Border(sides) => {
+                            for side in sides {
+                                style_stmts.extend(side.expand()?);
+                            }
+                        }
+                        RsxItem::Child(child) => {
+                            let (stmts, _) = child.expand(&id, counter)?;
+                            child_stmts.extend(stmts);
+                        }
+                    }
+                }
+
+                quote! {
+                    let #id = __rsx_dom.create_element(crate::dom::element::Element::new().with_name(#name)#element_setters);
+                    {
+                        let mut __rsx_style = crate::dom::styleengine::Style::default();
+                        #style_stmts
+                        __rsx_dom.set_style(#id, __rsx_style);
+                    }
+                    __rsx_dom.append_child(#parent, #id).expect("rsx! nodes are appended to an element");
+                    #child_stmts
+                }
+            }
+        };
+
+        Ok((stmts, id))
+    }
+}
+
+impl RsxBorderSide {
+    fn expand(&self) -> syn::Result<TokenStream2> {
+        let mut width = None;
+        let mut color = None;
+        for (field, values) in &self.fields {
+            match field.to_string().as_str() {
+                "width" => width = Some(length_tokens(single_value(field, values)?)),
+                "color" => color = Some(color_tokens(field, values)?),
+                other => return Err(syn::Error::new(field.span(), format!("unknown border field `{}`, expected `width` or `color`", other))),
+            }
+        }
+
+        let (Some(width), Some(color)) = (width, color) else {
+            return Err(syn::Error::new(self.side.span(), "border sides need both `width` and `color`"));
+        };
+
+        let setters = match self.side.to_string().as_str() {
+            "top" => quote! { .top(__rsx_width, __rsx_color) },
+            "right" => quote! { .right(__rsx_width, __rsx_color) },
+            "bottom" => quote! { .bottom(__rsx_width, __rsx_color) },
+            "left" => quote! { .left(__rsx_width, __rsx_color) },
+            "all" => quote! { .top(__rsx_width, __rsx_color).right(__rsx_width, __rsx_color).bottom(__rsx_width, __rsx_color).left(__rsx_width, __rsx_color) },
+            other => return Err(syn::Error::new(self.side.span(), format!("unknown border side `{}`, expected `top`, `right`, `bottom`, `left` or `all`", other))),
+        };
+
+        Ok(quote! {
+            {
+                let __rsx_width = #width;
+                let __rsx_color = #color;
+                __rsx_style.border = Some(__rsx_style.border.take().unwrap_or_default()#setters);
+            }
+        })
+    }
+}
+
+fn expand_property(property: &Ident, value: &Expr) -> syn::Result<TokenStream2> {
+    let styleengine = quote! { crate::dom::styleengine };
+
+    let stmt = match property.to_string().as_str() {
+        "style" => quote! { __rsx_style.apply(&(#value)); },
+        "display" => {
+            let value = enum_tokens(value, "Display");
+            quote! { __rsx_style.display = Some(#value); }
+        }
+        "float" => {
+            let value = enum_tokens(value, "Float");
+            quote! { __rsx_style.float = Some(#value); }
         }
+        "position" => {
+            let value = enum_tokens(value, "Position");
+            quote! { __rsx_style.position = Some(#value); }
+        }
+        "white_space" => {
+            let value = enum_tokens(value, "WhiteSpace");
+            quote! { __rsx_style.white_space = Some(#value); }
+        }
+        "overflow_x" | "overflow_y" => {
+            let field = property;
+            let value = enum_tokens(value, "Overflow");
+            quote! { __rsx_style.#field = Some(#value); }
+        }
+        "overflow" => {
+            let value = enum_tokens(value, "Overflow");
+            quote! {
+                __rsx_style.overflow_x = Some(#value);
+                __rsx_style.overflow_y = Some(#value);
+            }
+        }
+        // A bare number is a multiple of the font size, as in CSS
+        "line_height" => {
+            let value = if is_number(value) {
+                let value = number_tokens(value);
+                quote! { #styleengine::LineHeight::Number(#value) }
+            } else if is_ident(value, "normal") {
+                quote! { #styleengine::LineHeight::Normal }
+            } else {
+                quote! { #styleengine::LineHeight::Length(::std::convert::Into::<crate::dom::length::Length>::into(#value)) }
+            };
+            quote! { __rsx_style.line_height = Some(#value); }
+        }
+        "text_align" => {
+            let value = enum_tokens(value, "TextAlign");
+            quote! { __rsx_style.text_align = Some(#value); }
+        }
+        "text_indent" | "letter_spacing" | "word_spacing" => {
+            let field = property;
+            let value = length_tokens(value);
+            quote! { __rsx_style.#field = Some(::std::convert::Into::<crate::dom::length::Length>::into(#value)); }
+        }
+        // A keyword such as `super` or `text_top`, or a length raising the baseline
+        "vertical_align" => {
+            let value = if is_number(value) {
+                let value = number_tokens(value);
+                quote! { #styleengine::VerticalAlign::Length(crate::dom::length::Length::Px(#value)) }
+            } else {
+                enum_tokens(value, "VerticalAlign")
+            };
+            quote! { __rsx_style.vertical_align = Some(#value); }
+        }
+        "box_sizing" => {
+            let value = enum_tokens(value, "BoxSizing");
+            quote! { __rsx_style.box_sizing = Some(#value); }
+        }
+        "flex_direction" => {
+            let value = enum_tokens(value, "FlexDirection");
+            quote! { __rsx_style.flex_direction = Some(#value); }
+        }
+        "justify_content" => {
+            let value = enum_tokens(value, "JustifyContent");
+            quote! { __rsx_style.justify_content = Some(#value); }
+        }
+        "align_items" => {
+            let value = enum_tokens(value, "AlignItems");
+            quote! { __rsx_style.align_items = Some(#value); }
+        }
+        "align_content" => {
+            let value = enum_tokens(value, "AlignContent");
+            quote! { __rsx_style.align_content = Some(#value); }
+        }
+        "flex_wrap" => {
+            let value = enum_tokens(value, "FlexWrap");
+            quote! { __rsx_style.flex_wrap = Some(#value); }
+        }
+        "align_self" => {
+            let value = enum_tokens(value, "AlignSelf");
+            quote! { __rsx_style.align_self = Some(#value); }
+        }
+        "width" | "height" | "flex_basis" => {
+            let field = property;
+            let value = if is_number(value) {
+                let value = number_tokens(value);
+                quote! { #styleengine::Size::Length(crate::dom::length::Length::Px(#value)) }
+            } else if is_ident(value, "auto") {
+                quote! { #styleengine::Size::Auto }
+            } else {
+                quote! { #value }
+            };
+            quote! { __rsx_style.#field = Some(#value); }
+        }
+        "top" | "right" | "bottom" | "left" => {
+            let field = property;
+            let value = if is_ident(value, "auto") {
+                quote! { #styleengine::Size::Auto }
+            } else {
+                let value = length_tokens(value);
+                quote! { #styleengine::Size::Length(::std::convert::Into::<crate::dom::length::Length>::into(#value)) }
+            };
+            quote! { __rsx_style.position_offsets.get_or_insert_with(::std::default::Default::default).#field = Some(#value); }
+        }
+        "bg_color" | "color" => {
+            let field = property;
+            let value = color_tokens(property, std::slice::from_ref(value))?;
+            quote! { __rsx_style.#field = Some(#value); }
+        }
+        "margin" | "padding" => {
+            let field = property;
+            let value = box_values_tokens(property, value)?;
+            quote! { __rsx_style.#field = Some(#value); }
+        }
+        "flex_grow" | "flex_shrink" => {
+            let field = property;
+            let value = number_tokens(value);
+            quote! { __rsx_style.#field = Some(#value); }
+        }
+        "order" => quote! { __rsx_style.order = Some((#value) as i32); },
+        "row_gap" | "column_gap" => {
+            let field = property;
+            let value = length_tokens(value);
+            quote! { __rsx_style.#field = Some(::std::convert::Into::<crate::dom::length::Length>::into(#value)); }
+        }
+        "gap" => {
+            let value = length_tokens(value);
+            quote! {
+                let __rsx_gap = ::std::convert::Into::<crate::dom::length::Length>::into(#value);
+                __rsx_style.row_gap = Some(__rsx_gap);
+                __rsx_style.column_gap = Some(__rsx_gap);
+            }
+        }
+        "font_size" => {
+            let value = length_tokens(value);
+            quote! { __rsx_style.font.get_or_insert_with(::std::default::Default::default).font_size = Some(::std::convert::Into::<crate::dom::length::Length>::into(#value)); }
+        }
+        // A comma-separated list, as in CSS
+        "font_family" => quote! {
+            __rsx_style.font.get_or_insert_with(::std::default::Default::default).font_family = Some(
+                ::std::string::ToString::to_string(&(#value))
+                    .split(',')
+                    .map(|family| family.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+                    .filter(|family| !family.is_empty())
+                    .collect(),
+            );
+        },
+        "font_weight" => {
+            let value = if is_number(value) {
+                quote! { #styleengine::FontWeight::Absolute((#value) as u16) }
+            } else if is_ident(value, "normal") {
+                quote! { #styleengine::FontWeight::Absolute(#styleengine::FontWeight::NORMAL) }
+            } else if is_ident(value, "bold") {
+                quote! { #styleengine::FontWeight::Absolute(#styleengine::FontWeight::BOLD) }
+            } else {
+                enum_tokens(value, "FontWeight")
+            };
+            quote! { __rsx_style.font.get_or_insert_with(::std::default::Default::default).font_weight = Some(#value); }
+        }
+        "font_style" => {
+            let value = enum_tokens(value, "FontSlant");
+            quote! { __rsx_style.font.get_or_insert_with(::std::default::Default::default).font_style = Some(#value); }
+        }
+        // A percentage of the normal width
+        "font_stretch" => {
+            let value = number_tokens(value);
+            quote! { __rsx_style.font.get_or_insert_with(::std::default::Default::default).font_stretch = Some(#value); }
+        }
+        other => return Err(syn::Error::new(property.span(), format!("unknown style property `{}`", other))),
     };
-    
-    let struct_ident = Ident::new(&struct_name, ident.span());
-    
-    // Generate the code to create an instance of the struct and return ()
-    let expanded = quote! {
-        {
-            let _ = #struct_ident::default();
+
+    Ok(stmt)
+}
+
+/// A string literal is split into one `with_class` call per class name; any other expression is added as a single class.
+fn class_tokens(value: &Expr) -> TokenStream2 {
+    match value {
+        Expr::Lit(ExprLit { lit: Lit::Str(classes), .. }) => {
+            let classes = classes.value();
+            let classes = classes.split_whitespace();
+            quote! { #(.with_class(#classes))* }
         }
+        value => quote! { .with_class(&::std::string::ToString::to_string(&(#value))) },
+    }
+}
+
+fn single_value<'a>(field: &Ident, values: &'a [Expr]) -> syn::Result<&'a Expr> {
+    match values {
+        [value] => Ok(value),
+        _ => Err(syn::Error::new(field.span(), format!("`{}` takes a single value", field))),
+    }
+}
+
+fn number_tokens(value: &Expr) -> TokenStream2 {
+    quote! { ((#value) as f32) }
+}
+
+/// Numbers become `f32` pixels; anything else is expected to convert into a `Length`.
+fn length_tokens(value: &Expr) -> TokenStream2 {
+    if is_number(value) {
+        number_tokens(value)
+    } else {
+        quote! { #value }
+    }
+}
+
+fn is_number(value: &Expr) -> bool {
+    match value {
+        Expr::Lit(ExprLit { lit: Lit::Int(_) | Lit::Float(_), .. }) => true,
+        Expr::Unary(ExprUnary { op: UnOp::Neg(_), expr, .. }) => is_number(expr),
+        _ => false,
+    }
+}
+
+fn is_ident(value: &Expr, name: &str) -> bool {
+    matches!(value, Expr::Path(ExprPath { qself: None, path, .. }) if path.is_ident(name))
+}
+
+/// Turns a bare lowercase identifier such as `inline_block` into `Display::InlineBlock`; anything else is passed through.
+fn enum_tokens(value: &Expr, enum_name: &str) -> TokenStream2 {
+    if let Expr::Path(ExprPath { qself: None, path, .. }) = value
+        && let Some(ident) = path.get_ident()
+        && ident.to_string().starts_with(|c: char| c.is_ascii_lowercase())
+    {
+        let variant: String = ident
+            .to_string()
+            .split('_')
+            .map(|part| {
+                let mut chars = part.chars();
+                match chars.next() {
+                    None => String::new(),
+                    Some(f) => f.to_uppercase().collect::<String>() + chars.as_str(),
+                }
+            })
+            .collect();
+        let enum_ident = format_ident!("{}", enum_name);
+        let variant_ident = Ident::new(&variant, ident.span());
+        return quote! { crate::dom::styleengine::#enum_ident::#variant_ident };
+    }
+    quote! { #value }
+}
+
+/// Accepts `[r, g, b]`, `[r, g, b, a]`, the same list without brackets, or any expression evaluating to a `Color`.
+fn color_tokens(field: &Ident, values: &[Expr]) -> syn::Result<TokenStream2> {
+    let components: Vec<&Expr> = match values {
+        [Expr::Array(ExprArray { elems, .. })] => elems.iter().collect(),
+        [value] => return Ok(quote! { #value }),
+        values => values.iter().collect(),
+    };
+
+    let components: Vec<TokenStream2> = components.into_iter().map(number_tokens).collect();
+    match components.as_slice() {
+        [r, g, b] => Ok(quote! { crate::dom::styleengine::Color::new(#r, #g, #b, 1.0) }),
+        [r, g, b, a] => Ok(quote! { crate::dom::styleengine::Color::new(#r, #g, #b, #a) }),
+        _ => Err(syn::Error::new(field.span(), format!("`{}` expects 3 or 4 color components", field))),
+    }
+}
+
+/// Accepts a single number, `[all]`, `[vertical, horizontal]`, `[top, right, bottom, left]` or any expression evaluating to `BoxModelValues`.
+/// List entries may be numbers (pixels) or `Length`s.
+fn box_values_tokens(field: &Ident, value: &Expr) -> syn::Result<TokenStream2> {
+    let sides: Vec<TokenStream2> = match value {
+        Expr::Array(ExprArray { elems, .. }) => elems.iter().map(length_tokens).collect(),
+        value if is_number(value) => vec![number_tokens(value)],
+        value => return Ok(quote! { #value }),
     };
-    
-    TokenStream::from(expanded)
-}
\ No newline at end of file
+
+    let (top, right, bottom, left) = match sides.as_slice() {
+        [all] => (all, all, all, all),
+        [vertical, horizontal] => (vertical, horizontal, vertical, horizontal),
+        [top, right, bottom, left] => (top, right, bottom, left),
+        _ => return Err(syn::Error::new(field.span(), format!("`{}` expects 1, 2 or 4 values", field))),
+    };
+
+    Ok(quote! {
+        crate::dom::styleengine::BoxModelValues::default().top(#top).right(#right).bottom(#bottom).left(#left)
+    })
+}
@@ -5,7 +5,7 @@ use dom::Dom;
 use dom::debugtools::DebugTools;
 
 use std::num::NonZeroU32;
-use winit::event::{ElementState, Event, KeyEvent, WindowEvent};
+use winit::event::{ElementState, Event, KeyEvent, MouseScrollDelta, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::keyboard::{Key, KeyCode, NamedKey, PhysicalKey};
 
@@ -17,6 +17,9 @@ use crate::renderer::skiarenderer::SkiaRenderer;
 #[path = "utils/winit_app.rs"]
 mod winit_app;
 
+// How far one notch of a line-based mouse wheel scrolls
+const WHEEL_LINE_HEIGHT: f32 = 40.0;
+
 #[cfg(not(target_os = "android"))]
 fn main() {
     let s = String::from("hello");
@@ -35,6 +38,7 @@ pub(crate) fn entry(event_loop: EventLoop<()>) {
         println!("Mouse left node: {:?} at ({}, {})", event.node_id, event.x, event.y);
     });
     let mut dom = Dom::new();
+    let mut cursor_position = (0.0, 0.0);
 
 
     // Create the view (now updated for new DOM structure)
@@ -96,11 +100,27 @@ pub(crate) fn entry(event_loop: EventLoop<()>) {
                 let x = position.x as f32;
                 let y = position.y as f32;
 
+                cursor_position = (x, y);
+
                 // Process mouse movement for event system
                 event_system.process_mouse_move(&dom, x, y);
 
                 window.request_redraw();
             }
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::MouseWheel { delta, .. },
+            } if window_id == window.id() => {
+                // Wheel deltas are positive when scrolling up, which moves the content down
+                let (delta_x, delta_y) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (-x * WHEEL_LINE_HEIGHT, -y * WHEEL_LINE_HEIGHT),
+                    MouseScrollDelta::PixelDelta(position) => (-position.x as f32, -position.y as f32),
+                };
+
+                if event_system.process_mouse_wheel(&mut dom, cursor_position.0, cursor_position.1, delta_x, delta_y) {
+                    window.request_redraw();
+                }
+            }
             Event::WindowEvent {
                 event: WindowEvent::CloseRequested
                 | WindowEvent::KeyboardInput {
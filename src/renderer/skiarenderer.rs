@@ -5,18 +5,18 @@ use crate::dom::{
     debugtools::DebugTools,
     dom::{Dom, NodeContent, NodeId},
     events::EventSystem,
-    fontmanager::get_thread_local_font_mgr,
-    layoutengine::{LayoutData, TextInfo},
+    inline::InlineFragment,
+    layoutengine::{EdgeSizes, LayoutData, Rect as LayoutRect, TextInfo},
+    scroll::Clipping,
     styleengine::{BorderStyle, ComputedStyle},
-    text::Text,
 };
-use skia_safe::{AlphaType, Canvas, Color, Color4f, ColorType, Font, FontMgr, FontStyle, ImageInfo, Paint, PaintStyle, Path, Point, Rect, surfaces};
+use skia_safe::{AlphaType, Canvas, Color, Color4f, ColorType, ImageInfo, Paint, PaintStyle, Path, Point, Rect, TextBlobBuilder, surfaces};
 
 pub struct SkiaRenderer;
 
 impl SkiaRenderer {
     pub fn draw_dom(canvas: &Canvas, dom: &Dom) {
-            Self::render_node(canvas, dom, dom.root);
+            Self::render_node(canvas, dom, dom.root, &Clipping::default());
     }
 
     pub fn render(dom: &mut Dom, buffer: &mut [u32], width: usize, height: usize, debug_tools: Option<&mut DebugTools>, event_system: Option<&mut EventSystem>) {
@@ -57,50 +57,104 @@ impl SkiaRenderer {
     }
                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                               
     
-    fn render_node(canvas: &Canvas, dom: &Dom, node_id: NodeId) {
+    fn render_node(canvas: &Canvas, dom: &Dom, node_id: NodeId, clipping: &Clipping<Option<LayoutRect>>) {
         let key: slotmap::DefaultKey = node_id.into();
 
         let content = dom.content.get(key);
         let layout_data = dom.layout.get(key);
 
+        // Clip to the scroll containers this node is inside, which positioned descendants
+        // can escape even where their ancestors are clipped by them
+        let (clip, children_clipping) = dom.clip_inside(node_id, clipping);
+        canvas.save();
+        if let Some(clip) = clip {
+            canvas.clip_rect(Rect::from_xywh(clip.x, clip.y, clip.width, clip.height), None, None);
+        }
+
         if let (Some(content), Some(layout_data)) = (content, layout_data) {
             match content {
                 NodeContent::Element(_) => {
                     if let Some(computed_style) = dom.computed_styles.get(key) {
-                        Self::draw_element(canvas, computed_style, *layout_data);
+                        Self::draw_element(canvas, computed_style, *layout_data, dom.inline_fragments.get(key).map(Vec::as_slice));
                     }
                 }
-                NodeContent::Text(text) => {
-                    let parent_id = dom.parent(node_id).unwrap();
-                    if let Some(computed_style) = dom.get_computed_style(parent_id){
-                        if let Some(text_info) = dom.text_info.get(key) {
-                            Self::draw_text(canvas, text, &computed_style, *layout_data, text_info);
-                        }
+                NodeContent::Text(_) => {
+                    if let (Some(computed_style), Some(text_info)) = (dom.computed_styles.get(key), dom.text_info.get(key)) {
+                        Self::draw_text(canvas, computed_style, *layout_data, text_info);
                     }
                 }
             }
         }
+        canvas.restore();
 
-        // Collect child IDs using our new children method
-        if let Some(child_ids) = dom.children(node_id) {
-            for &child_id in child_ids {
-                Self::render_node(canvas, dom, child_id);
+        // Positioned children paint over their in-flow siblings
+        for child_id in dom.children_in_paint_order(node_id) {
+            Self::render_node(canvas, dom, child_id, &children_clipping);
+        }
+
+        // Scrollbars go over the content they scroll
+        if dom.is_scroll_container(node_id) {
+            canvas.save();
+            if let Some(clip) = clip {
+                canvas.clip_rect(Rect::from_xywh(clip.x, clip.y, clip.width, clip.height), None, None);
             }
+            Self::draw_scrollbars(canvas, dom, node_id);
+            canvas.restore();
+        }
+    }
+
+    // Draws a faint track with a rounded thumb for each scrollbar the node shows.
+    fn draw_scrollbars(canvas: &Canvas, dom: &Dom, node_id: NodeId) {
+        let (horizontal, vertical) = dom.scrollbars(node_id);
+
+        let mut track_paint = Paint::new(Color4f::new(1.0, 1.0, 1.0, 0.05), None);
+        track_paint.set_style(PaintStyle::Fill);
+        let mut thumb_paint = Paint::new(Color4f::new(0.6, 0.6, 0.6, 0.6), None);
+        thumb_paint.set_style(PaintStyle::Fill);
+        thumb_paint.set_anti_alias(true);
+
+        for scrollbar in [horizontal, vertical].into_iter().flatten() {
+            let track = scrollbar.track;
+            canvas.draw_rect(Rect::from_xywh(track.x, track.y, track.width, track.height), &track_paint);
+
+            // Inset the thumb a little so it floats inside the track
+            let thumb = scrollbar.thumb;
+            let thumb_rect = Rect::from_xywh(thumb.x + 1.0, thumb.y + 1.0, (thumb.width - 2.0).max(0.0), (thumb.height - 2.0).max(0.0));
+            let radius = thumb_rect.width().min(thumb_rect.height()) / 2.0;
+            canvas.draw_round_rect(thumb_rect, radius, radius, &thumb_paint);
+        }
+    }
+
+    // Draws the border and background of an element, once per line for an inline element
+    // broken over several; the start and end edges go on its first and last fragments.
+    fn draw_element(canvas: &Canvas, style: &ComputedStyle, layout_data: LayoutData, fragments: Option<&[InlineFragment]>) {
+        let widths = style.border.widths();
+        let Some(fragments) = fragments else {
+            let bounds = Rect::from_xywh(layout_data.computed_x, layout_data.computed_y, layout_data.actual_width, layout_data.actual_height);
+            Self::draw_box(canvas, style, bounds, widths);
+            return;
+        };
+        for fragment in fragments {
+            let bounds = Rect::from_xywh(layout_data.computed_x + fragment.rect.x, layout_data.computed_y + fragment.rect.y, fragment.rect.width, fragment.rect.height);
+            let mut fragment_widths = widths;
+            if !fragment.first {
+                fragment_widths.left = 0.0;
+            }
+            if !fragment.last {
+                fragment_widths.right = 0.0;
+            }
+            Self::draw_box(canvas, style, bounds, fragment_widths);
         }
     }
 
     // Draws the border and then the background inset within it.
-    fn draw_element(canvas: &Canvas, style: &ComputedStyle, layout_data: LayoutData) {
-        let x = layout_data.computed_x;
-        let y = layout_data.computed_y;
-        let width = layout_data.actual_width;
-        let height = layout_data.actual_height;
-
-        // Extract border widths
-        let top_width = style.border.top.map(|b| b.width).unwrap_or(0.0);
-        let right_width = style.border.right.map(|b| b.width).unwrap_or(0.0);
-        let bottom_width = style.border.bottom.map(|b| b.width).unwrap_or(0.0);
-        let left_width = style.border.left.map(|b| b.width).unwrap_or(0.0);
+    fn draw_box(canvas: &Canvas, style: &ComputedStyle, bounds: Rect, widths: EdgeSizes) {
+        let x = bounds.left;
+        let y = bounds.top;
+        let width = bounds.width();
+        let height = bounds.height();
+
+        let (top_width, right_width, bottom_width, left_width) = (widths.top, widths.right, widths.bottom, widths.left);
 
         // Draw borders with proper mitered corners
         if top_width > 0.0 {
@@ -169,8 +223,8 @@ impl SkiaRenderer {
         }
     }
 
-    // Draws the element's box first, then positions the text inside the padding area.
-    fn draw_text(canvas: &Canvas, text: &Text, style: &ComputedStyle, layout_data: LayoutData, text_info: &TextInfo) {
+    // Draws the debug box the text occupies, then paints its shaped glyph runs.
+    fn draw_text(canvas: &Canvas, style: &ComputedStyle, layout_data: LayoutData, text_info: &TextInfo) {
         // Draw debug rect to see what text is supposed to occupy
         let border_rect = Rect::from_xywh(layout_data.computed_x, layout_data.computed_y, layout_data.actual_width, layout_data.actual_height);
         let border_color = Color4f::new(0.0, 1.0, 0.0, 0.2);
@@ -178,33 +232,20 @@ impl SkiaRenderer {
         border_paint.set_style(PaintStyle::Fill);
          canvas.draw_rect(border_rect, &border_paint);
 
-        // Now, draw the text content itself, positioned inside the padding area.
-        let content_x = layout_data.computed_x;
-        let mut current_y = layout_data.computed_y;
-
         // Set up text paint using the resolved `color` property
-        // let text_color = Color4f::new(style.color.r, style.color.g, style.color.b, style.color.a);
-        let text_color = Color4f::new(1.0, 1.0, 1.0, 1.0);
+        let text_color: Color4f = style.color.into();
         let mut paint = Paint::new(text_color, None);
         paint.set_style(PaintStyle::Fill);
 
-        let font_mgr = get_thread_local_font_mgr();
-        let typeface = font_mgr
-            .match_family_style(style.font_family.as_str(), FontStyle::normal())
-            .unwrap_or_else(|| font_mgr.legacy_make_typeface(None, FontStyle::normal()).expect("Failed to create fallback typeface"));
-
-        let font = Font::new(typeface, style.font_size);
-        let (_, metrics) = font.metrics();
-
-        // Draw each line of text
-        for (i, line) in text_info.lines.iter().enumerate() {
-            let line_height = text_info.line_heights[i];
-            // The baseline for drawing text is adjusted by the font's ascent metric.
-            let baseline_y = current_y - metrics.ascent;
-
-            canvas.draw_str(line, Point::new(content_x, baseline_y), &font, &paint);
-
-            current_y += line_height;
+        // Glyph positions are relative to the text's top-left corner, baselines included
+        let mut builder = TextBlobBuilder::new();
+        for run in &text_info.runs {
+            let (glyphs, positions) = builder.alloc_run_pos(&run.font, run.glyphs.len(), None);
+            glyphs.copy_from_slice(&run.glyphs);
+            positions.copy_from_slice(&run.positions);
+        }
+        if let Some(blob) = builder.make() {
+            canvas.draw_text_blob(&blob, Point::new(layout_data.computed_x, layout_data.computed_y), &paint);
         }
     }
 }
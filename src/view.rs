@@ -6,22 +6,12 @@ use crate::{
         Dom,
         dom::NodeId,
         element::Element,
-        styleengine::{BorderStyle, BoxModelValues, BoxSizing, Color, Display, Style, Float, Font},
+        styleengine::{BorderStyle, BoxModelValues, BoxSizing, Color, Style, Float, Font},
         text::Text,
     },
 };
 
-struct Div;
-
-impl Div {
-    fn default() -> Self {
-        println!("Creating a default Div instance");
-        Div
-    }
-}
-
 pub fn create_view(dom: &mut Dom) {
-    rsx!{div}
     let style = Style {
         bg_color: Some(Color::new(0.1, 0.1, 0.1, 1.0)),
         padding: Some(BoxModelValues::all(20.0)),
@@ -32,23 +22,21 @@ pub fn create_view(dom: &mut Dom) {
         .left(5.0, Color::YELLOW)),
         ..Default::default()
     };
-    
-    let text = dom.create_text(Text::new("aaaaa aaaaa aaaaa aaaaa aaaaa aaaaa"));
-
-    let inlinetest1 = dom.append_new_element(dom.root, Element::new());
-    let inlinetest2 = dom.append_new_element(dom.root, Element::new());
-    let blocktest1 = dom.append_new_element(dom.root, Element::new());
-    let blocktest2 = dom.append_new_element(dom.root, Element::new());
 
-    dom.append_child(inlinetest1, text);
-
-    dom.set_display(inlinetest1, Display::InlineBlock);
-    dom.set_display(inlinetest2, Display::InlineBlock);
-    dom.set_style(inlinetest1, style.clone());
-    dom.set_style(inlinetest2, style.clone());
-
-    dom.set_style(blocktest1, style.clone());
-    dom.set_style(blocktest2, style.clone());
+    rsx! {
+        dom, dom.root,
+        div {
+            style: style,
+            display: inline_block,
+            "aaaaa aaaaa aaaaa aaaaa aaaaa aaaaa"
+        },
+        div {
+            style: style,
+            display: inline_block,
+        },
+        div { style: style },
+        div { style: style },
+    };
 
     test_float(dom);
 }
@@ -65,8 +53,8 @@ let style = Style {
     };
 
     let text = dom.create_text(Text { content: "aaaaa aaaaa aaaaa aaaaa aaaaa aaaaa".to_owned()});
-    let floatleft1 = dom.append_new_styled_element(dom.root, Element::new(), &style);
-    let floatleft2 = dom.append_new_styled_element(dom.root, Element::new(), &style);
+    let floatleft1 = dom.append_new_styled_element(dom.root, Element::new(), &style).unwrap();
+    let floatleft2 = dom.append_new_styled_element(dom.root, Element::new(), &style).unwrap();
     // let floatright1 = dom.append_new_styled_element(dom.root, Element::new(), &style);
     // let floatright2 = dom.append_new_styled_element(dom.root, Element::new(), &style);
     dom.set_float(floatleft1, Float::Left);
@@ -74,31 +62,8 @@ let style = Style {
     // dom.set_float(floatright1, Float::Right);
     // dom.set_float(floatright2, Float::Right);
     // dom.append_child(floatleft1, text.clone());
-    dom.append_child(floatleft2, text);
+    dom.append_child(floatleft2, text).unwrap();
     // dom.append_child(floatright1, text);
     // dom.append_child(floatright2, text);
 
 }
-
-// pub fn create_view(dom: &mut Dom, dom.root: NodeId) {
-//     rsx! {
-//         dom,
-//         dom.root,
-//         div {
-//             border {
-//                 top { width: 5.0, color: 0.0, 0.0, 1.0, 1.0 },
-//                 right { width: 5.0, color: 1.0, 0.0, 0.0, 1.0 },
-//                 bottom { width: 5.0, color: 0.0, 1.0, 0.0, 1.0 },
-//                 left { width: 5.0, color: 1.0, 1.0, 0.0, 1.0 }
-//             },
-//             padding: [20.0, 20.0, 20.0, 20.0],
-//             bg_color: [0.1, 0.1, 0.1, 1.0],
-//             "inner text",
-//             div {
-//                 // Nested element
-//                 bg_color: [0.2, 0.2, 0.2, 1.0],
-//                 "nested text"
-//             }
-//         }
-//     };
-// }